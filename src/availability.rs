@@ -0,0 +1,132 @@
+use crate::Player;
+use anyhow::{anyhow, Result};
+use std::collections::HashMap;
+
+// When a player can play, read from "available=1-2" (rounds) or "available=19:00-20:30" (times) in the roster.
+// Several windows are separated by commas, a missing end means until the end of the session.
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Availability {
+    // First and last round, counted from 1
+    Rounds(usize, Option<usize>),
+    // Start and end in minutes after midnight
+    Time(u32, Option<u32>),
+}
+
+// Times of a session, needed for availability given as times
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Session {
+    // Start of the first round in minutes after midnight
+    pub start: Option<u32>,
+    // Length of a round in minutes
+    pub round_length: u32,
+}
+
+// "19:30" in minutes after midnight
+pub fn parse_time(time: &str) -> Result<u32> {
+    let (hours, minutes) = time
+        .split_once(':')
+        .ok_or_else(|| anyhow!("Time without minutes: {}", time))?;
+    let hours: u32 = hours.trim().parse()?;
+    let minutes: u32 = minutes.trim().parse()?;
+    if hours > 23 || minutes > 59 {
+        return Err(anyhow!("Invalid time: {}", time));
+    }
+    Ok(hours * 60 + minutes)
+}
+
+impl Availability {
+    pub fn parse(window: &str) -> Result<Availability> {
+        let window = window.trim();
+        let (start, end) = match window.split_once('-') {
+            Some((start, end)) => (start.trim(), end.trim()),
+            None => (window, window),
+        };
+
+        if window.contains(':') {
+            let start = if start.is_empty() {
+                0
+            } else {
+                parse_time(start)?
+            };
+            let end = if end.is_empty() {
+                None
+            } else {
+                Some(parse_time(end)?)
+            };
+            // A session doesn't run past midnight, so a window like 23:00-01:00 is a mistake too
+            if end.map(|end| end < start).unwrap_or(false) {
+                return Err(anyhow!("The window ends before it starts: {}", window));
+            }
+            Ok(Availability::Time(start, end))
+        } else {
+            let start: usize = if start.is_empty() { 1 } else { start.parse()? };
+            let end: Option<usize> = if end.is_empty() {
+                None
+            } else {
+                Some(end.parse()?)
+            };
+            if start == 0 {
+                return Err(anyhow!("Rounds are counted from 1: {}", window));
+            }
+            if end.map(|end| end < start).unwrap_or(false) {
+                return Err(anyhow!("The window ends before it starts: {}", window));
+            }
+            Ok(Availability::Rounds(start, end))
+        }
+    }
+
+    pub fn covers(&self, round: usize, session: &Session) -> bool {
+        match *self {
+            Availability::Rounds(start, end) => {
+                start <= round && end.map(|end| round <= end).unwrap_or(true)
+            }
+            Availability::Time(start, end) => match session.start {
+                Some(session_start) => {
+                    let round_start = session_start + (round as u32 - 1) * session.round_length;
+                    let round_end = round_start + session.round_length;
+                    start <= round_start && end.map(|end| round_end <= end).unwrap_or(true)
+                }
+                // Without the session times every round counts as covered
+                None => true,
+            },
+        }
+    }
+}
+
+pub fn is_available(player: &Player, round: usize, session: &Session) -> bool {
+    player.availability.is_empty()
+        || player
+            .availability
+            .iter()
+            .any(|window| window.covers(round, session))
+}
+
+// The players that can play in the round
+pub fn players_for_round(
+    players: &HashMap<u8, Player>,
+    round: usize,
+    session: &Session,
+) -> HashMap<u8, Player> {
+    players
+        .iter()
+        .filter(|(_, player)| is_available(player, round, session))
+        .map(|(id, player)| (*id, player.clone()))
+        .collect()
+}
+
+// Times can only be turned into rounds if the session start is known
+pub fn check_session(players: &HashMap<u8, Player>, session: &Session) -> Result<()> {
+    let uses_times = players.values().any(|player| {
+        player
+            .availability
+            .iter()
+            .any(|window| matches!(window, Availability::Time(_, _)))
+    });
+    if uses_times && session.start.is_none() {
+        return Err(anyhow!(
+            "The roster has availability times, the session start is needed (--session-start 19:00)"
+        ));
+    }
+    Ok(())
+}
@@ -0,0 +1,547 @@
+use crate::context::ScrimContext;
+use crate::options::Options;
+use crate::template::ScrimRenderer;
+use crate::{balance, scrim_id, Player, Position, Roster, SearchProgress};
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::{HashMap, VecDeque};
+use std::io::{BufRead, Lines, Write};
+use std::time::Duration;
+
+// Runs scrims from a chat: players sign up by reacting to a message with their roles, !balance
+// generates the scrims, !announce posts the chosen one and !move puts the teams into their voice
+// channels. The chat service is behind the Gateway trait. JsonGateway is the transport that comes
+// with it: the events come in as JSON lines and the bot answers with the actions for the chat, so a
+// small Discord client in any language can relay between the two, leaving out the events of the
+// bot itself.
+
+pub const TANK_EMOJI: &str = "🛡";
+pub const DAMAGE_EMOJI: &str = "⚔";
+pub const SUPPORT_EMOJI: &str = "💉";
+
+// How long !balance searches if neither the config nor the chat gives a time limit
+pub const DEFAULT_TIME_LIMIT: Duration = Duration::from_secs(30);
+
+// What the bot does in the chat, users are given by their display name
+pub trait Gateway {
+    // Returns the id of the new message
+    fn send_message(&mut self, channel: &str, text: &str) -> Result<String>;
+    fn add_reaction(&mut self, channel: &str, message_id: &str, emoji: &str) -> Result<()>;
+    fn move_to_voice_channel(&mut self, user: &str, voice_channel: &str) -> Result<()>;
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum BotEvent {
+    Message {
+        channel: String,
+        user: String,
+        text: String,
+    },
+    ReactionAdded {
+        message_id: String,
+        user: String,
+        emoji: String,
+    },
+    ReactionRemoved {
+        message_id: String,
+        user: String,
+        emoji: String,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BotConfig {
+    // Voice channel of every team, in the order of the teams
+    pub team_channels: Vec<String>,
+    // Where !lobby moves everyone back to
+    pub lobby_channel: Option<String>,
+    // Users allowed to open signups, balance, announce and move players, everyone if empty
+    pub organisers: Vec<String>,
+    // Command line options every search starts with, like ["--time-limit", "30"]
+    pub balance_args: Vec<String>,
+}
+
+// A player that signed up and the roles they want to play
+#[derive(Debug, Clone, PartialEq)]
+pub struct Signup {
+    pub user: String,
+    pub tank: bool,
+    pub damage: bool,
+    pub support: bool,
+}
+
+impl Signup {
+    fn plays(&self, position: Position) -> bool {
+        match position {
+            Position::Tank => self.tank,
+            Position::Damage => self.damage,
+            Position::Support => self.support,
+        }
+    }
+
+    fn set(&mut self, position: Position, plays: bool) {
+        match position {
+            Position::Tank => self.tank = plays,
+            Position::Damage => self.damage = plays,
+            Position::Support => self.support = plays,
+        }
+    }
+
+    fn roles_string(&self) -> String {
+        [Position::Tank, Position::Damage, Position::Support]
+            .iter()
+            .filter(|position| self.plays(**position))
+            .map(|position| position.label())
+            .collect::<Vec<&str>>()
+            .join(", ")
+    }
+}
+
+pub struct Bot {
+    pub config: BotConfig,
+    // Everyone the bot knows the SR of, by name
+    pub players: HashMap<String, Player>,
+    // Message the signups react to
+    pub signup_message: Option<String>,
+    // In the order players signed up
+    pub signups: Vec<Signup>,
+    pub scrims: Vec<ScrimContext>,
+    pub announced: Option<ScrimContext>,
+}
+
+fn position_of(emoji: &str) -> Option<Position> {
+    // Clients add a variation selector to some emoji
+    match emoji.trim_end_matches('\u{fe0f}') {
+        TANK_EMOJI => Some(Position::Tank),
+        DAMAGE_EMOJI => Some(Position::Damage),
+        SUPPORT_EMOJI => Some(Position::Support),
+        _ => None,
+    }
+}
+
+impl Bot {
+    pub fn new(roster: &Roster, config: BotConfig) -> Bot {
+        Bot {
+            config,
+            players: roster
+                .players
+                .values()
+                .map(|player| (player.name.clone(), player.clone()))
+                .collect(),
+            signup_message: None,
+            signups: Vec::new(),
+            scrims: Vec::new(),
+            announced: None,
+        }
+    }
+
+    pub fn handle(&mut self, event: &BotEvent, gateway: &mut impl Gateway) -> Result<()> {
+        match event {
+            BotEvent::Message {
+                channel,
+                user,
+                text,
+            } => self.handle_message(channel, user, text, gateway),
+            BotEvent::ReactionAdded {
+                message_id,
+                user,
+                emoji,
+            } => {
+                self.react(message_id, user, emoji, true);
+                Ok(())
+            }
+            BotEvent::ReactionRemoved {
+                message_id,
+                user,
+                emoji,
+            } => {
+                self.react(message_id, user, emoji, false);
+                Ok(())
+            }
+        }
+    }
+
+    fn handle_message(
+        &mut self,
+        channel: &str,
+        user: &str,
+        text: &str,
+        gateway: &mut impl Gateway,
+    ) -> Result<()> {
+        let mut words = text.split_whitespace();
+        let command = match words.next() {
+            Some(command) if command.starts_with('!') => command,
+            _ => return Ok(()),
+        };
+        let args: Vec<&str> = words.collect();
+        let is_organiser =
+            self.config.organisers.is_empty() || self.config.organisers.iter().any(|o| o == user);
+
+        let reply = match command {
+            "!register" => self.register(user, &args),
+            "!signups" => Ok(self.signups_string()),
+            "!signup" | "!balance" | "!announce" | "!move" | "!lobby" if !is_organiser => {
+                Err(anyhow!("Only organisers can use {}", command))
+            }
+            "!signup" => return self.open_signup(channel, gateway),
+            "!balance" => self.balance(&args),
+            "!announce" => self.announce(args.first().copied()),
+            "!move" => self.move_teams(gateway),
+            "!lobby" => self.move_to_lobby(gateway),
+            _ => return Ok(()),
+        };
+        let text = reply.unwrap_or_else(|error| error.to_string());
+        gateway.send_message(channel, &text)?;
+        Ok(())
+    }
+
+    fn open_signup(&mut self, channel: &str, gateway: &mut impl Gateway) -> Result<()> {
+        let message_id = gateway.send_message(
+            channel,
+            &format!(
+                "Signups are open, react with {} for tank, {} for damage and {} for support",
+                TANK_EMOJI, DAMAGE_EMOJI, SUPPORT_EMOJI
+            ),
+        )?;
+        // The signups are open even if a reaction can't be added, players can still add it themselves
+        self.signup_message = Some(message_id.clone());
+        self.signups.clear();
+        self.scrims.clear();
+        self.announced = None;
+        for emoji in &[TANK_EMOJI, DAMAGE_EMOJI, SUPPORT_EMOJI] {
+            gateway.add_reaction(channel, &message_id, emoji)?;
+        }
+        Ok(())
+    }
+
+    // A reaction picks a role, taking back the last one takes back the signup
+    fn react(&mut self, message_id: &str, user: &str, emoji: &str, added: bool) {
+        if self.signup_message.as_deref() != Some(message_id) {
+            return;
+        }
+        let position = match position_of(emoji) {
+            Some(position) => position,
+            None => return,
+        };
+        match self.signups.iter().position(|signup| signup.user == user) {
+            Some(i) => {
+                self.signups[i].set(position, added);
+                let signup = &self.signups[i];
+                if !signup.tank && !signup.damage && !signup.support {
+                    self.signups.remove(i);
+                }
+            }
+            None if added => {
+                let mut signup = Signup {
+                    user: user.to_string(),
+                    tank: false,
+                    damage: false,
+                    support: false,
+                };
+                signup.set(position, true);
+                self.signups.push(signup);
+            }
+            None => {}
+        }
+    }
+
+    // !register <tank SR> <damage SR> <support SR>
+    fn register(&mut self, user: &str, args: &[&str]) -> Result<String> {
+        let usage = || anyhow!("Register with !register <tank SR> <damage SR> <support SR>");
+        if args.len() != 3 || user.contains(" - ") {
+            return Err(usage());
+        }
+        let mut sr = [0i16; 3];
+        for (i, arg) in args.iter().enumerate() {
+            sr[i] = arg.parse().map_err(|_| usage())?;
+        }
+        // The roles come from the signup, the roster ones only matter for players that don't sign up
+        let player = Player::new(format!(
+            "{} - {} - {} - {} - tds",
+            user, sr[0], sr[1], sr[2]
+        ))?;
+        self.players.insert(user.to_string(), player);
+        Ok(format!(
+            "Registered {} with {} tank, {} damage and {} support SR",
+            user, sr[0], sr[1], sr[2]
+        ))
+    }
+
+    fn signups_string(&self) -> String {
+        if self.signups.is_empty() {
+            return "Nobody signed up yet".to_string();
+        }
+        let mut text = format!("{} players signed up:", self.signups.len());
+        for signup in &self.signups {
+            let unknown = if self.players.contains_key(&signup.user) {
+                ""
+            } else {
+                ", not registered"
+            };
+            text.push_str(&format!(
+                "\n{} ({}{})",
+                signup.user,
+                signup.roles_string(),
+                unknown
+            ));
+        }
+        text
+    }
+
+    // The registered players that signed up, with the roles they picked
+    pub fn signed_up_roster(&self) -> Result<Roster> {
+        let mut players = HashMap::new();
+        for signup in &self.signups {
+            if let Some(player) = self.players.get(&signup.user) {
+                let mut player = player.clone();
+                player.plays_tank = signup.tank;
+                player.plays_damage = signup.damage;
+                player.plays_support = signup.support;
+                players.insert(Roster::next_id(&players)?, player);
+            }
+        }
+        Ok(Roster { players })
+    }
+
+    // !balance [options], the options are added to the ones of the config. Only the options of the
+    // search can be given in the chat, the others could read files on the host.
+    fn balance(&mut self, args: &[&str]) -> Result<String> {
+        let unknown: Vec<&str> = self
+            .signups
+            .iter()
+            .filter(|signup| !self.players.contains_key(&signup.user))
+            .map(|signup| signup.user.as_str())
+            .collect();
+        if !unknown.is_empty() {
+            return Err(anyhow!(
+                "Not registered yet: {}. Register with !register <tank SR> <damage SR> <support SR>",
+                unknown.join(", ")
+            ));
+        }
+
+        let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+        Options::check_search_args(&args)?;
+        let mut options = self.config.balance_args.clone();
+        options.extend(args);
+        let mut config = Options::parse(options)?.balance_config();
+        config.time_limit = config.time_limit.or(Some(DEFAULT_TIME_LIMIT));
+        let result = balance(
+            &self.signed_up_roster()?,
+            &config,
+            &SearchProgress::silent(),
+        )
+        .map_err(|error| anyhow!("Could not balance the signups: {}", error))?;
+        self.scrims = result.scrims;
+        self.announced = None;
+
+        let mut text = format!("Found {} scrims:", self.scrims.len());
+        for scrim in self.scrims.iter().take(3) {
+            let teams: Vec<String> = scrim
+                .teams
+                .iter()
+                .map(|team| format!("{} ({:.0})", team.name, team.average_sr))
+                .collect();
+            text.push_str(&format!(
+                "\n{} {} rating {}: {}",
+                scrim.label,
+                scrim.id,
+                scrim.rating,
+                teams.join(" vs ")
+            ));
+        }
+        text.push_str("\nAnnounce one with !announce <scrim>");
+        Ok(text)
+    }
+
+    // !announce [scrim], the best scrim if none is given
+    fn announce(&mut self, query: Option<&str>) -> Result<String> {
+        let scrim = match query {
+            None => self.scrims.first(),
+            Some(query) => scrim_id::find_scrim(&self.scrims, query),
+        }
+        .ok_or_else(|| match query {
+            None => anyhow!("There are no scrims yet, generate them with !balance"),
+            Some(query) => anyhow!("There is no scrim {}", query),
+        })?
+        .clone();
+
+        let text = format!("```\n{}```", ScrimRenderer::new(None)?.render(&scrim)?);
+        self.announced = Some(scrim);
+        Ok(text)
+    }
+
+    fn announced(&self) -> Result<&ScrimContext> {
+        self.announced
+            .as_ref()
+            .ok_or_else(|| anyhow!("No scrim was announced yet, announce one with !announce"))
+    }
+
+    // Every team into its voice channel
+    fn move_teams(&self, gateway: &mut impl Gateway) -> Result<String> {
+        let scrim = self.announced()?;
+        if scrim.teams.len() > self.config.team_channels.len() {
+            return Err(anyhow!(
+                "There are {} teams but only {} team voice channels",
+                scrim.teams.len(),
+                self.config.team_channels.len()
+            ));
+        }
+        for (team, channel) in scrim.teams.iter().zip(&self.config.team_channels) {
+            for player in &team.players {
+                gateway.move_to_voice_channel(&player.name, channel)?;
+            }
+        }
+        Ok(format!(
+            "Moved the teams of scrim {} to their channels",
+            scrim.label
+        ))
+    }
+
+    fn move_to_lobby(&self, gateway: &mut impl Gateway) -> Result<String> {
+        let scrim = self.announced()?;
+        let lobby = self
+            .config
+            .lobby_channel
+            .as_ref()
+            .ok_or_else(|| anyhow!("There is no lobby channel"))?;
+        for team in &scrim.teams {
+            for player in &team.players {
+                gateway.move_to_voice_channel(&player.name, lobby)?;
+            }
+        }
+        Ok("Moved everyone to the lobby".to_string())
+    }
+}
+
+// A gateway over JSON lines. Every action is written as a line with an id like
+// {"id": 1, "action": "send_message", "channel": "...", "text": "..."} and answered by a line of the
+// input that replies to it, {"reply_to": 1, "message_id": "..."} for a new message,
+// {"reply_to": 1} when it was done or {"reply_to": 1, "error": "..."}. Events that come in while
+// the bot waits for an answer are kept for later, answers to no action that is waited for are dropped.
+pub struct JsonGateway<R: BufRead, W: Write> {
+    lines: Lines<R>,
+    output: W,
+    next_id: u64,
+    // Events read while waiting for an answer, in the order they came in
+    events: VecDeque<String>,
+}
+
+impl<R: BufRead, W: Write> JsonGateway<R, W> {
+    pub fn new(input: R, output: W) -> JsonGateway<R, W> {
+        JsonGateway {
+            lines: input.lines(),
+            output,
+            next_id: 1,
+            events: VecDeque::new(),
+        }
+    }
+
+    // The next event, the ones kept while waiting for an answer first
+    fn next_event(&mut self) -> Result<Option<String>> {
+        match self.events.pop_front() {
+            Some(event) => Ok(Some(event)),
+            None => self.next_line(),
+        }
+    }
+
+    // The next line of the input that isn't empty
+    fn next_line(&mut self) -> Result<Option<String>> {
+        for line in &mut self.lines {
+            let line = line?;
+            if !line.trim().is_empty() {
+                return Ok(Some(line));
+            }
+        }
+        Ok(None)
+    }
+
+    fn write(&mut self, line: &Value) -> Result<()> {
+        writeln!(self.output, "{}", line)?;
+        self.output.flush()?;
+        Ok(())
+    }
+
+    fn request(&mut self, mut action: Value) -> Result<Value> {
+        let id = self.next_id;
+        self.next_id += 1;
+        action["id"] = json!(id);
+        self.write(&action)?;
+        loop {
+            let line = self.next_line()?.ok_or_else(|| {
+                anyhow!("The input ended before {} was answered", action["action"])
+            })?;
+            // Everything that isn't an answer is an event, invalid ones are reported by run()
+            let reply = match serde_json::from_str::<Value>(&line) {
+                Ok(reply) if reply.get("reply_to").is_some() => reply,
+                _ => {
+                    self.events.push_back(line);
+                    continue;
+                }
+            };
+            if reply["reply_to"] != json!(id) {
+                continue;
+            }
+            return match reply["error"].as_str() {
+                Some(error) => Err(anyhow!("{}", error)),
+                None => Ok(reply),
+            };
+        }
+    }
+}
+
+impl<R: BufRead, W: Write> Gateway for JsonGateway<R, W> {
+    fn send_message(&mut self, channel: &str, text: &str) -> Result<String> {
+        let reply = self.request(json!({
+            "action": "send_message",
+            "channel": channel,
+            "text": text,
+        }))?;
+        reply["message_id"]
+            .as_str()
+            .map(|message_id| message_id.to_string())
+            .ok_or_else(|| anyhow!("The answer to send_message has no message_id"))
+    }
+
+    fn add_reaction(&mut self, channel: &str, message_id: &str, emoji: &str) -> Result<()> {
+        self.request(json!({
+            "action": "add_reaction",
+            "channel": channel,
+            "message_id": message_id,
+            "emoji": emoji,
+        }))?;
+        Ok(())
+    }
+
+    fn move_to_voice_channel(&mut self, user: &str, voice_channel: &str) -> Result<()> {
+        self.request(json!({
+            "action": "move_to_voice_channel",
+            "user": user,
+            "voice_channel": voice_channel,
+        }))?;
+        Ok(())
+    }
+}
+
+// Runs the bot on the events of the input until it ends. Events that can't be read or handled are
+// reported with an {"error": "..."} line and the bot goes on with the next one.
+pub fn run(
+    roster: &Roster,
+    config: BotConfig,
+    input: impl BufRead,
+    output: impl Write,
+) -> Result<()> {
+    let mut bot = Bot::new(roster, config);
+    let mut gateway = JsonGateway::new(input, output);
+    while let Some(line) = gateway.next_event()? {
+        let handled = serde_json::from_str::<BotEvent>(&line)
+            .map_err(|error| anyhow!("Invalid event {}: {}", line, error))
+            .and_then(|event| bot.handle(&event, &mut gateway));
+        if let Err(error) = handled {
+            gateway.write(&json!({ "error": error.to_string() }))?;
+        }
+    }
+    Ok(())
+}
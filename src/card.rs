@@ -0,0 +1,265 @@
+use crate::context::ScrimContext;
+use anyhow::Result;
+use embedded_graphics::mono_font::iso_8859_1::{FONT_10X20, FONT_6X13, FONT_9X18, FONT_9X18_BOLD};
+use embedded_graphics::mono_font::{MonoFont, MonoTextStyle};
+use embedded_graphics::pixelcolor::Rgb888;
+use embedded_graphics::prelude::*;
+use embedded_graphics::primitives::{
+    Circle, PrimitiveStyle, Rectangle, RoundedRectangle, Triangle,
+};
+use embedded_graphics::text::{Alignment, Baseline, Text, TextStyleBuilder};
+use std::convert::Infallible;
+
+// Lineup cards are drawn with the bitmap fonts bundled in embedded-graphics,
+// so no system fonts are needed to render them.
+
+const MARGIN: i32 = 20;
+const COLUMN_WIDTH: i32 = 280;
+const HEADER_HEIGHT: i32 = 64;
+const TEAM_HEADER_HEIGHT: i32 = 52;
+const ROW_HEIGHT: i32 = 28;
+const FOOTER_HEIGHT: i32 = 36;
+const MAX_NAME_LENGTH: usize = 20;
+
+const BACKGROUND: Rgb888 = Rgb888::new(29, 31, 36);
+const PANEL: Rgb888 = Rgb888::new(42, 45, 52);
+const TEXT: Rgb888 = Rgb888::new(230, 230, 230);
+const MUTED: Rgb888 = Rgb888::new(169, 173, 182);
+const TANK: Rgb888 = Rgb888::new(79, 143, 214);
+const DAMAGE: Rgb888 = Rgb888::new(214, 84, 79);
+const SUPPORT: Rgb888 = Rgb888::new(95, 191, 106);
+
+// RGB pixel buffer that embedded-graphics draws into
+struct Canvas {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+impl Canvas {
+    fn new(width: u32, height: u32) -> Canvas {
+        Canvas {
+            width,
+            height,
+            pixels: vec![0; (width * height * 3) as usize],
+        }
+    }
+
+    fn to_png(&self) -> Result<Vec<u8>> {
+        let mut png_bytes: Vec<u8> = Vec::new();
+        {
+            let mut encoder = png::Encoder::new(&mut png_bytes, self.width, self.height);
+            encoder.set_color(png::ColorType::Rgb);
+            encoder.set_depth(png::BitDepth::Eight);
+            let mut writer = encoder.write_header()?;
+            writer.write_image_data(&self.pixels)?;
+        }
+        Ok(png_bytes)
+    }
+}
+
+impl OriginDimensions for Canvas {
+    fn size(&self) -> Size {
+        Size::new(self.width, self.height)
+    }
+}
+
+impl DrawTarget for Canvas {
+    type Color = Rgb888;
+    type Error = Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        for Pixel(point, color) in pixels {
+            if point.x < 0
+                || point.y < 0
+                || point.x >= self.width as i32
+                || point.y >= self.height as i32
+            {
+                continue;
+            }
+            let index = ((point.y as u32 * self.width + point.x as u32) * 3) as usize;
+            self.pixels[index] = color.r();
+            self.pixels[index + 1] = color.g();
+            self.pixels[index + 2] = color.b();
+        }
+        Ok(())
+    }
+}
+
+fn draw_text(
+    canvas: &mut Canvas,
+    text: &str,
+    position: Point,
+    font: &MonoFont,
+    color: Rgb888,
+    alignment: Alignment,
+) {
+    let character_style = MonoTextStyle::new(font, color);
+    let text_style = TextStyleBuilder::new()
+        .alignment(alignment)
+        .baseline(Baseline::Top)
+        .build();
+    Text::with_text_style(text, position, character_style, text_style)
+        .draw(canvas)
+        .unwrap();
+}
+
+// Tanks are drawn as squares, DPS as triangles and supports as circles
+fn draw_role_icon(canvas: &mut Canvas, role: &str, top_left: Point) {
+    match role {
+        "Tank" => Rectangle::new(top_left, Size::new(14, 14))
+            .into_styled(PrimitiveStyle::with_fill(TANK))
+            .draw(canvas)
+            .unwrap(),
+        "Damage" => Triangle::new(
+            top_left + Point::new(7, 0),
+            top_left + Point::new(0, 14),
+            top_left + Point::new(14, 14),
+        )
+        .into_styled(PrimitiveStyle::with_fill(DAMAGE))
+        .draw(canvas)
+        .unwrap(),
+        _ => Circle::new(top_left, 15)
+            .into_styled(PrimitiveStyle::with_fill(SUPPORT))
+            .draw(canvas)
+            .unwrap(),
+    }
+}
+
+fn shorten(name: &str) -> String {
+    if name.chars().count() > MAX_NAME_LENGTH {
+        let mut short: String = name.chars().take(MAX_NAME_LENGTH - 1).collect();
+        short.push('~');
+        short
+    } else {
+        name.to_string()
+    }
+}
+
+// Renders one scrim as a PNG lineup card
+pub fn render_card(context: &ScrimContext) -> Result<Vec<u8>> {
+    let number_of_teams = context.teams.len() as i32;
+    let number_of_rows: i32 = context
+        .roles
+        .iter()
+        .map(|role| role.rows.len() as i32)
+        .sum();
+
+    let width = 2 * MARGIN + number_of_teams * COLUMN_WIDTH;
+    let height = 2 * MARGIN
+        + HEADER_HEIGHT
+        + TEAM_HEADER_HEIGHT
+        + number_of_rows * ROW_HEIGHT
+        + FOOTER_HEIGHT;
+
+    let mut canvas = Canvas::new(width as u32, height as u32);
+    canvas.clear(BACKGROUND).unwrap();
+
+    draw_text(
+        &mut canvas,
+        &format!("SCRIM {} ({})", context.label, context.id),
+        Point::new(MARGIN, MARGIN),
+        &FONT_10X20,
+        TEXT,
+        Alignment::Left,
+    );
+    draw_text(
+        &mut canvas,
+        &format!(
+            "Average SR: {:.2}   Rating: {}",
+            context.sr_average, context.rating
+        ),
+        Point::new(MARGIN, MARGIN + 28),
+        &FONT_6X13,
+        MUTED,
+        Alignment::Left,
+    );
+
+    let panel_top = MARGIN + HEADER_HEIGHT;
+    for (i, team) in context.teams.iter().enumerate() {
+        let left = MARGIN + i as i32 * COLUMN_WIDTH;
+
+        RoundedRectangle::with_equal_corners(
+            Rectangle::new(
+                Point::new(left + 4, panel_top),
+                Size::new(
+                    (COLUMN_WIDTH - 8) as u32,
+                    (TEAM_HEADER_HEIGHT + number_of_rows * ROW_HEIGHT + 8) as u32,
+                ),
+            ),
+            Size::new(8, 8),
+        )
+        .into_styled(PrimitiveStyle::with_fill(PANEL))
+        .draw(&mut canvas)
+        .unwrap();
+
+        draw_text(
+            &mut canvas,
+            &shorten(&team.name),
+            Point::new(left + 16, panel_top + 10),
+            &FONT_9X18_BOLD,
+            TEXT,
+            Alignment::Left,
+        );
+        draw_text(
+            &mut canvas,
+            &format!("Average SR: {:.2}", team.average_sr),
+            Point::new(left + 16, panel_top + 32),
+            &FONT_6X13,
+            MUTED,
+            Alignment::Left,
+        );
+
+        let mut row_top = panel_top + TEAM_HEADER_HEIGHT;
+        for role in &context.roles {
+            for row in &role.rows {
+                if let Some(player) = row.get(i) {
+                    draw_role_icon(
+                        &mut canvas,
+                        &player.role,
+                        Point::new(left + 16, row_top + 2),
+                    );
+                    draw_text(
+                        &mut canvas,
+                        &match &player.sub_role {
+                            Some(sub_role) => format!("{} [{}]", shorten(&player.name), sub_role),
+                            None => shorten(&player.name),
+                        },
+                        Point::new(left + 40, row_top),
+                        &FONT_9X18,
+                        TEXT,
+                        Alignment::Left,
+                    );
+                    draw_text(
+                        &mut canvas,
+                        &player.sr.to_string(),
+                        Point::new(left + COLUMN_WIDTH - 16, row_top),
+                        &FONT_9X18,
+                        MUTED,
+                        Alignment::Right,
+                    );
+                }
+                row_top += ROW_HEIGHT;
+            }
+        }
+    }
+
+    let bench = if context.bench.is_empty() {
+        "No players are left out".to_string()
+    } else {
+        format!("Leftover players: {}", context.bench.join(", "))
+    };
+    draw_text(
+        &mut canvas,
+        &bench,
+        Point::new(MARGIN, height - MARGIN - FOOTER_HEIGHT + 16),
+        &FONT_6X13,
+        MUTED,
+        Alignment::Left,
+    );
+
+    canvas.to_png()
+}
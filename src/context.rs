@@ -0,0 +1,236 @@
+use crate::player::duo_sub_roles;
+use crate::Player;
+use crate::Position;
+use serde::Serialize;
+
+// Structured view of a single scrim that is handed to the output templates.
+// Both Matchup and OW2Matchup can be turned into a ScrimContext.
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ScrimContext {
+    pub label: String,
+    pub id: String,
+    pub title: String,
+    pub sr_average: f32,
+    pub rating: i16,
+    pub teams: Vec<TeamContext>,
+    pub roles: Vec<RoleContext>,
+    pub bench: Vec<String>,
+    pub sum_of_average_differences: f32,
+    pub sum_of_deviation_differences: f32,
+    pub schedule: Vec<RoundContext>,
+    pub hero_conflicts: Vec<String>,
+    pub sub_role_clashes: Vec<String>,
+    pub language_conflicts: Vec<String>,
+    // Players put on a role they don't play by hand, the search never does that
+    pub off_role_warnings: Vec<String>,
+    // Whether the roster lists languages, only then the comms languages of the teams are shown
+    pub has_languages: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TeamContext {
+    pub name: String,
+    pub average_sr: f32,
+    pub players: Vec<PlayerContext>,
+    pub roles: Vec<TeamRoleContext>,
+    // Languages every player of the team speaks
+    pub comms: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TeamRoleContext {
+    pub role: String,
+    pub label: String,
+    pub average_sr: f32,
+    pub average_difference: f32,
+    pub deviation: f32,
+    pub deviation_difference: f32,
+    pub players: Vec<PlayerContext>,
+}
+
+// One role over all teams, the rows hold one player per team for every slot of the role
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RoleContext {
+    pub role: String,
+    pub label: String,
+    pub average_sr: f32,
+    pub average_deviation: f32,
+    pub rows: Vec<Vec<PlayerContext>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PlayerContext {
+    pub name: String,
+    pub role: String,
+    pub sr: i16,
+    pub sub_role: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RoundContext {
+    pub round: usize,
+    pub matches: Vec<MatchContext>,
+    pub bye: Option<String>,
+    pub maps: Vec<MapContext>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MapContext {
+    pub mode: String,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MatchContext {
+    pub home: String,
+    pub away: String,
+    pub sr_difference: f32,
+}
+
+impl PlayerContext {
+    pub fn new(player: &Player, position: Position) -> PlayerContext {
+        PlayerContext {
+            name: player.name.clone(),
+            role: format!("{:?}", position),
+            sr: player.get_sr(position),
+            sub_role: None,
+        }
+    }
+}
+
+impl TeamRoleContext {
+    pub fn new(
+        position: Position,
+        players: Vec<&Player>,
+        average_sr: f32,
+        deviation: f32,
+        full_role_average: f32,
+        average_deviation: f32,
+    ) -> TeamRoleContext {
+        let (sub_roles, _) = duo_sub_roles(&players, position);
+        TeamRoleContext {
+            role: format!("{:?}", position),
+            label: position.label().to_string(),
+            average_sr,
+            average_difference: (average_sr - full_role_average).abs(),
+            deviation,
+            deviation_difference: (deviation - average_deviation).abs(),
+            players: players
+                .into_iter()
+                .zip(sub_roles)
+                .map(|(player, sub_role)| PlayerContext {
+                    sub_role: sub_role.map(|sub_role| sub_role.label().to_string()),
+                    ..PlayerContext::new(player, position)
+                })
+                .collect(),
+        }
+    }
+}
+
+impl TeamContext {
+    pub fn new(name: String, average_sr: f32, roles: Vec<TeamRoleContext>) -> TeamContext {
+        let players = roles
+            .iter()
+            .flat_map(|role| role.players.iter().cloned())
+            .collect();
+        TeamContext {
+            name,
+            average_sr,
+            players,
+            roles,
+            comms: String::new(),
+        }
+    }
+}
+
+impl ScrimContext {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        label: &str,
+        id: &str,
+        title: &str,
+        sr_average: f32,
+        rating: i16,
+        full_role_average: [f32; 3],
+        average_deviations: [f32; 3],
+        teams: Vec<TeamContext>,
+        bench: Vec<String>,
+    ) -> ScrimContext {
+        let position_vec = vec![Position::Tank, Position::Damage, Position::Support];
+
+        let mut roles: Vec<RoleContext> = Vec::new();
+        let mut sum_of_average_differences: f32 = 0.0;
+        let mut sum_of_deviation_differences: f32 = 0.0;
+        for position in position_vec {
+            for team in &teams {
+                sum_of_average_differences += team.roles[position as usize].average_difference;
+                sum_of_deviation_differences += team.roles[position as usize].deviation_difference;
+            }
+
+            let number_of_rows = teams
+                .iter()
+                .map(|team| team.roles[position as usize].players.len())
+                .max()
+                .unwrap_or(0);
+
+            let mut rows: Vec<Vec<PlayerContext>> = Vec::new();
+            for row in 0..number_of_rows {
+                rows.push(
+                    teams
+                        .iter()
+                        .filter_map(|team| team.roles[position as usize].players.get(row))
+                        .cloned()
+                        .collect(),
+                );
+            }
+
+            roles.push(RoleContext {
+                role: format!("{:?}", position),
+                label: position.label().to_string(),
+                average_sr: full_role_average[position as usize],
+                average_deviation: average_deviations[position as usize],
+                rows,
+            });
+        }
+
+        ScrimContext {
+            label: label.to_string(),
+            id: id.to_string(),
+            title: title.to_string(),
+            sr_average,
+            rating,
+            teams,
+            roles,
+            bench,
+            sum_of_average_differences,
+            sum_of_deviation_differences,
+            schedule: Vec::new(),
+            hero_conflicts: Vec::new(),
+            sub_role_clashes: Vec::new(),
+            language_conflicts: Vec::new(),
+            off_role_warnings: Vec::new(),
+            has_languages: false,
+        }
+    }
+}
+
+impl ScrimContext {
+    // Renames a team everywhere it appears in the scrim
+    pub fn rename_team(&mut self, index: usize, name: &str) {
+        let old_prefix = format!("{}: ", self.teams[index].name);
+        let new_prefix = format!("{}: ", name);
+        for message in self
+            .hero_conflicts
+            .iter_mut()
+            .chain(self.sub_role_clashes.iter_mut())
+            .chain(self.language_conflicts.iter_mut())
+            .chain(self.off_role_warnings.iter_mut())
+        {
+            if let Some(rest) = message.strip_prefix(&old_prefix) {
+                *message = format!("{}{}", new_prefix, rest);
+            }
+        }
+        self.teams[index].name = name.to_string();
+    }
+}
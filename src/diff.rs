@@ -0,0 +1,258 @@
+use crate::context::{ScrimContext, TeamContext};
+use serde::Serialize;
+
+// Differences between two lineups of the same roster,
+// e.g. two of the top candidates or a scrim before and after a rebalance.
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ScrimDiff {
+    pub from_id: String,
+    pub to_id: String,
+    pub from_rating: i16,
+    pub to_rating: i16,
+    pub rating_delta: i16,
+    pub moved_players: Vec<PlayerChange>,
+    pub role_changes: Vec<PlayerChange>,
+    pub teams: Vec<TeamDiff>,
+}
+
+// A team or role of None means the player is on the bench
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PlayerChange {
+    pub name: String,
+    pub from_team: Option<String>,
+    pub to_team: Option<String>,
+    pub from_role: Option<String>,
+    pub to_role: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TeamDiff {
+    pub from_team: String,
+    pub to_team: String,
+    pub from_average_sr: f32,
+    pub to_average_sr: f32,
+    pub average_sr_delta: f32,
+    pub roles: Vec<RoleDiff>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RoleDiff {
+    pub role: String,
+    pub from_average_sr: f32,
+    pub to_average_sr: f32,
+    pub average_sr_delta: f32,
+}
+
+// Team and role of a player, None if the player is on the bench
+fn find_player(scrim: &ScrimContext, name: &str) -> (Option<String>, Option<String>) {
+    for team in &scrim.teams {
+        for player in &team.players {
+            if player.name == name {
+                return (Some(team.name.clone()), Some(player.role.clone()));
+            }
+        }
+    }
+    (None, None)
+}
+
+fn shared_players(team: &TeamContext, other: &TeamContext) -> usize {
+    team.players
+        .iter()
+        .filter(|player| other.players.iter().any(|p| p.name == player.name))
+        .count()
+}
+
+// Pairs every team of the first scrim with a team of the second one.
+// Teams keep their names in most cases, otherwise the teams sharing the most players are paired.
+fn pair_teams(from: &ScrimContext, to: &ScrimContext) -> Vec<(usize, usize)> {
+    let mut pairs: Vec<(usize, usize)> = Vec::new();
+
+    let same_names = from.teams.len() == to.teams.len()
+        && from
+            .teams
+            .iter()
+            .all(|team| to.teams.iter().any(|other| other.name == team.name));
+    if same_names {
+        for (i, team) in from.teams.iter().enumerate() {
+            let j = to
+                .teams
+                .iter()
+                .position(|other| other.name == team.name)
+                .unwrap();
+            pairs.push((i, j));
+        }
+        return pairs;
+    }
+
+    let mut candidates: Vec<(usize, usize, usize)> = Vec::new();
+    for (i, team) in from.teams.iter().enumerate() {
+        for (j, other) in to.teams.iter().enumerate() {
+            candidates.push((shared_players(team, other), i, j));
+        }
+    }
+    candidates.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)).then(a.2.cmp(&b.2)));
+
+    for (_, i, j) in candidates {
+        if pairs.iter().all(|(a, b)| *a != i && *b != j) {
+            pairs.push((i, j));
+        }
+    }
+    pairs.sort();
+    pairs
+}
+
+pub fn diff_scrims(from: &ScrimContext, to: &ScrimContext) -> ScrimDiff {
+    let pairs = pair_teams(from, to);
+
+    // The team of the second scrim that continues a team of the first one
+    let paired_name = |team: &Option<String>| -> Option<String> {
+        team.as_ref().map(|name| {
+            let i = from.teams.iter().position(|t| &t.name == name).unwrap();
+            match pairs.iter().find(|(a, _)| *a == i) {
+                Some((_, j)) => to.teams[*j].name.clone(),
+                None => name.clone(),
+            }
+        })
+    };
+
+    let mut names: Vec<String> = Vec::new();
+    for scrim in &[from, to] {
+        for team in &scrim.teams {
+            for player in &team.players {
+                if !names.contains(&player.name) {
+                    names.push(player.name.clone());
+                }
+            }
+        }
+        for name in &scrim.bench {
+            if !names.contains(name) {
+                names.push(name.clone());
+            }
+        }
+    }
+
+    let mut moved_players: Vec<PlayerChange> = Vec::new();
+    let mut role_changes: Vec<PlayerChange> = Vec::new();
+    for name in names {
+        let (from_team, from_role) = find_player(from, &name);
+        let (to_team, to_role) = find_player(to, &name);
+        let change = PlayerChange {
+            name,
+            from_team: from_team.clone(),
+            to_team: to_team.clone(),
+            from_role: from_role.clone(),
+            to_role: to_role.clone(),
+        };
+        if paired_name(&from_team) != to_team {
+            moved_players.push(change);
+        } else if from_role != to_role {
+            role_changes.push(change);
+        }
+    }
+
+    let mut teams: Vec<TeamDiff> = Vec::new();
+    for (i, j) in pairs {
+        let from_team = &from.teams[i];
+        let to_team = &to.teams[j];
+        let roles = from_team
+            .roles
+            .iter()
+            .zip(to_team.roles.iter())
+            .map(|(from_role, to_role)| RoleDiff {
+                role: from_role.role.clone(),
+                from_average_sr: from_role.average_sr,
+                to_average_sr: to_role.average_sr,
+                average_sr_delta: to_role.average_sr - from_role.average_sr,
+            })
+            .collect();
+        teams.push(TeamDiff {
+            from_team: from_team.name.clone(),
+            to_team: to_team.name.clone(),
+            from_average_sr: from_team.average_sr,
+            to_average_sr: to_team.average_sr,
+            average_sr_delta: to_team.average_sr - from_team.average_sr,
+            roles,
+        });
+    }
+
+    ScrimDiff {
+        from_id: from.id.clone(),
+        to_id: to.id.clone(),
+        from_rating: from.rating,
+        to_rating: to.rating,
+        rating_delta: to.rating - from.rating,
+        moved_players,
+        role_changes,
+        teams,
+    }
+}
+
+fn or_bench(value: &Option<String>) -> &str {
+    match value {
+        Some(value) => value,
+        None => "Bench",
+    }
+}
+
+impl ScrimDiff {
+    pub fn get_pretty_string(&self) -> String {
+        let mut s = String::new();
+        s.push_str("-------------------------------------\n");
+        s.push_str(&format!("Diff {} -> {}\n", self.from_id, self.to_id));
+        s.push_str(&format!(
+            "Rating: {} -> {} ({:+})\n",
+            self.from_rating, self.to_rating, self.rating_delta
+        ));
+
+        s.push_str("\nMoved players:\n");
+        if self.moved_players.is_empty() {
+            s.push_str("  none\n");
+        }
+        for change in &self.moved_players {
+            s.push_str(&format!(
+                "  {}: {} ({}) -> {} ({})\n",
+                change.name,
+                or_bench(&change.from_team),
+                or_bench(&change.from_role),
+                or_bench(&change.to_team),
+                or_bench(&change.to_role)
+            ));
+        }
+
+        s.push_str("\nRole changes:\n");
+        if self.role_changes.is_empty() {
+            s.push_str("  none\n");
+        }
+        for change in &self.role_changes {
+            s.push_str(&format!(
+                "  {} ({}): {} -> {}\n",
+                change.name,
+                or_bench(&change.to_team),
+                or_bench(&change.from_role),
+                or_bench(&change.to_role)
+            ));
+        }
+
+        s.push_str("\nAverages:\n");
+        for team in &self.teams {
+            if team.from_team == team.to_team {
+                s.push_str(&format!("  {}", team.to_team));
+            } else {
+                s.push_str(&format!("  {} -> {}", team.from_team, team.to_team));
+            }
+            s.push_str(&format!(
+                ": {:.2} -> {:.2} ({:+.2})\n",
+                team.from_average_sr, team.to_average_sr, team.average_sr_delta
+            ));
+            for role in &team.roles {
+                s.push_str(&format!(
+                    "    {: <8}{:.1} -> {:.1} ({:+.1})\n",
+                    role.role, role.from_average_sr, role.to_average_sr, role.average_sr_delta
+                ));
+            }
+        }
+        s.push_str("=====================================\n");
+        s
+    }
+}
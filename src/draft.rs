@@ -0,0 +1,439 @@
+use crate::context::ScrimContext;
+use crate::matchup::TeamComp;
+use crate::matchup_ow2::OW2TeamComp;
+use crate::series::main_role;
+use crate::Matchup;
+use crate::OW2Matchup;
+use crate::Player;
+use crate::Position;
+use anyhow::{anyhow, Result};
+use itertools::Itertools;
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+
+// Captains draft: the captains pick their players one by one instead of the full search.
+// After every pick the remaining picks are played out greedily for each player in the pool,
+// the pick whose projected final Matchup has the lowest rating is recommended.
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DraftOrder {
+    Snake,
+    Linear,
+}
+
+#[derive(Debug, Clone)]
+pub struct Draft<'a> {
+    players: &'a HashMap<u8, Player>,
+    ow_2: bool,
+    order: DraftOrder,
+    pub team_names: Vec<String>,
+    pub teams: Vec<Vec<u8>>,
+    pub pool: Vec<u8>,
+    number_of_picks: usize,
+    role_averages: [f32; 3],
+    target_sr: f32,
+}
+
+fn best_sr(player: &Player) -> i16 {
+    player.get_sr(main_role(player))
+}
+
+// The captains with the highest SR on their main role
+pub fn choose_captains(players: &HashMap<u8, Player>, number_of_teams: usize) -> Vec<u8> {
+    let mut ids: Vec<u8> = players.keys().cloned().collect();
+    ids.sort_by(|a, b| {
+        best_sr(&players[b])
+            .cmp(&best_sr(&players[a]))
+            .then(a.cmp(b))
+    });
+    ids.truncate(number_of_teams);
+    ids
+}
+
+impl<'a> Draft<'a> {
+    pub fn new(
+        players: &'a HashMap<u8, Player>,
+        captains: Vec<u8>,
+        order: DraftOrder,
+        ow_2: bool,
+    ) -> Draft<'a> {
+        let position_vec = vec![Position::Tank, Position::Damage, Position::Support];
+
+        let mut role_averages: [f32; 3] = [0.0; 3];
+        for position in position_vec {
+            let role_srs: Vec<i16> = players
+                .values()
+                .filter(|player| player.plays_position(position))
+                .map(|player| player.get_sr(position))
+                .collect();
+            if !role_srs.is_empty() {
+                role_averages[position as usize] =
+                    role_srs.iter().map(|sr| *sr as f32).sum::<f32>() / role_srs.len() as f32;
+            }
+        }
+        let target_sr = players
+            .values()
+            .map(|player| best_sr(player) as f32)
+            .sum::<f32>()
+            / players.len() as f32;
+
+        let mut pool: Vec<u8> = players
+            .keys()
+            .filter(|id| !captains.contains(id))
+            .cloned()
+            .collect();
+        pool.sort_by(|a, b| {
+            best_sr(&players[b])
+                .cmp(&best_sr(&players[a]))
+                .then(a.cmp(b))
+        });
+
+        Draft {
+            players,
+            ow_2,
+            order,
+            team_names: captains
+                .iter()
+                .map(|id| format!("Team {}", players[id].name))
+                .collect(),
+            teams: captains.iter().map(|id| vec![*id]).collect(),
+            pool,
+            number_of_picks: 0,
+            role_averages,
+            target_sr,
+        }
+    }
+
+    fn team_size(&self) -> usize {
+        if self.ow_2 {
+            5
+        } else {
+            6
+        }
+    }
+
+    // The role of every slot of a team
+    fn slots(&self) -> Vec<Position> {
+        let mut slots = vec![Position::Tank];
+        if !self.ow_2 {
+            slots.push(Position::Tank);
+        }
+        slots.append(&mut vec![
+            Position::Damage,
+            Position::Damage,
+            Position::Support,
+            Position::Support,
+        ]);
+        slots
+    }
+
+    // Index of the team that picks next, None once all teams are full
+    pub fn team_on_the_clock(&self) -> Option<usize> {
+        let number_of_teams = self.teams.len();
+        if number_of_teams == 0
+            || self.pool.is_empty()
+            || self.teams.iter().all(|team| team.len() >= self.team_size())
+        {
+            return None;
+        }
+        let round = self.number_of_picks / number_of_teams;
+        let index = self.number_of_picks % number_of_teams;
+        match self.order {
+            DraftOrder::Snake if round % 2 == 1 => Some(number_of_teams - 1 - index),
+            _ => Some(index),
+        }
+    }
+
+    // Whether the players can still be put on distinct slots of a team
+    fn can_fill(&self, members: &[u8], slots: &[Position]) -> bool {
+        match members.split_first() {
+            None => true,
+            Some((first, rest)) => {
+                let player = &self.players[first];
+                (0..slots.len()).any(|i| {
+                    if !player.plays_position(slots[i]) || slots[..i].contains(&slots[i]) {
+                        return false;
+                    }
+                    let mut remaining_slots = slots.to_vec();
+                    remaining_slots.remove(i);
+                    self.can_fill(rest, &remaining_slots)
+                })
+            }
+        }
+    }
+
+    // Players of the pool the team can still pick without ending up unable to field a lineup
+    pub fn eligible_picks(&self, team: usize) -> Vec<u8> {
+        let slots = self.slots();
+        self.pool
+            .iter()
+            .filter(|id| {
+                let mut members = self.teams[team].clone();
+                members.push(**id);
+                self.can_fill(&members, &slots)
+            })
+            .cloned()
+            .collect()
+    }
+
+    pub fn pick(&mut self, id: u8) -> Result<()> {
+        let team = self
+            .team_on_the_clock()
+            .ok_or_else(|| anyhow!("The draft is already finished"))?;
+        let position = self
+            .pool
+            .iter()
+            .position(|pool_id| *pool_id == id)
+            .ok_or_else(|| anyhow!("{} is not in the pool", self.players[&id].name))?;
+        self.pool.remove(position);
+        self.teams[team].push(id);
+        self.number_of_picks += 1;
+        Ok(())
+    }
+
+    // Finishes the draft by letting every team pick the player that brings its average SR closest to the target
+    fn complete_greedily(&mut self) {
+        while let Some(team) = self.team_on_the_clock() {
+            let team_sr: f32 = self.teams[team]
+                .iter()
+                .map(|id| best_sr(&self.players[id]) as f32)
+                .sum();
+            let team_size = (self.teams[team].len() + 1) as f32;
+            let best = self.eligible_picks(team).into_iter().min_by(|a, b| {
+                let distance = |id: &u8| {
+                    ((team_sr + best_sr(&self.players[id]) as f32) / team_size - self.target_sr)
+                        .abs()
+                };
+                distance(a).partial_cmp(&distance(b)).unwrap()
+            });
+            match best {
+                Some(id) => self.pick(id).unwrap(),
+                None => break,
+            }
+        }
+    }
+
+    // Puts the players of a team on the slots so the role averages come closest to the averages of the roster
+    fn assign_roles(&self, members: &[u8]) -> Option<Vec<u8>> {
+        let slots = self.slots();
+        if members.len() != slots.len() {
+            return None;
+        }
+
+        let mut best: Option<(f32, Vec<u8>)> = None;
+        for lineup in members.iter().cloned().permutations(members.len()) {
+            let fits = lineup
+                .iter()
+                .zip(slots.iter())
+                .all(|(id, position)| self.players[id].plays_position(*position));
+            if !fits {
+                continue;
+            }
+            let mut cost: f32 = 0.0;
+            for position in [Position::Tank, Position::Damage, Position::Support] {
+                let role_srs: Vec<f32> = lineup
+                    .iter()
+                    .zip(slots.iter())
+                    .filter(|(_, slot)| **slot == position)
+                    .map(|(id, _)| self.players[id].get_sr(position) as f32)
+                    .collect();
+                let average = role_srs.iter().sum::<f32>() / role_srs.len() as f32;
+                cost += (average - self.role_averages[position as usize]).abs();
+            }
+            if best
+                .as_ref()
+                .map(|(best_cost, _)| cost < *best_cost)
+                .unwrap_or(true)
+            {
+                best = Some((cost, lineup));
+            }
+        }
+        best.map(|(_, lineup)| lineup)
+    }
+
+    // Rating of the lineup the finished draft results in, None if a team can't field a lineup
+    fn rating(&self) -> Option<i16> {
+        self.get_context("").map(|context| context.rating)
+    }
+
+    // Rating of the final Matchup if the team on the clock picks the player
+    // and all following picks are made greedily
+    pub fn projected_rating(&self, id: u8) -> Option<i16> {
+        let mut projection = self.clone();
+        projection.pick(id).ok()?;
+        projection.complete_greedily();
+        projection.rating()
+    }
+
+    // The pick with the lowest projected rating and that rating
+    pub fn recommend(&self) -> Option<(u8, i16)> {
+        let team = self.team_on_the_clock()?;
+        self.eligible_picks(team)
+            .into_iter()
+            .filter_map(|id| self.projected_rating(id).map(|rating| (id, rating)))
+            .min_by_key(|(_, rating)| *rating)
+    }
+
+    pub fn get_context(&self, label: &str) -> Option<ScrimContext> {
+        let mut lineups: Vec<Vec<u8>> = Vec::new();
+        for team in &self.teams {
+            lineups.push(self.assign_roles(team)?);
+        }
+
+        let context = if self.ow_2 {
+            let teams: Vec<OW2TeamComp> = lineups
+                .iter()
+                .zip(self.team_names.iter())
+                .map(|(l, name)| (name.clone(), l[0], l[1], l[2], l[3], l[4]))
+                .collect();
+            let mut matchup = OW2Matchup::new(teams, self.players);
+            matchup.players_left_over = self.pool.clone();
+            matchup.get_context(label, self.players)
+        } else {
+            let teams: Vec<TeamComp> = lineups
+                .iter()
+                .zip(self.team_names.iter())
+                .map(|(l, name)| (name.clone(), l[0], l[1], l[2], l[3], l[4], l[5]))
+                .collect();
+            let mut matchup = Matchup::new(teams, self.players);
+            matchup.players_left_over = self.pool.clone();
+            matchup.get_context(label, self.players)
+        };
+        Some(context)
+    }
+
+    // The teams so far and the remaining pool with the roles every player is eligible for
+    pub fn get_pool_string(&self) -> String {
+        let mut s = String::new();
+        s.push_str("-------------------------------------\n");
+        for (name, team) in self.team_names.iter().zip(self.teams.iter()) {
+            let members: Vec<&str> = team
+                .iter()
+                .map(|id| self.players[id].name.as_str())
+                .collect();
+            s.push_str(&format!("{}: {}\n", name, members.join(", ")));
+        }
+
+        let eligible = match self.team_on_the_clock() {
+            Some(team) => self.eligible_picks(team),
+            None => Vec::new(),
+        };
+        s.push_str("\nPool:\n");
+        for id in &self.pool {
+            let player = &self.players[id];
+            let mut roles = String::new();
+            for position in [Position::Tank, Position::Damage, Position::Support] {
+                if player.plays_position(position) {
+                    roles.push_str(&format!(
+                        "{}: {: <6}",
+                        position.label(),
+                        player.get_sr(position)
+                    ));
+                } else {
+                    roles.push_str(&format!("{: <w$}", "", w = position.label().len() + 8));
+                }
+            }
+            let marker = if eligible.contains(id) { " " } else { "x" };
+            s.push_str(&format!(
+                "{} {: <16}{}\n",
+                marker,
+                player.name,
+                roles.trim_end()
+            ));
+        }
+        s
+    }
+}
+
+fn find_in_pool(draft: &Draft, input: &str) -> Option<u8> {
+    draft
+        .pool
+        .iter()
+        .find(|id| draft.players[id].name.eq_ignore_ascii_case(input))
+        .cloned()
+}
+
+// The captains given by name, every one of them in the roster and captain of one team only
+pub fn find_captains(players: &HashMap<u8, Player>, captain_names: &[String]) -> Result<Vec<u8>> {
+    let mut captains: Vec<u8> = Vec::new();
+    for name in captain_names {
+        let id = players
+            .iter()
+            .find(|(_, player)| player.name.eq_ignore_ascii_case(name))
+            .map(|(id, _)| *id)
+            .ok_or_else(|| anyhow!("Captain {} is not in the roster", name))?;
+        if captains.contains(&id) {
+            return Err(anyhow!("{} is given as captain twice", players[&id].name));
+        }
+        captains.push(id);
+    }
+    Ok(captains)
+}
+
+// Runs the draft on the command line. Picks are entered by name, an empty line takes the recommended pick.
+pub fn run_draft(
+    players: &HashMap<u8, Player>,
+    captain_names: &[String],
+    order: DraftOrder,
+    ow_2: bool,
+) -> Result<ScrimContext> {
+    let captains: Vec<u8> = if captain_names.is_empty() {
+        let team_size = if ow_2 { 5 } else { 6 };
+        choose_captains(players, players.len() / team_size)
+    } else {
+        find_captains(players, captain_names)?
+    };
+    if captains.len() < 2 {
+        return Err(anyhow!("A draft needs at least two captains"));
+    }
+
+    let mut draft = Draft::new(players, captains, order, ow_2);
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+
+    while let Some(team) = draft.team_on_the_clock() {
+        print!("{}", draft.get_pool_string());
+        let recommendation = draft.recommend();
+        match recommendation {
+            Some((id, rating)) => println!(
+                "\nRecommended pick: {} (projected rating: {})",
+                players[&id].name, rating
+            ),
+            None => println!("\nNo pick leads to a complete lineup"),
+        }
+        print!("{} picks: ", draft.team_names[team]);
+        io::stdout().flush()?;
+
+        let line = lines
+            .next()
+            .ok_or_else(|| anyhow!("The draft was aborted"))??;
+        let input = line.trim();
+        let id = if input.is_empty() {
+            match recommendation {
+                Some((id, _)) => id,
+                None => continue,
+            }
+        } else {
+            match find_in_pool(&draft, input) {
+                Some(id) => id,
+                None => {
+                    println!("{} is not in the pool", input);
+                    continue;
+                }
+            }
+        };
+        if !draft.eligible_picks(team).contains(&id) {
+            println!(
+                "{} can't pick {} and still field a full lineup",
+                draft.team_names[team], players[&id].name
+            );
+            continue;
+        }
+        draft.pick(id)?;
+    }
+
+    print!("{}", draft.get_pool_string());
+    draft
+        .get_context("A")
+        .ok_or_else(|| anyhow!("The drafted teams can't field a full lineup"))
+}
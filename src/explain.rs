@@ -0,0 +1,104 @@
+use crate::context::ScrimContext;
+use serde::Serialize;
+
+// Why a scrim got its rating. The rating adds up the differences of the role averages (times 5)
+// and of the role deviations (times 2) of every team, plus the penalties for conflicts.
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Explanation {
+    pub id: String,
+    pub label: String,
+    pub rating: i16,
+    pub average_part: i16,
+    pub deviation_part: i16,
+    pub penalty_part: i16,
+    // Largest gap first
+    pub role_gaps: Vec<RoleGap>,
+    pub conflicts: Vec<String>,
+    // Players on a role they don't play, they don't change the rating
+    pub off_role_warnings: Vec<String>,
+    // The same in sentences
+    pub lines: Vec<String>,
+}
+
+// Difference between the strongest and the weakest team in one role
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RoleGap {
+    pub role: String,
+    pub strongest_team: String,
+    pub weakest_team: String,
+    pub gap: f32,
+}
+
+fn role_gaps(scrim: &ScrimContext) -> Vec<RoleGap> {
+    let mut role_gaps: Vec<RoleGap> = Vec::new();
+    for (i, role) in scrim.roles.iter().enumerate() {
+        let strongest = scrim
+            .teams
+            .iter()
+            .max_by(|a, b| a.roles[i].average_sr.total_cmp(&b.roles[i].average_sr));
+        let weakest = scrim
+            .teams
+            .iter()
+            .min_by(|a, b| a.roles[i].average_sr.total_cmp(&b.roles[i].average_sr));
+        if let (Some(strongest), Some(weakest)) = (strongest, weakest) {
+            role_gaps.push(RoleGap {
+                role: role.role.clone(),
+                strongest_team: strongest.name.clone(),
+                weakest_team: weakest.name.clone(),
+                gap: strongest.roles[i].average_sr - weakest.roles[i].average_sr,
+            });
+        }
+    }
+    role_gaps.sort_by(|a, b| b.gap.total_cmp(&a.gap));
+    role_gaps
+}
+
+pub fn explain_scrim(scrim: &ScrimContext) -> Explanation {
+    let average_part = (scrim.sum_of_average_differences * 5.0) as i16;
+    let deviation_part = (scrim.sum_of_deviation_differences * 2.0) as i16;
+    // What's left of the rating are the penalties, up to rounding
+    let penalty_part = scrim
+        .rating
+        .saturating_sub(average_part)
+        .saturating_sub(deviation_part)
+        .max(0);
+    let role_gaps = role_gaps(scrim);
+    let conflicts: Vec<String> = scrim
+        .hero_conflicts
+        .iter()
+        .chain(scrim.sub_role_clashes.iter())
+        .chain(scrim.language_conflicts.iter())
+        .cloned()
+        .collect();
+
+    let mut lines: Vec<String> = vec![format!(
+        "Rating {}: {} from the role averages, {} from the role deviations, {} from penalties",
+        scrim.rating, average_part, deviation_part, penalty_part
+    )];
+    for role_gap in &role_gaps {
+        if role_gap.gap < 1.0 {
+            lines.push(format!("{}: all teams are even", role_gap.role));
+        } else {
+            lines.push(format!(
+                "{}: {} are {:.0} SR stronger than {}",
+                role_gap.role, role_gap.strongest_team, role_gap.gap, role_gap.weakest_team
+            ));
+        }
+    }
+    lines.extend(conflicts.iter().cloned());
+    lines.extend(scrim.off_role_warnings.iter().cloned());
+
+    Explanation {
+        id: scrim.id.clone(),
+        label: scrim.label.clone(),
+        rating: scrim.rating,
+        average_part,
+        deviation_part,
+        penalty_part,
+        role_gaps,
+        conflicts,
+        off_role_warnings: scrim.off_role_warnings.clone(),
+        lines,
+    }
+}
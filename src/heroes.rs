@@ -0,0 +1,120 @@
+use crate::Player;
+use crate::Position;
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+// Hero catalogue shipped with the tool, the same as heroes.txt
+const HERO_CATALOGUE: &str = include_str!("../heroes.txt");
+
+// Role of every hero, the names are lowercase
+static HERO_POSITIONS: LazyLock<HashMap<String, Position>> = LazyLock::new(|| {
+    let mut hero_positions: HashMap<String, Position> = HashMap::new();
+    for line in HERO_CATALOGUE.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (role, heroes) = match line.split_once(": ") {
+            Some(split) => split,
+            None => continue,
+        };
+        let position = match role {
+            "Tank" => Position::Tank,
+            "Damage" => Position::Damage,
+            "Support" => Position::Support,
+            _ => continue,
+        };
+        for hero in heroes.split(", ") {
+            hero_positions.insert(hero.trim().to_lowercase(), position);
+        }
+    }
+    hero_positions
+});
+
+pub fn hero_position(hero: &str) -> Option<Position> {
+    HERO_POSITIONS.get(&hero.to_lowercase()).copied()
+}
+
+// Heroes of the player's pool that are not in the catalogue
+pub fn unknown_heroes(player: &Player) -> Vec<&str> {
+    player
+        .heroes
+        .iter()
+        .filter(|hero| hero_position(hero).is_none())
+        .map(|hero| hero.as_str())
+        .collect()
+}
+
+fn heroes_for(player: &Player, position: Position) -> Vec<&str> {
+    player
+        .heroes
+        .iter()
+        .filter(|hero| hero_position(hero) == Some(position))
+        .map(|hero| hero.as_str())
+        .collect()
+}
+
+// Whether every player can get a hero of their own
+fn can_assign(pools: &[Vec<&str>], used: &mut Vec<String>) -> bool {
+    match pools.split_first() {
+        None => true,
+        Some((pool, rest)) => pool.iter().any(|hero| {
+            let hero = hero.to_lowercase();
+            if used.contains(&hero) {
+                return false;
+            }
+            used.push(hero);
+            let assigned = can_assign(rest, used);
+            used.pop();
+            assigned
+        }),
+    }
+}
+
+// Checks that a team can field a distinct hero on every slot.
+// A hero belongs to one role, so every role is checked on its own.
+// Players without a hero pool can play any hero and never cause a conflict.
+pub fn team_conflicts(team_name: &str, slots: &[(Position, &Player)]) -> Vec<String> {
+    let position_vec = vec![Position::Tank, Position::Damage, Position::Support];
+    let mut conflicts: Vec<String> = Vec::new();
+
+    for position in position_vec {
+        let role_players: Vec<&Player> = slots
+            .iter()
+            .filter(|(slot, player)| *slot == position && !player.heroes.is_empty())
+            .map(|(_, player)| *player)
+            .collect();
+
+        let mut names: Vec<&str> = Vec::new();
+        let mut pools: Vec<Vec<&str>> = Vec::new();
+        for player in &role_players {
+            let pool = heroes_for(player, position);
+            if pool.is_empty() {
+                conflicts.push(format!(
+                    "{}: {} has no {} hero",
+                    team_name,
+                    player.name,
+                    position.label()
+                ));
+            } else {
+                names.push(player.name.as_str());
+                pools.push(pool);
+            }
+        }
+
+        if pools.len() > 1 && !can_assign(&pools, &mut Vec::new()) {
+            let mut heroes: Vec<&str> = pools.concat();
+            heroes.sort_unstable();
+            heroes.dedup();
+            conflicts.push(format!(
+                "{}: {} can't play distinct {} heroes ({})",
+                team_name,
+                names.join(" and "),
+                position.label(),
+                heroes.join(", ")
+            ));
+        }
+    }
+
+    conflicts
+}
@@ -0,0 +1,30 @@
+use crate::context::ScrimContext;
+use crate::template::register_helpers;
+use anyhow::Result;
+use handlebars::Handlebars;
+use serde::Serialize;
+
+// Self-contained page showing the top scrims side by side
+const REPORT_TEMPLATE: &str = include_str!("../templates/report.hbs");
+
+#[derive(Serialize)]
+struct ReportContext<'a> {
+    scrims: &'a [ScrimContext],
+    // Highest role average of any team, the role bars are scaled to it
+    max_sr: f32,
+}
+
+pub fn render_report(scrims: &[ScrimContext]) -> Result<String> {
+    let max_sr = scrims
+        .iter()
+        .flat_map(|scrim| scrim.teams.iter())
+        .flat_map(|team| team.roles.iter())
+        .map(|role| role.average_sr)
+        .fold(0.0, f32::max);
+
+    let mut handlebars = Handlebars::new();
+    register_helpers(&mut handlebars);
+    handlebars.register_template_string("report", REPORT_TEMPLATE)?;
+
+    Ok(handlebars.render("report", &ReportContext { scrims, max_sr })?)
+}
@@ -0,0 +1,41 @@
+use crate::Player;
+
+// Spoken languages are read from "languages=de,en" in the roster.
+// A team needs at least one language all of its players speak for comms.
+
+// Languages every player of the team speaks, players without languages in the roster speak any of them.
+// None if no player of the team lists a language.
+pub fn common_languages(team: &[&Player]) -> Option<Vec<String>> {
+    let mut common: Option<Vec<String>> = None;
+    for player in team {
+        if player.languages.is_empty() {
+            continue;
+        }
+        common = Some(match common {
+            None => player.languages.clone(),
+            Some(languages) => languages
+                .into_iter()
+                .filter(|language| player.languages.contains(language))
+                .collect(),
+        });
+    }
+    common
+}
+
+// "de, en" for the output, "-" if nobody lists a language
+pub fn get_comms_string(team: &[&Player]) -> String {
+    match common_languages(team) {
+        Some(languages) if languages.is_empty() => "none".to_string(),
+        Some(languages) => languages.join(", "),
+        None => "-".to_string(),
+    }
+}
+
+pub fn team_conflict(team_name: &str, team: &[&Player]) -> Option<String> {
+    match common_languages(team) {
+        Some(languages) if languages.is_empty() => {
+            Some(format!("{}: no common comms language", team_name))
+        }
+        _ => None,
+    }
+}
@@ -0,0 +1,330 @@
+// Balances Overwatch scrims: a roster goes in, the best ways to split it into teams come out.
+// The command line tool in main.rs is one front end of this library.
+
+use anyhow::{anyhow, Result};
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fs;
+use std::time::Duration;
+use web_time::Instant;
+
+pub mod player;
+pub use player::Player;
+
+pub mod team;
+use team::Team;
+
+pub mod team_ow2;
+use team_ow2::OW2Team;
+
+pub mod types;
+pub use types::{Penalties, Position, SubRole};
+
+pub mod matchup;
+use matchup::Matchup;
+
+pub mod matchup_ow2;
+use matchup_ow2::OW2Matchup;
+
+pub mod scrim_id;
+
+pub mod context;
+pub use context::ScrimContext;
+
+pub mod template;
+
+pub mod reader;
+
+pub mod schedule;
+
+pub mod maps;
+
+pub mod card;
+
+pub mod diff;
+
+pub mod explain;
+
+pub mod draft;
+
+pub mod heroes;
+
+pub mod languages;
+
+pub mod stacks;
+
+pub mod html;
+
+pub mod series;
+
+pub mod availability;
+use availability::Session;
+
+pub mod naming;
+use naming::{TeamNamer, TeamNaming};
+
+pub mod progress;
+pub use progress::{CancellationToken, ProgressEvent, ProgressObserver, SearchProgress};
+
+mod search;
+pub use search::SearchResult;
+
+pub mod options;
+
+pub mod lineup;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub mod server;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub mod tui;
+
+pub mod bot;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub mod rpc;
+
+pub mod wasm;
+
+// The players of one session, numbered from 1 in the order of the roster
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Roster {
+    pub players: HashMap<u8, Player>,
+}
+
+impl Roster {
+    // One player per line in the format of players.txt, lines starting with # are skipped
+    pub fn parse(roster: &str) -> Result<Roster> {
+        Ok(Roster::parse_with_warnings(roster)?.0)
+    }
+
+    // Like parse, but also hands back the lines that could not be read and the unknown heroes
+    pub fn parse_with_warnings(roster: &str) -> Result<(Roster, Vec<String>)> {
+        let mut players: HashMap<u8, Player> = HashMap::new();
+        let mut warnings = Vec::new();
+        for line in roster.lines() {
+            if !line.starts_with('#') {
+                match Player::new(line.to_string()) {
+                    Ok(player) => {
+                        for hero in heroes::unknown_heroes(&player) {
+                            warnings.push(format!(
+                                "Unknown hero {} in the pool of {}",
+                                hero, player.name
+                            ));
+                        }
+                        players.insert(Roster::next_id(&players)?, player);
+                    }
+                    Err(error) => warnings.push(format!("Error with parsing player: {}", error)),
+                }
+            }
+        }
+        Ok((Roster { players }, warnings))
+    }
+
+    pub fn from_file(players_file: &str) -> Result<Roster> {
+        Ok(Roster::from_file_with_warnings(players_file)?.0)
+    }
+
+    pub fn from_file_with_warnings(players_file: &str) -> Result<(Roster, Vec<String>)> {
+        let roster = fs::read_to_string(players_file)
+            .map_err(|error| anyhow!("Could not read {}: {}", players_file, error))?;
+        Roster::parse_with_warnings(&roster)
+    }
+
+    // The roster without the players that left and with the ones that joined, numbered again
+    pub fn changed(&self, remove: &[String], add: &[String]) -> Result<Roster> {
+        let mut ids: Vec<&u8> = self.players.keys().collect();
+        ids.sort();
+        for name in remove {
+            if !self.players.values().any(|player| &player.name == name) {
+                return Err(anyhow!("There is no player {}", name));
+            }
+        }
+
+        let mut players = HashMap::new();
+        for id in ids {
+            let player = &self.players[id];
+            if !remove.contains(&player.name) {
+                players.insert(Roster::next_id(&players)?, player.clone());
+            }
+        }
+        let (added, warnings) = Roster::parse_with_warnings(&add.join("\n"))?;
+        if added.len() != add.len() {
+            return Err(anyhow!(
+                "Not every added player could be read: {}",
+                warnings.join(", ")
+            ));
+        }
+        let mut added_ids: Vec<&u8> = added.players.keys().collect();
+        added_ids.sort();
+        for id in added_ids {
+            players.insert(Roster::next_id(&players)?, added.players[id].clone());
+        }
+        Ok(Roster { players })
+    }
+
+    // Players are numbered with a u8, so a roster holds at most 255 of them
+    pub(crate) fn next_id(players: &HashMap<u8, Player>) -> Result<u8> {
+        u8::try_from(players.len() + 1)
+            .map_err(|_| anyhow!("A roster can't have more than {} players", u8::MAX))
+    }
+
+    pub fn len(&self) -> usize {
+        self.players.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.players.is_empty()
+    }
+}
+
+// Everything that decides which scrims the search finds
+#[derive(Debug, Clone, PartialEq)]
+pub struct BalanceConfig {
+    // Teams of 5 with one tank instead of 6 with two
+    pub ow_2: bool,
+    pub number_of_scrims: usize,
+    pub number_of_threads: usize,
+    pub penalties: Penalties,
+    pub require_sub_roles: bool,
+    pub session: Session,
+    // Plans this many rounds instead of alternatives for one round
+    pub series: Option<usize>,
+    pub team_naming: TeamNaming,
+    pub time_limit: Option<Duration>,
+}
+
+impl Default for BalanceConfig {
+    fn default() -> BalanceConfig {
+        BalanceConfig {
+            ow_2: false,
+            number_of_scrims: 10,
+            number_of_threads: 16,
+            penalties: Penalties::default(),
+            require_sub_roles: false,
+            session: Session {
+                start: None,
+                round_length: 30,
+            },
+            series: None,
+            team_naming: TeamNaming::Default,
+            time_limit: None,
+        }
+    }
+}
+
+// Searches the best scrims for the roster, the best one first.
+// With a series it returns one scrim per round instead.
+pub fn balance(
+    roster: &Roster,
+    config: &BalanceConfig,
+    progress: &SearchProgress,
+) -> Result<SearchResult> {
+    let mut team_namer = TeamNamer::new(&config.team_naming)?;
+    availability::check_session(&roster.players, &config.session)?;
+    let team_size = if config.ow_2 { 5 } else { 6 };
+    if roster.len() < team_size {
+        return Err(anyhow!(
+            "The roster has {} players, a team needs {}",
+            roster.len(),
+            team_size
+        ));
+    }
+    stacks::check_stacks(&roster.players, team_size)?;
+    // Players that leave early or join late can leave a round without a full team
+    for round in 1..=config.series.unwrap_or(1) {
+        let available =
+            availability::players_for_round(&roster.players, round, &config.session).len();
+        if available < team_size {
+            return Err(anyhow!(
+                "Round {} has {} available players, a team needs {}",
+                round,
+                available,
+                team_size
+            ));
+        }
+    }
+
+    let progress = match config.time_limit {
+        Some(time_limit) => progress.with_deadline(Instant::now() + time_limit),
+        None => progress.search(),
+    };
+
+    let mut result = match config.series {
+        Some(number_of_rounds) => {
+            search::generate_series(config, &roster.players, number_of_rounds, &progress)?
+        }
+        None => {
+            let round_players =
+                availability::players_for_round(&roster.players, 1, &config.session);
+            search::generate_scrims(config, &round_players, config.number_of_scrims, &progress)?
+        }
+    };
+
+    // The rounds of a series get new names even with the default names the search uses
+    if config.series.is_some() {
+        for scrim in result.scrims.iter_mut() {
+            team_namer.name_teams(scrim);
+        }
+    } else if !team_namer.is_default() {
+        team_namer.name_alternatives(&mut result.scrims);
+    }
+
+    Ok(result)
+}
+
+// Balances the roster again after players left or joined. The scrims that move the fewest players
+// of the current scrim come first, and the teams keep the names of the current teams they are closest to.
+pub fn rebalance(
+    roster: &Roster,
+    current: &ScrimContext,
+    config: &BalanceConfig,
+    progress: &SearchProgress,
+) -> Result<SearchResult> {
+    let config = BalanceConfig {
+        series: None,
+        team_naming: TeamNaming::Default,
+        ..config.clone()
+    };
+    let mut result = balance(roster, &config, progress)?;
+
+    let mut ranked: Vec<(usize, ScrimContext)> = result
+        .scrims
+        .into_iter()
+        .map(|mut scrim| {
+            keep_team_names(current, &mut scrim);
+            let moves = diff::diff_scrims(current, &scrim).moved_players.len();
+            (moves, scrim)
+        })
+        .collect();
+    ranked.sort_by_key(|(moves, scrim)| (*moves, scrim.rating));
+
+    result.scrims = ranked
+        .into_iter()
+        .enumerate()
+        .map(|(i, (_, mut scrim))| {
+            scrim.label = search::scrim_label(i);
+            scrim
+        })
+        .collect();
+    Ok(result)
+}
+
+// Names every team of the scrim after the team of the current scrim it shares the most players with
+fn keep_team_names(current: &ScrimContext, scrim: &mut ScrimContext) {
+    // Placeholders first, so the teams are paired by their players and not by their names.
+    // Team names never start with #, those lines are comments in a team names file.
+    for i in 0..scrim.teams.len() {
+        scrim.rename_team(i, &format!("#{}", i + 1));
+    }
+    let team_diffs = diff::diff_scrims(current, scrim).teams;
+    for i in 0..scrim.teams.len() {
+        let placeholder = scrim.teams[i].name.clone();
+        if let Some(team_diff) = team_diffs
+            .iter()
+            .find(|team_diff| team_diff.to_team == placeholder)
+        {
+            scrim.rename_team(i, &team_diff.from_team);
+        }
+    }
+}
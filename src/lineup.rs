@@ -0,0 +1,234 @@
+use crate::context::{PlayerContext, ScrimContext};
+use crate::matchup::TeamComp;
+use crate::matchup_ow2::OW2TeamComp;
+use crate::{Matchup, OW2Matchup, Penalties, Position, Roster};
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+
+// A scrim put together by hand, e.g. after players were moved between teams in the web UI.
+// The players are given by name, it is rated the same way the search rates its scrims.
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Lineup {
+    pub label: String,
+    pub teams: Vec<LineupTeam>,
+    #[serde(default)]
+    pub bench: Vec<String>,
+}
+
+// One tank makes an OW2 team, two tanks a 6v6 team
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct LineupTeam {
+    pub name: String,
+    pub tank: Vec<String>,
+    pub damage: Vec<String>,
+    pub support: Vec<String>,
+}
+
+// Where a player is in a lineup
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Place {
+    // The slot of a role in a team
+    Team(usize, Position, usize),
+    Bench(usize),
+}
+
+impl LineupTeam {
+    pub fn role(&self, position: Position) -> &Vec<String> {
+        match position {
+            Position::Tank => &self.tank,
+            Position::Damage => &self.damage,
+            Position::Support => &self.support,
+        }
+    }
+
+    fn role_mut(&mut self, position: Position) -> &mut Vec<String> {
+        match position {
+            Position::Tank => &mut self.tank,
+            Position::Damage => &mut self.damage,
+            Position::Support => &mut self.support,
+        }
+    }
+
+    // The role slots from the first tank to the last support
+    pub fn slots(&self) -> Vec<(Position, usize)> {
+        [Position::Tank, Position::Damage, Position::Support]
+            .iter()
+            .flat_map(|position| (0..self.role(*position).len()).map(move |slot| (*position, slot)))
+            .collect()
+    }
+}
+
+impl Lineup {
+    pub fn from_context(scrim: &ScrimContext) -> Lineup {
+        let names = |players: &[PlayerContext]| -> Vec<String> {
+            players.iter().map(|player| player.name.clone()).collect()
+        };
+        Lineup {
+            label: scrim.label.clone(),
+            teams: scrim
+                .teams
+                .iter()
+                .map(|team| LineupTeam {
+                    name: team.name.clone(),
+                    tank: names(&team.roles[Position::Tank as usize].players),
+                    damage: names(&team.roles[Position::Damage as usize].players),
+                    support: names(&team.roles[Position::Support as usize].players),
+                })
+                .collect(),
+            bench: scrim.bench.clone(),
+        }
+    }
+
+    // The names of every player in the teams and on the bench
+    pub fn names(&self) -> Vec<&String> {
+        self.teams
+            .iter()
+            .flat_map(|team| team.tank.iter().chain(&team.damage).chain(&team.support))
+            .chain(&self.bench)
+            .collect()
+    }
+
+    pub fn get(&self, place: Place) -> Option<&String> {
+        match place {
+            Place::Team(team, position, slot) => self.teams.get(team)?.role(position).get(slot),
+            Place::Bench(i) => self.bench.get(i),
+        }
+    }
+
+    // Swaps the players of two places, a player moves to another team, role or the bench this way
+    pub fn swap(&mut self, a: Place, b: Place) -> Result<()> {
+        let name_a = self
+            .get(a)
+            .cloned()
+            .ok_or_else(|| anyhow!("No player at {:?}", a))?;
+        let name_b = self
+            .get(b)
+            .cloned()
+            .ok_or_else(|| anyhow!("No player at {:?}", b))?;
+        self.set(a, name_b);
+        self.set(b, name_a);
+        Ok(())
+    }
+
+    fn set(&mut self, place: Place, name: String) {
+        match place {
+            Place::Team(team, position, slot) => self.teams[team].role_mut(position)[slot] = name,
+            Place::Bench(i) => self.bench[i] = name,
+        }
+    }
+}
+
+pub fn rate_lineup(lineup: &Lineup, roster: &Roster, penalties: Penalties) -> Result<ScrimContext> {
+    let mut player_ids: HashMap<&str, u8> = HashMap::new();
+    for (id, player) in &roster.players {
+        player_ids.insert(player.name.as_str(), *id);
+    }
+    let find = |name: &String| -> Result<u8> {
+        player_ids
+            .get(name.as_str())
+            .copied()
+            .ok_or_else(|| anyhow!("Player {} is not in the roster", name))
+    };
+
+    let is_ow2 = lineup
+        .teams
+        .first()
+        .map(|team| team.tank.len() == 1)
+        .unwrap_or(false);
+    let number_of_tanks = if is_ow2 { 1 } else { 2 };
+    if lineup.teams.len() < 2 {
+        return Err(anyhow!("A scrim needs at least two teams"));
+    }
+    for team in &lineup.teams {
+        if team.tank.len() != number_of_tanks || team.damage.len() != 2 || team.support.len() != 2 {
+            return Err(anyhow!(
+                "Team {} needs {} tanks, 2 damage and 2 supports",
+                team.name,
+                number_of_tanks
+            ));
+        }
+    }
+    // Every player of the roster is in the lineup exactly once
+    let mut names: HashSet<&String> = HashSet::new();
+    for name in lineup.names() {
+        find(name)?;
+        if !names.insert(name) {
+            return Err(anyhow!("Player {} is in the lineup twice", name));
+        }
+    }
+    let mut ids: Vec<&u8> = roster.players.keys().collect();
+    ids.sort();
+    if let Some(id) = ids
+        .into_iter()
+        .find(|id| !names.contains(&roster.players[id].name))
+    {
+        return Err(anyhow!(
+            "Player {} is missing from the lineup",
+            roster.players[id].name
+        ));
+    }
+
+    let mut bench: Vec<u8> = Vec::new();
+    for name in &lineup.bench {
+        bench.push(find(name)?);
+    }
+
+    let players = &roster.players;
+    let mut context = if is_ow2 {
+        let mut teams: Vec<OW2TeamComp> = Vec::new();
+        for team in &lineup.teams {
+            teams.push((
+                team.name.clone(),
+                find(&team.tank[0])?,
+                find(&team.damage[0])?,
+                find(&team.damage[1])?,
+                find(&team.support[0])?,
+                find(&team.support[1])?,
+            ));
+        }
+        let mut matchup = OW2Matchup::new(teams, players);
+        matchup.players_left_over = bench;
+        matchup.apply_penalties(players, penalties);
+        matchup.get_context(&lineup.label, players)
+    } else {
+        let mut teams: Vec<TeamComp> = Vec::new();
+        for team in &lineup.teams {
+            teams.push((
+                team.name.clone(),
+                find(&team.tank[0])?,
+                find(&team.tank[1])?,
+                find(&team.damage[0])?,
+                find(&team.damage[1])?,
+                find(&team.support[0])?,
+                find(&team.support[1])?,
+            ));
+        }
+        let mut matchup = Matchup::new(teams, players);
+        matchup.players_left_over = bench;
+        matchup.apply_penalties(players, penalties);
+        matchup.get_context(&lineup.label, players)
+    };
+
+    // Players on a role they don't play are allowed, but worth a warning
+    for team in &lineup.teams {
+        for (position, names) in [
+            (Position::Tank, &team.tank),
+            (Position::Damage, &team.damage),
+            (Position::Support, &team.support),
+        ] {
+            for name in names {
+                if !players[&find(name)?].plays_position(position) {
+                    context.off_role_warnings.push(format!(
+                        "{}: {} doesn't play {}",
+                        team.name,
+                        name,
+                        position.label()
+                    ));
+                }
+            }
+        }
+    }
+    Ok(context)
+}
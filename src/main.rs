@@ -1,886 +1,199 @@
-// import Team and Player
-
-use indicatif::ProgressBar;
-use itertools::Itertools;
-use std::cmp;
-use std::fs::File;
-use std::io::prelude::*;
-use std::io::{BufRead, BufReader};
-use std::sync::{Arc, Mutex};
-use std::thread;
-
-mod player;
-use player::Player;
-
-mod team;
-use team::Team;
-
-mod team_ow2;
-use team_ow2::OW2Team;
-
-mod types;
-use types::Position;
-
-mod matchup;
-use matchup::Matchup;
-
-mod matchup_ow2;
-use matchup_ow2::OW2Matchup;
-
+// Command line front end of the balancer
+
+use anyhow::{anyhow, Result};
+use autophil::bot::{self, BotConfig};
+use autophil::context::ScrimContext;
+use autophil::maps::MapPool;
+use autophil::options::Options;
+use autophil::server::{Api, Server};
+use autophil::template::ScrimRenderer;
+use autophil::{card, diff, draft, html, reader, rpc, schedule, scrim_id, series, tui};
+use autophil::{Player, Roster, SearchProgress};
 use std::collections::HashMap;
-use std::collections::HashSet;
-use std::iter::FromIterator;
-
-
-fn main() {
-    let player_map: HashMap<u8, Player> = read_players();
-
-    let ow_2 = false;
-
-    let number_of_printed_scrims = 10;
-
-    let number_of_threads = 16;
-
-    let duos = make_duos(&player_map, ow_2);
-
-    let mut team_names: Vec<String> = vec![
-        "Fighting Foxes".to_string(),
-        "Dancing Dragons".to_string(),
-        "Iron Wolves".to_string(),
-        "Strike Team 404".to_string(),
-        "Knightly Kittens".to_string(),
-        "Suwon Tigers".to_string(),
-    ];
-
-    let number_of_teams: usize = player_map.len() / if ow_2 { 5 } else { 6 };
+use std::fs::{self, File};
+use std::io::prelude::*;
+use std::path::Path;
 
-    let team_permutations = get_permutations(6);
+fn main() -> Result<()> {
+    let options = Options::from_args()?;
 
-    for _ in team_names.len()..number_of_teams {
-        let team_name = format!("Team {}", team_names.len() + 1);
-        team_names.push(team_name);
+    if let Some(address) = &options.serve {
+        let server = Server::with_api(
+            address,
+            Api::with_players_file(&options.players_file),
+            options.allow_remote,
+        )?;
+        println!("Listening on http://{}", address);
+        server.run();
+        return Ok(());
     }
 
-    if ow_2 {
-        let matchups = create_ow2_matchups(&duos, number_of_teams, number_of_threads);
-        println!("There are {} possible matchups", matchups.len());
-        let scrims = create_ow2_scrims(
-            &player_map,
-            matchups,
-            number_of_teams,
-            team_names,
-            number_of_threads,
-            team_permutations
-        );
-
-        let mut file = File::create("scrims.txt").unwrap();
-        let mut i: u8 = 0;
-        for scrim in scrims.iter().take(number_of_printed_scrims) {
-            file.write(format!("SCRIM {}:\n", (65 + i) as char).as_bytes())
-                .unwrap();
-            file.write_all(scrim.get_pretty_string(&player_map).as_bytes())
-                .unwrap();
-            i += 1;
-        }
-    } else {
-        let matchups = create_matchups(&duos, number_of_teams, number_of_threads);
-        println!("There are {} possible matchups", matchups.len());
-        let scrims = create_scrims(
-            &player_map,
-            matchups,
-            number_of_teams,
-            team_names,
-            number_of_threads,
-        );
-
-        let mut file = File::create("scrims.txt").unwrap();
-        let mut i: u8 = 0;
-        for scrim in scrims.iter().take(number_of_printed_scrims) {
-            file.write(format!("SCRIM {}:\n", (65 + i) as char).as_bytes())
-                .unwrap();
-            file.write_all(scrim.get_pretty_string(&player_map).as_bytes())
-                .unwrap();
-            i += 1;
-        }
-    };
-}
-
-fn get_permutations(number_of_teams: usize) -> Vec<Vec<Vec<usize>>> {
-    let uneven = if number_of_teams % 2 != 0 {
-        true
-    } else {
-        false
-    };
-
-    let last_chunk = number_of_teams / 2;
-
-    let perms = (1..=number_of_teams).permutations(number_of_teams);
-    let mut perms_seen: Vec<String> = Vec::new();
-    let mut perm_vec: Vec<Vec<Vec<usize>>> = Vec::new();
-    for p in perms {
-        let chunks = p.into_iter().chunks(2);
-        let mut sorted_vecs: Vec<Vec<usize>> = Vec::new();
-        let mut i = 0;
-        let mut last_num: usize = 0;
-        for c in chunks.into_iter() {
-            let mut vec = c.into_iter().collect::<Vec<usize>>();
-            if uneven && i == last_chunk {
-                last_num = vec.pop().unwrap();
-                break;
-            }
-            vec.sort();
-            sorted_vecs.push(vec);
-            i += 1;
-        }
-
-        if uneven {
-            let last_index = sorted_vecs.len() - 1;
-            let last = &mut sorted_vecs[last_index];
-            last.push(last_num);
-            last.sort();
-        }
-        
-        let mut perm_str: String = String::new();
-        for sorted_vec in &sorted_vecs {
-            perm_str.push_str(
-                &sorted_vec
-                    .into_iter()
-                    .map(|i| i.to_string())
-                    .collect::<String>(),
-            );
-        }
-        if perms_seen.contains(&perm_str) {
-            continue;
-        }
-        perms_seen.push(perm_str.clone());
-
-        let mut perm_vec_i: Vec<Vec<usize>> = Vec::new();
-        for sorted_vec in sorted_vecs {
-            perm_vec_i.push(sorted_vec);
-        }
-        
-        perm_vec.push(perm_vec_i);
+    if options.rpc {
+        let stdin = std::io::stdin();
+        return rpc::run(stdin.lock(), std::io::stdout());
     }
-    perm_vec
-}
 
-// Read all players from players.txt
-fn read_players() -> HashMap<u8, Player> {
-    // open file players.txt and read all lines that don't start with #
-    let mut players: HashMap<u8, Player> = HashMap::new();
-    // let file = File::open("players_balanced.txt").expect("File not found");
-    let file = File::open("players.txt").expect("File not found");
-    // let file = File::open("players_2.txt").expect("File not found");
-    let mut i = 1;
-    for line in BufReader::new(file).lines() {
-        let line = line.unwrap();
-        if !line.starts_with("#") {
-            let player = Player::new(line);
-            if player.is_ok() {
-                players.insert(i, player.unwrap());
-                i += 1;
-            } else {
-                println!("Error with parsing player");
-            }
+    // The bot talks over stdout, the warnings go to stderr
+    if let Some(bot_config_file) = &options.bot_config_file {
+        let config: BotConfig = serde_json::from_str(&fs::read_to_string(bot_config_file)?)
+            .map_err(|error| anyhow!("Invalid bot config {}: {}", bot_config_file, error))?;
+        let (roster, warnings) = Roster::from_file_with_warnings(&options.players_file)?;
+        for warning in warnings {
+            eprintln!("{}", warning);
         }
+        let stdin = std::io::stdin();
+        return bot::run(&roster, config, stdin.lock(), std::io::stdout());
     }
-    players
-}
 
-fn create_scrims(
-    players_raw: &HashMap<u8, Player>,
-    matchups: Vec<(Vec<(u8, u8)>, Vec<(u8, u8)>, Vec<(u8, u8)>)>,
-    number_of_teams: usize,
-    team_names_raw: Vec<String>,
-    number_of_threads: usize,
-) -> Vec<Matchup> {
-    let scrims: Vec<Vec<Matchup>> = Vec::new();
-
-    let all_player_vec: Vec<u8> = players_raw.keys().cloned().collect();
-
-    println!("{:?}", team_names_raw);
-
-    let number_of_matchups = matchups.len();
-
-    let scrim_progress_bar = ProgressBar::new(number_of_matchups as u64);
-    scrim_progress_bar.reset();
-
-    let mut chunk_size = number_of_matchups / number_of_threads;
-
-    if chunk_size == 0 {
-        chunk_size = 1;
+    let (roster, warnings) = Roster::from_file_with_warnings(&options.players_file)?;
+    for warning in warnings {
+        println!("{}", warning);
     }
+    let player_map: &HashMap<u8, Player> = &roster.players;
 
-    let matchup_chunks = matchups.into_iter().chunks(chunk_size);
-
-    let mut handles = vec![];
-
-    let mutex = Mutex::new((scrims, scrim_progress_bar));
-    let arc = Arc::new(mutex);
-
-    for matchup_chunk in matchup_chunks.into_iter() {
-        let matchup_chunk: Vec<(Vec<(u8, u8)>, Vec<(u8, u8)>, Vec<(u8, u8)>)> =
-            matchup_chunk.collect();
-
-        let all_player_set: HashSet<u8> = HashSet::from_iter(all_player_vec.clone().into_iter());
-        let cloned_arc = Arc::clone(&arc);
-        let players = players_raw.clone();
-        let team_names = team_names_raw.clone();
-
-        let mut best_rating: i16 = i16::MAX;
-
-        let handle = thread::spawn(move || {
-            let mut all_scrims: Vec<Matchup> = Vec::new();
-            let mut counter = 0;
-
-            for possible_matchup in matchup_chunk {
-                let tank_vec = &possible_matchup.0;
-                let damage_vec = &possible_matchup.1;
-                let support_vec = &possible_matchup.2;
-
-                let dps_iter = (0..number_of_teams).permutations(number_of_teams);
-                let supp_iter = (0..number_of_teams).permutations(number_of_teams);
-
-                for dps_perm in dps_iter {
-                    for supp_perm in supp_iter.clone() {
-                        let mut matchup_teams: Vec<(String, u8, u8, u8, u8, u8, u8)> = Vec::new();
-                        for i in 0..number_of_teams {
-                            matchup_teams.push((
-                                team_names[i].clone(),
-                                tank_vec.get(i).unwrap().0,
-                                tank_vec.get(i).unwrap().1,
-                                damage_vec.get(dps_perm[i]).unwrap().0,
-                                damage_vec.get(dps_perm[i]).unwrap().1,
-                                support_vec.get(supp_perm[i]).unwrap().0,
-                                support_vec.get(supp_perm[i]).unwrap().1,
-                            ));
-                        }
-                        let m_clone = matchup_teams.clone();
-                        let mut matchup = Matchup::new(matchup_teams, &players);
-                        let rating = matchup.rating;
-                        if rating < (best_rating as f32 * 1.1) as i16 {
-                            let mut players_playing: Vec<u8> = Vec::new();
-                            
-                            for m_team in m_clone {
-                                players_playing.push(m_team.1);
-                                players_playing.push(m_team.2);
-                                players_playing.push(m_team.3);
-                                players_playing.push(m_team.4);
-                                players_playing.push(m_team.5);
-                                players_playing.push(m_team.6);
-                            }
-                            
-                            // println!("all_player_set: {:?}", all_player_set);
-                        
-                            let playing_players_set: HashSet<u8> = HashSet::from_iter(players_playing.into_iter());
-                            // println!("playing_players_set: {:?}", playing_players_set);
-
-                            let players_on_bench: HashSet<_> = all_player_set.difference(&playing_players_set).collect();
-                            // println!("players_on_bench: {:?}", players_on_bench);
-                            // println!();
-
-                            let players_left_over_vec: Vec<_> = players_on_bench.into_iter().cloned().collect();
-
-                            matchup.players_left_over = players_left_over_vec;
-
-                            all_scrims.push(matchup);
-                            best_rating = cmp::min(rating, best_rating);
-                        }
-                    }
-                }
-                counter += 1;
-                if (counter % 1000) == 0 {
-                    cloned_arc.lock().unwrap().1.inc(1000);
-                }
-            }
-            // println!("Adding {} scrims to the shared vector", all_scrims.len());
-            let all_scrims_vector = &mut cloned_arc.lock().unwrap().0;
-            all_scrims_vector.push(all_scrims);
-        });
-        handles.push(handle);
-    }
-
-    for handle in handles {
-        handle.join().unwrap();
-    }
-
-    let scrim_vector = arc.lock().unwrap().0.clone();
-    let mut scrims: Vec<Matchup> = Vec::new();
-    for mut scrim_chunk in scrim_vector {
-        scrims.append(&mut scrim_chunk);
-    }
-
-    arc.lock().unwrap().1.finish();
-
-    println!("\nTotal number of scrims: {}", scrims.len());
-    scrims.sort_by(|a, b| a.rating.partial_cmp(&b.rating).unwrap());
-
-    scrims
-}
-
-fn make_duos(players: &HashMap<u8, Player>, ow_2: bool) -> HashMap<Position, Vec<(u8, u8)>> {
-    let position_vec = vec![Position::Tank, Position::Damage, Position::Support];
-    let mut duos: HashMap<Position, Vec<(u8, u8)>> = HashMap::new();
-
-    for position in position_vec {
-        let mut role_list: Vec<(u8, &Player)> = Vec::new();
-        println!("All players with {:?} role", position);
-        for (id, player) in players.iter() {
-            if player.plays_position(position) {
-                role_list.push((*id, &player));
-                println!("{}: {}", id, player.name);
-            }
-        }
-
-        let tuples: Vec<(u8, u8)> = if ow_2 && position == Position::Tank {
-            // collect the role list into a list of tuples with (id, 0)
-            role_list.iter().map(|(id, _)| (*id, 0)).collect()
+    if let Some((from, to)) = &options.diff {
+        let scrims_file = options.scrims_file.as_ref().unwrap_or(&options.output_file);
+        let from = find_scrim(from, scrims_file, player_map)?;
+        let to = find_scrim(to, scrims_file, player_map)?;
+        let scrim_diff = diff::diff_scrims(&from, &to);
+        if options.json {
+            println!("{}", serde_json::to_string_pretty(&scrim_diff)?);
         } else {
-            role_list
-                .iter()
-                .combinations(2)
-                .map(|pair| (pair[0].0, pair[1].0))
-                .collect()
-        };
-        // println!("Position: {:?}, number of pairs: {}", position, tuples.len());
-        // for t in &tuples {
-        //     println!("({}, {})", players.get(&t.0).unwrap().name, players.get(&t.1).unwrap().name);
-        // }
-        duos.insert(position, tuples);
+            print!("{}", scrim_diff.get_pretty_string());
+        }
+        return Ok(());
     }
 
-    duos
-}
-
-fn create_matchups(
-    player_duos: &HashMap<Position, Vec<(u8, u8)>>,
-    number_of_teams: usize,
-    number_of_threads: usize,
-) -> Vec<(Vec<(u8, u8)>, Vec<(u8, u8)>, Vec<(u8, u8)>)> {
-    let position_vec = vec![Position::Tank, Position::Damage, Position::Support];
+    let renderer = ScrimRenderer::new(options.template_file.as_deref())?;
 
-    println!("Number of teams: {}", number_of_teams);
-
-    let mut combination_map: HashMap<Position, Vec<Vec<(u8, u8)>>> = HashMap::new();
+    // The first Ctrl-C stops the search, the second one the program
+    let progress = SearchProgress::console();
+    let cancel = progress.cancel.clone();
+    ctrlc::set_handler(move || {
+        if cancel.is_cancelled() {
+            std::process::exit(130);
+        }
+        println!("\nStopping the search");
+        cancel.cancel();
+    })?;
 
-    for position in &position_vec {
-        let combinations_crowded = player_duos[&position]
+    let mut contexts: Vec<ScrimContext> = match &options.scrims_file {
+        Some(scrims_file) => reader::read_scrims_file(scrims_file, player_map)?
             .iter()
-            .combinations(number_of_teams as usize);
-        println!(
-            "There are {} possible {:?} combinations",
-            combinations_crowded.clone().count(),
-            position
-        );
-
-        let mut combinations_vec: Vec<Vec<&(u8, u8)>> = combinations_crowded.collect();
-
-        combinations_vec.retain(|c| {
-            let mut player_ids: Vec<u8> = Vec::new();
-            for player in c {
-                player_ids.push(player.0);
-                player_ids.push(player.1);
+            .map(|scrim| scrim.get_context(player_map))
+            .collect(),
+        None if options.draft => vec![draft::run_draft(
+            player_map,
+            &options.captains,
+            options.draft_order,
+            options.ow_2,
+        )?],
+        None => {
+            let result = autophil::balance(&roster, &options.balance_config(), &progress)?;
+            if !result.complete {
+                println!("The search was stopped early, these are the best scrims found so far");
             }
-            player_ids.iter().unique().count() == player_ids.len()
-        });
-
-        println!("{} of those are unique", combinations_vec.len());
-
-        let mut clean_combinations: Vec<Vec<(u8, u8)>> = Vec::new();
-
-        for combination in &combinations_vec {
-            let mut clean_combination: Vec<(u8, u8)> = Vec::new();
-            for player_vec in combination {
-                clean_combination.push((player_vec.0, player_vec.1));
-            }
-            clean_combinations.push(clean_combination);
+            result.scrims
         }
-        println!();
-
-        combination_map.insert(*position, clean_combinations);
-    }
-
-    let number_of_tank_combinations = combination_map[&Position::Tank].len();
-
-    let mut all_dps_supp_combinations: Vec<(Vec<(u8, u8)>, Vec<(u8, u8)>)> = Vec::new();
+    };
 
-    // First calculating all possible Damage & Support pairings
-    'damage_outer_loop: for damage_pairs_this_matchup in &combination_map[&Position::Damage] {
-        let mut dps_players_seen_this_run: Vec<u8> = Vec::new();
-        for dps_duo in damage_pairs_this_matchup {
-            dps_players_seen_this_run.push(dps_duo.0);
-            dps_players_seen_this_run.push(dps_duo.1);
-        }
-        // Check if all names are unique
-        if dps_players_seen_this_run.iter().unique().count() != dps_players_seen_this_run.len() {
-            continue 'damage_outer_loop;
-        }
-        // All players are unique so far, we can now pair these with Support players
-        'support_inner_loop: for support_pairs_this_matchup in &combination_map[&Position::Support]
-        {
-            let mut dps_and_support_players_seen_this_run = dps_players_seen_this_run.clone();
-            for support_duo in support_pairs_this_matchup {
-                dps_and_support_players_seen_this_run.push(support_duo.0);
-                dps_and_support_players_seen_this_run.push(support_duo.1);
-            }
-            // Check if all names are unique
-            if dps_and_support_players_seen_this_run
+    if !options.scrim_ids.is_empty() {
+        contexts.retain(|context| {
+            options
+                .scrim_ids
                 .iter()
-                .unique()
-                .count()
-                != dps_and_support_players_seen_this_run.len()
-            {
-                continue 'support_inner_loop;
-            }
-
-            // Looks like a possible tank / support matchup has been found, it can now be created and appended to the list of all combinations
-            let mut dps_supp_combination: (Vec<(u8, u8)>, Vec<(u8, u8)>) = (Vec::new(), Vec::new());
-            dps_supp_combination.0 = damage_pairs_this_matchup.clone();
-            dps_supp_combination.1 = support_pairs_this_matchup.clone();
-            all_dps_supp_combinations.push(dps_supp_combination);
-        }
-    }
-
-    println!("Starting to combine the tank matchups");
-
-    let matchup_vec: Vec<Vec<(Vec<(u8, u8)>, Vec<(u8, u8)>, Vec<(u8, u8)>)>> = Vec::new();
-    //                          | Tank Duos      DPS Duos       Support Duos  |
-    //                          +---------------------------------------------+
-    //                          |  One Matchup                                |
-
-    let tank_progress_bar = ProgressBar::new(number_of_tank_combinations as u64);
-    tank_progress_bar.reset();
-
-    let mut chunk_size = number_of_tank_combinations / number_of_threads;
-
-    if chunk_size == 0 {
-        chunk_size = 1;
-    }
-
-    let tank_chunks = &combination_map[&Position::Tank]
-        .clone()
-        .into_iter()
-        .chunks(chunk_size);
-
-    let mut handles = vec![];
-
-    let mutex = Mutex::new((matchup_vec, tank_progress_bar));
-    let arc = Arc::new(mutex);
-
-    for tank_chunk in tank_chunks.into_iter() {
-        let tank_chunk: Vec<Vec<(u8, u8)>> = tank_chunk.collect();
-
-        let dps_supp_combinations = all_dps_supp_combinations.clone();
-
-        // println!("length of matchup chunk: {}", matchup_chunk.len());
-
-        let cloned_arc = Arc::clone(&arc);
-
-        let handle = thread::spawn(move || {
-            let mut matchups_this_thread: Vec<(Vec<(u8, u8)>, Vec<(u8, u8)>, Vec<(u8, u8)>)> =
-                Vec::new();
-            let mut counter = 0;
-
-            for tank_pairs_in_this_matchup in tank_chunk {
-                let mut tank_names: Vec<u8> = Vec::new();
-                for pair in &tank_pairs_in_this_matchup {
-                    tank_names.push(pair.0);
-                    tank_names.push(pair.1);
-                }
-
-                'dps_support_loop: for dps_support_pairs in &dps_supp_combinations {
-                    let mut names_in_this_matchup = tank_names.clone();
-                    // Add the DPS pairs
-                    for pair in &dps_support_pairs.0 {
-                        names_in_this_matchup.push(pair.0);
-                        names_in_this_matchup.push(pair.1);
-                    }
-                    // Add the Support pairs
-                    for pair in &dps_support_pairs.1 {
-                        names_in_this_matchup.push(pair.0);
-                        names_in_this_matchup.push(pair.1);
-                    }
-
-                    // Check if any names are double
-                    if names_in_this_matchup.iter().unique().count() != names_in_this_matchup.len()
-                    {
-                        continue 'dps_support_loop;
-                    }
-
-                    // No names are double, this is a possibly valid matchup
-                    let dps_pairs: Vec<(u8, u8)> = dps_support_pairs.0.clone();
-                    let support_pairs: Vec<(u8, u8)> = dps_support_pairs.1.clone();
-
-                    let full_matchup: (Vec<(u8, u8)>, Vec<(u8, u8)>, Vec<(u8, u8)>) =
-                        (tank_pairs_in_this_matchup.clone(), dps_pairs, support_pairs);
-
-                    matchups_this_thread.push(full_matchup);
-                }
-                counter += 1;
-                if (counter % 10) == 0 {
-                    cloned_arc.lock().unwrap().1.inc(10);
-                }
-            }
-            cloned_arc.lock().unwrap().0.push(matchups_this_thread);
+                .any(|query| scrim_id::id_matches(&context.id, query))
         });
-        handles.push(handle);
     }
 
-    for handle in handles {
-        handle.join().unwrap();
-    }
+    let map_pool = match options.maps_file.as_deref() {
+        Some("default") => Some(MapPool::default_pool()),
+        Some(maps_file) => Some(MapPool::from_file(maps_file)?),
+        None => None,
+    };
 
-    let matchup_vector = arc.lock().unwrap().0.clone();
-    let mut matchups: Vec<(Vec<(u8, u8)>, Vec<(u8, u8)>, Vec<(u8, u8)>)> = Vec::new();
-    for mut matchup_chunk in matchup_vector {
-        matchups.append(&mut matchup_chunk);
+    if options.tui {
+        return tui::run(contexts, &roster, options.penalties(), |contexts| {
+            write_scrims(
+                &options,
+                &renderer,
+                map_pool.as_ref(),
+                contexts.to_vec(),
+                player_map,
+            )
+        });
     }
-
-    // println!("There are {} possible matchups", matchups.len());
-
-    matchups
+    write_scrims(&options, &renderer, map_pool.as_ref(), contexts, player_map)
 }
 
-fn create_ow2_scrims(
-    players_raw: &HashMap<u8, Player>,
-    matchups: Vec<(Vec<u8>, Vec<(u8, u8)>, Vec<(u8, u8)>)>,
-    number_of_teams: usize,
-    team_names_raw: Vec<String>,
-    number_of_threads: usize,
-    team_permutations: Vec<Vec<Vec<usize>>>
-) -> Vec<OW2Matchup> {
-    let scrims: Vec<Vec<OW2Matchup>> = Vec::new();
-    
-    let all_player_vec: Vec<u8> = players_raw.keys().cloned().collect();
-
-    println!("{:?}", team_names_raw);
-
-    let number_of_matchups = matchups.len();
-
-    let scrim_progress_bar = ProgressBar::new(number_of_matchups as u64);
-    scrim_progress_bar.reset();
-
-    let mut chunk_size = number_of_matchups / number_of_threads;
-
-    if chunk_size == 0 {
-        chunk_size = 1;
-    }
-
-    let matchup_chunks = matchups.into_iter().chunks(chunk_size);
-
-    let mut handles = vec![];
-
-    let mutex = Mutex::new((scrims, scrim_progress_bar));
-    let arc = Arc::new(mutex);
-
-    for matchup_chunk in matchup_chunks.into_iter() {
-        let matchup_chunk: Vec<(Vec<u8>, Vec<(u8, u8)>, Vec<(u8, u8)>)> = matchup_chunk.collect();
-
-        let all_player_set: HashSet<u8> = HashSet::from_iter(all_player_vec.clone().into_iter());
-        let cloned_arc = Arc::clone(&arc);
-        let players = players_raw.clone();
-        let team_names = team_names_raw.clone();
-        let team_perms = team_permutations.clone();
-
-        let handle = thread::spawn(move || {
-            let mut all_scrims: Vec<OW2Matchup> = Vec::new();
-            let mut counter = 0;
-
-            let mut best_rating: i16 = i16::MAX;
-
-            for possible_matchup in matchup_chunk {
-                let tank_vec = &possible_matchup.0;
-                let damage_vec = &possible_matchup.1;
-                let support_vec = &possible_matchup.2;
-
-                let dps_iter = (0..number_of_teams).permutations(number_of_teams);
-                let supp_iter = (0..number_of_teams).permutations(number_of_teams);
-
-                for dps_perm in dps_iter {
-                    for supp_perm in supp_iter.clone() {
-                        let mut matchup_teams: Vec<(String, u8, u8, u8, u8, u8)> = Vec::new();
-                        for i in 0..number_of_teams {
-                            matchup_teams.push((
-                                team_names[i].clone(),
-                                *tank_vec.get(i).unwrap(),
-                                damage_vec.get(dps_perm[i]).unwrap().0,
-                                damage_vec.get(dps_perm[i]).unwrap().1,
-                                support_vec.get(supp_perm[i]).unwrap().0,
-                                support_vec.get(supp_perm[i]).unwrap().1,
-                            ));
-                        }
-                        
-                        let mut players_playing: Vec<u8> = Vec::new();
-                        let m_clone = matchup_teams.clone();
-                        
-                        for m_team in m_clone {
-                            players_playing.push(m_team.1);
-                            players_playing.push(m_team.2);
-                            players_playing.push(m_team.3);
-                            players_playing.push(m_team.4);
-                            players_playing.push(m_team.5);
-                        }
-                        
-                        // println!("all_player_set: {:?}", all_player_set);
-                    
-                        let playing_players_set: HashSet<u8> = HashSet::from_iter(players_playing.into_iter());
-                        // println!("playing_players_set: {:?}", playing_players_set);
-
-                        let players_on_bench: HashSet<_> = all_player_set.difference(&playing_players_set).collect();
-                        // println!("players_on_bench: {:?}", players_on_bench);
-                        // println!();
-
-                        let players_left_over_vec: Vec<_> = players_on_bench.into_iter().cloned().collect();
-
-                        if number_of_teams <= 3 {
-                            let mut matchup = OW2Matchup::new(matchup_teams, &players);
-                            let rating = matchup.rating;
-
-                            matchup.players_left_over = players_left_over_vec;
-
-                            if rating < (best_rating as f32) as i16 {
-                                all_scrims.push(matchup);
-                                best_rating = cmp::min(rating, best_rating);
-                            }
-                        } else {
-                            for permutation in &team_perms {
-                                let mut rating = 0;
-                                let mut this_permutation_matchups: Vec<(String, u8, u8, u8, u8, u8)> = Vec::new();
-
-                                for perm in permutation {
-                                    let mut this_matchup_teams: Vec<(String, u8, u8, u8, u8, u8)> = Vec::new();
-                                    for i in perm {
-                                        this_matchup_teams.push(matchup_teams[*i-1].clone());
-                                        this_permutation_matchups.push(matchup_teams[*i-1].clone());
-                                    }
-                                    let mut matchup = OW2Matchup::new(this_matchup_teams, &players);
-
-                                    matchup.players_left_over = players_left_over_vec.clone();
-                                    rating += matchup.rating;
-                                }
-                                if rating < (best_rating as f32) as i16 {
-                                    all_scrims.push(OW2Matchup::new(matchup_teams.clone(), &players));
-                                    best_rating = cmp::min(rating, best_rating);
-                                }
-                            }
-                        }
-                    }
-                }
-                counter += 1;
-                if (counter % 1000) == 0 {
-                    cloned_arc.lock().unwrap().1.inc(1000);
-                }
+// Writes the scrims to the output file and the other outputs that were asked for
+fn write_scrims(
+    options: &Options,
+    renderer: &ScrimRenderer,
+    map_pool: Option<&MapPool>,
+    mut contexts: Vec<ScrimContext>,
+    player_map: &HashMap<u8, Player>,
+) -> Result<()> {
+    if options.schedule {
+        for context in contexts.iter_mut() {
+            context.schedule = schedule::round_robin(&context.teams, options.number_of_rounds);
+            if let Some(map_pool) = map_pool {
+                map_pool.assign(&mut context.schedule, options.maps_per_round)?;
             }
-            let all_scrims_vector = &mut cloned_arc.lock().unwrap().0;
-            all_scrims_vector.push(all_scrims);
-        });
-        handles.push(handle);
+        }
     }
 
-    for handle in handles {
-        handle.join().unwrap();
+    let mut file = File::create(&options.output_file)?;
+    for context in &contexts {
+        file.write_all(renderer.render(context)?.as_bytes())?;
     }
-
-    let scrim_vector = arc.lock().unwrap().0.clone();
-    let mut scrims: Vec<OW2Matchup> = Vec::new();
-    for mut scrim_chunk in scrim_vector {
-        scrims.append(&mut scrim_chunk);
+    if options.series.is_some() {
+        let summary = series::get_summary_string(&contexts, player_map, &options.session);
+        print!("{}", summary);
+        file.write_all(summary.as_bytes())?;
     }
 
-    arc.lock().unwrap().1.finish();
-
-    println!("\nTotal number of scrims: {}", scrims.len());
-    scrims.sort_by(|a, b| a.rating.partial_cmp(&b.rating).unwrap());
-
-    scrims
-}
-
-fn create_ow2_matchups(
-    player_duos: &HashMap<Position, Vec<(u8, u8)>>,
-    number_of_teams: usize,
-    number_of_threads: usize
-) -> Vec<(Vec<u8>, Vec<(u8, u8)>, Vec<(u8, u8)>)> {
-    let position_vec = vec![Position::Tank, Position::Damage, Position::Support];
-
-    println!("Number of teams: {}", number_of_teams);
-
-    let mut combination_map: HashMap<Position, Vec<Vec<(u8, u8)>>> = HashMap::new();
-
-    for position in &position_vec {
-        let combinations_crowded = player_duos[&position]
-            .iter()
-            .combinations(number_of_teams as usize);
-        println!(
-            "There are {} possible {:?} combinations",
-            combinations_crowded.clone().count(),
-            position
-        );
-
-        let mut combinations_vec: Vec<Vec<&(u8, u8)>> = combinations_crowded.collect();
-
-        combinations_vec.retain(|c| {
-            let mut player_ids: Vec<u8> = Vec::new();
-            for player in c {
-                player_ids.push(player.0);
-                player_ids.push(player.1);
-            }
-            position == &Position::Tank || (player_ids.iter().unique().count() == player_ids.len())
-        });
-
-        println!("{} of those are unique", combinations_vec.len());
-
-        let mut clean_combinations: Vec<Vec<(u8, u8)>> = Vec::new();
-
-        for combination in &combinations_vec {
-            let mut clean_combination: Vec<(u8, u8)> = Vec::new();
-            for player_vec in combination {
-                clean_combination.push((player_vec.0, player_vec.1));
-            }
-            clean_combinations.push(clean_combination);
-        }
-        println!();
-
-        combination_map.insert(*position, clean_combinations);
+    if let Some(html_file) = &options.html_file {
+        fs::write(html_file, html::render_report(&contexts)?)?;
     }
 
-    let number_of_tank_combinations = combination_map[&Position::Tank].len();
-
-    let mut all_dps_supp_combinations: Vec<(Vec<(u8, u8)>, Vec<(u8, u8)>)> = Vec::new();
-
-
-    // First calculating all possible Damage & Support pairings
-    'damage_outer_loop: for damage_pairs_this_matchup in &combination_map[&Position::Damage] {
-        let mut dps_players_seen_this_run: Vec<u8> = Vec::new();
-        for dps_duo in damage_pairs_this_matchup {
-            dps_players_seen_this_run.push(dps_duo.0);
-            dps_players_seen_this_run.push(dps_duo.1);
-        }
-        // Check if all names are unique
-        if dps_players_seen_this_run.iter().unique().count() != dps_players_seen_this_run.len() {
-            continue 'damage_outer_loop;
-        }
-        // All players are unique so far, we can now pair these with Support players
-        'support_inner_loop: for support_pairs_this_matchup in &combination_map[&Position::Support]
-        {
-            let mut dps_and_support_players_seen_this_run = dps_players_seen_this_run.clone();
-            for support_duo in support_pairs_this_matchup {
-                dps_and_support_players_seen_this_run.push(support_duo.0);
-                dps_and_support_players_seen_this_run.push(support_duo.1);
-            }
-            // Check if all names are unique
-            if dps_and_support_players_seen_this_run
-                .iter()
-                .unique()
-                .count()
-                != dps_and_support_players_seen_this_run.len()
-            {
-                continue 'support_inner_loop;
-            }
-
-            // Looks like a possible tank / support matchup has been found, it can now be created and appended to the list of all combinations
-            let mut dps_supp_combination: (Vec<(u8, u8)>, Vec<(u8, u8)>) = (Vec::new(), Vec::new());
-            dps_supp_combination.0 = damage_pairs_this_matchup.clone();
-            dps_supp_combination.1 = support_pairs_this_matchup.clone();
-            all_dps_supp_combinations.push(dps_supp_combination);
+    if let Some(card_directory) = &options.card_directory {
+        fs::create_dir_all(card_directory)?;
+        for context in &contexts {
+            let card_file = Path::new(card_directory).join(format!("scrim_{}.png", context.label));
+            fs::write(card_file, card::render_card(context)?)?;
         }
     }
 
+    Ok(())
+}
 
-    let matchup_vec: Vec<Vec<(Vec<u8>, Vec<(u8, u8)>, Vec<(u8, u8)>)>> = Vec::new();
-    //                      | Tanks    DPS Duos       Support Duos  |
-    //                      +---------------------------------------+
-    //                      |  One Matchup                          |
-
-    let tank_progress_bar = ProgressBar::new(number_of_tank_combinations as u64);
-    tank_progress_bar.reset();
-
-    let mut chunk_size = number_of_tank_combinations / number_of_threads;
-
-    if chunk_size == 0 {
-        chunk_size = 1;
-    }
-
-    // Copy all tanks from combination_map into a Vector, only retaining the first player of each duo
-    let clean_tank_vec: Vec<Vec<u8>> = combination_map[&Position::Tank]
+// A scrim is referenced either by a file holding it or by its id in the scrims file
+fn find_scrim(
+    reference: &str,
+    scrims_file: &str,
+    player_map: &HashMap<u8, Player>,
+) -> Result<ScrimContext> {
+    if Path::new(reference).is_file() {
+        let scrims = reader::read_scrims_file(reference, player_map)?;
+        return scrims
+            .first()
+            .map(|scrim| scrim.get_context(player_map))
+            .ok_or_else(|| anyhow!("There is no scrim in {}", reference));
+    }
+
+    let mut found: Vec<ScrimContext> = reader::read_scrims_file(scrims_file, player_map)?
         .iter()
-        .map(|c| c.iter().map(|d| d.0).collect())
+        .map(|scrim| scrim.get_context(player_map))
+        .filter(|context| scrim_id::id_matches(&context.id, reference))
         .collect();
-
-    println!("Chunk size: {}", chunk_size);
-
-    let tank_chunks = clean_tank_vec.into_iter().chunks(chunk_size);
-
-    let mut handles = vec![];
-
-    let mutex = Mutex::new((matchup_vec, tank_progress_bar));
-    let arc = Arc::new(mutex);
-
-    for tank_chunk in tank_chunks.into_iter() {
-        let tank_chunk: Vec<Vec<u8>> = tank_chunk.collect();
-
-        let dps_supp_combinations = all_dps_supp_combinations.clone();
-
-        let cloned_arc = Arc::clone(&arc);
-
-        let handle = thread::spawn(move || {
-            let mut matchups_this_thread: Vec<(Vec<u8>, Vec<(u8, u8)>, Vec<(u8, u8)>)> = Vec::new();
-            let mut counter = 0;
-
-            for tank_pairs_in_this_matchup in tank_chunk {
-                let mut tank_names: Vec<u8> = Vec::new();
-                for tank in &tank_pairs_in_this_matchup {
-                    tank_names.push(*tank);
-                }
-
-                'dps_support_loop: for dps_support_pairs in &dps_supp_combinations {
-                    let mut names_in_this_matchup = tank_names.clone();
-                    // Add the DPS pairs
-                    for pair in &dps_support_pairs.0 {
-                        names_in_this_matchup.push(pair.0);
-                        names_in_this_matchup.push(pair.1);
-                    }
-                    // Add the Support pairs
-                    for pair in &dps_support_pairs.1 {
-                        names_in_this_matchup.push(pair.0);
-                        names_in_this_matchup.push(pair.1);
-                    }
-
-                    // Check if any names are double
-                    if names_in_this_matchup.iter().unique().count() != names_in_this_matchup.len()
-                    {
-                        continue 'dps_support_loop;
-                    }
-
-                    // No names are double, this is a possibly valid matchup
-                    let dps_pairs: Vec<(u8, u8)> = dps_support_pairs.0.clone();
-                    let support_pairs: Vec<(u8, u8)> = dps_support_pairs.1.clone();
-
-                    let full_matchup: (Vec<u8>, Vec<(u8, u8)>, Vec<(u8, u8)>) =
-                        (tank_pairs_in_this_matchup.clone(), dps_pairs, support_pairs);
-
-                    matchups_this_thread.push(full_matchup);
-                }
-                counter += 1;
-                if (counter % 10) == 0 {
-                    cloned_arc.lock().unwrap().1.inc(10);
-                }
-            }
-            cloned_arc.lock().unwrap().0.push(matchups_this_thread);
-        });
-        handles.push(handle);
+    match found.len() {
+        0 => Err(anyhow!(
+            "There is no scrim {} in {}",
+            reference,
+            scrims_file
+        )),
+        1 => Ok(found.remove(0)),
+        _ => Err(anyhow!("The scrim id {} is ambiguous", reference)),
     }
-
-    for handle in handles {
-        handle.join().unwrap();
-    }
-
-    let matchup_vector = arc.lock().unwrap().0.clone();
-    let mut matchups: Vec<(Vec<u8>, Vec<(u8, u8)>, Vec<(u8, u8)>)> = Vec::new();
-    for mut matchup_chunk in matchup_vector {
-        matchups.append(&mut matchup_chunk);
-    }
-
-    // println!("There are {} possible matchups", matchups.len());
-
-    matchups
 }
@@ -0,0 +1,92 @@
+use crate::context::{MapContext, RoundContext};
+use anyhow::{anyhow, Result};
+use std::collections::HashSet;
+use std::fs;
+
+// Map pool shipped with the tool, the same as maps.txt
+const DEFAULT_MAP_POOL: &str = include_str!("../maps.txt");
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MapPool {
+    // (Mode, maps of the mode) in the order the modes rotate
+    pub modes: Vec<(String, Vec<String>)>,
+}
+
+impl MapPool {
+    pub fn default_pool() -> MapPool {
+        MapPool::parse(DEFAULT_MAP_POOL).unwrap()
+    }
+
+    pub fn from_file(maps_file: &str) -> Result<MapPool> {
+        MapPool::parse(&fs::read_to_string(maps_file)?)
+    }
+
+    // Every line that doesn't start with # looks like "Control: Busan, Ilios, Nepal"
+    pub fn parse(text: &str) -> Result<MapPool> {
+        let mut modes: Vec<(String, Vec<String>)> = Vec::new();
+        for line in text.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (mode, maps) = line
+                .split_once(": ")
+                .ok_or_else(|| anyhow!("Map pool line without a mode: {}", line))?;
+            let maps: Vec<String> = maps
+                .split(", ")
+                .map(|map| map.trim().to_string())
+                .filter(|map| !map.is_empty())
+                .collect();
+            if !maps.is_empty() {
+                modes.push((mode.trim().to_string(), maps));
+            }
+        }
+        if modes.is_empty() {
+            return Err(anyhow!("The map pool is empty"));
+        }
+        Ok(MapPool { modes })
+    }
+
+    // Gives every round its maps. The modes rotate from map to map, so by default every round,
+    // and with it every pairing of teams, plays each mode once. Maps don't repeat within the session
+    // until all maps of a mode have been played. Fewer maps per round than modes would leave
+    // pairings without some modes, so that is an error.
+    pub fn assign(&self, rounds: &mut [RoundContext], maps_per_round: Option<usize>) -> Result<()> {
+        let maps_per_round = maps_per_round.unwrap_or(self.modes.len());
+        if maps_per_round < self.modes.len() {
+            return Err(anyhow!(
+                "The map pool has {} modes, a round needs at least {} maps to play each of them",
+                self.modes.len(),
+                self.modes.len()
+            ));
+        }
+        let mut used_maps: HashSet<&str> = HashSet::new();
+        let mut next_mode = 0;
+
+        for round in rounds.iter_mut() {
+            round.maps.clear();
+            for _ in 0..maps_per_round {
+                let (mode, maps) = &self.modes[next_mode % self.modes.len()];
+                next_mode += 1;
+
+                // Start over with the maps of this mode once all of them have been played
+                if maps.iter().all(|map| used_maps.contains(map.as_str())) {
+                    for map in maps {
+                        used_maps.remove(map.as_str());
+                    }
+                }
+                let map = maps
+                    .iter()
+                    .find(|map| !used_maps.contains(map.as_str()))
+                    .unwrap();
+                used_maps.insert(map);
+
+                round.maps.push(MapContext {
+                    mode: mode.clone(),
+                    name: map.clone(),
+                });
+            }
+        }
+        Ok(())
+    }
+}
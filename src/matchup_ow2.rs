@@ -1,8 +1,20 @@
+use crate::context::{ScrimContext, TeamContext, TeamRoleContext};
+use crate::heroes::team_conflicts;
+use crate::languages;
+use crate::player::duo_sub_roles;
+use crate::scrim_id::scrim_id;
+use crate::stacks::{stack_rating_change, team_bonuses};
+use crate::types::Penalties;
 use crate::OW2Team;
 use crate::Player;
 use crate::Position;
 use std::collections::HashMap;
 
+// (Team name, Tank, Damage 1, Damage 2, Support 1, Support 2)
+pub type OW2TeamComp = (String, u8, u8, u8, u8, u8);
+// The same team with its average SR appended
+pub type OW2ExtendedTeam = (String, u8, u8, u8, u8, u8, f32);
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum MatchupKind {
     TwoWayScrim,
@@ -12,7 +24,7 @@ pub enum MatchupKind {
 #[derive(Debug, Clone, PartialEq)]
 pub struct OW2Matchup {
     pub kind: MatchupKind,
-    pub extended_teams: Vec<(String, u8, u8, u8, u8, u8, f32)>,
+    pub extended_teams: Vec<OW2ExtendedTeam>,
     pub sr_average: f32,
     pub full_role_average: [f32; 3],
     pub standard_deviations: [f32; 3],
@@ -22,10 +34,7 @@ pub struct OW2Matchup {
 }
 
 impl OW2Matchup {
-    pub fn new(
-        teams: Vec<(String, u8, u8, u8, u8, u8)>,
-        players: &HashMap<u8, Player>,
-    ) -> OW2Matchup {
+    pub fn new(teams: Vec<OW2TeamComp>, players: &HashMap<u8, Player>) -> OW2Matchup {
         let kind = if teams.len() == 2 {
             MatchupKind::TwoWayScrim
         } else {
@@ -33,7 +42,7 @@ impl OW2Matchup {
         };
 
         let mut created_teams: Vec<OW2Team> = Vec::new();
-        let mut extended_teams: Vec<(String, u8, u8, u8, u8, u8, f32)> = Vec::new();
+        let mut extended_teams: Vec<OW2ExtendedTeam> = Vec::new();
         for team_comp in &teams {
             let team = OW2Team::new(
                 team_comp.0.clone(),
@@ -62,7 +71,6 @@ impl OW2Matchup {
         let mut full_role_average: [f32; 3] = [0.0; 3];
         let mut standard_deviations: [f32; 3] = [0.0; 3];
         let mut average_deviations: [f32; 3] = [0.0; 3];
-        let scrim_average_sr: f32;
         let mut team_srs: Vec<f32> = Vec::new();
 
         let position_vec = vec![Position::Tank, Position::Damage, Position::Support];
@@ -71,23 +79,23 @@ impl OW2Matchup {
             let team_sr = team.get_average_sr();
             team_srs.push(team_sr);
         }
-        scrim_average_sr = team_srs.iter().sum::<f32>() / number_of_teams;
+        let scrim_average_sr = team_srs.iter().sum::<f32>() / number_of_teams;
 
         let average_variance = team_srs
             .iter()
             .map(|x| (x - scrim_average_sr).powi(2))
             .sum::<f32>()
             / number_of_teams;
-        let sr_average_deviation = average_variance.sqrt();
+        let _sr_average_deviation = average_variance.sqrt();
 
         let mut sum_of_all_dev_diffs: f32 = 0.0;
         let mut sum_of_all_avg_diffs: f32 = 0.0;
 
         for position in &position_vec {
             for team in &created_teams {
-                averages[*position as usize] += team.get_average_sr_of_role_duo(position.clone());
+                averages[*position as usize] += team.get_average_sr_of_role_duo(*position);
                 standard_deviations[*position as usize] +=
-                    team.get_standard_deviation_of_role_duo(position.clone());
+                    team.get_standard_deviation_of_role_duo(*position);
             }
 
             full_role_average[*position as usize] = averages[*position as usize] / number_of_teams;
@@ -95,19 +103,19 @@ impl OW2Matchup {
                 standard_deviations[*position as usize] / number_of_teams;
 
             for team in &created_teams {
-                let dev_diff = (team.get_standard_deviation_of_role_duo(position.clone())
+                let dev_diff = (team.get_standard_deviation_of_role_duo(*position)
                     - average_deviations[*position as usize])
                     .abs();
                 sum_of_all_dev_diffs += dev_diff;
-                let avg_diff = (team.get_average_sr_of_role_duo(position.clone())
+                let avg_diff = (team.get_average_sr_of_role_duo(*position)
                     - full_role_average[*position as usize])
                     .abs();
                 sum_of_all_avg_diffs += avg_diff;
             }
         }
 
-        // let rating = (sum_of_all_dev_diffs + sr_average_deviation) as i16;
-        let rating = (sum_of_all_dev_diffs + sum_of_all_avg_diffs*5.0) as i16;
+        // let rating = (sum_of_all_dev_diffs + _sr_average_deviation) as i16;
+        let rating = (sum_of_all_dev_diffs + sum_of_all_avg_diffs * 5.0) as i16;
 
         OW2Matchup {
             kind,
@@ -161,13 +169,13 @@ impl OW2Matchup {
             for team in &created_teams {
                 extended_string.push_str(&format!(
                     "{: >25.1}",
-                    team.get_average_sr_of_role_duo(position.clone())
+                    team.get_average_sr_of_role_duo(*position)
                 ));
             }
-            extended_string.push_str("\n");
+            extended_string.push('\n');
             extended_string.push_str("Avg Diff");
             for team in &created_teams {
-                let avg_diff = (team.get_average_sr_of_role_duo(position.clone())
+                let avg_diff = (team.get_average_sr_of_role_duo(*position)
                     - self.full_role_average[*position as usize])
                     .abs();
                 extended_string.push_str(&format!("{: >25.1}", avg_diff));
@@ -178,19 +186,19 @@ impl OW2Matchup {
             for team in &created_teams {
                 extended_string.push_str(&format!(
                     "{: >25.1}",
-                    team.get_standard_deviation_of_role_duo(position.clone())
+                    team.get_standard_deviation_of_role_duo(*position)
                 ));
             }
-            extended_string.push_str("\n");
+            extended_string.push('\n');
             extended_string.push_str("Dev Diff");
             for team in &created_teams {
-                let dev_diff = (team.get_standard_deviation_of_role_duo(position.clone())
+                let dev_diff = (team.get_standard_deviation_of_role_duo(*position)
                     - self.average_deviations[*position as usize])
                     .abs();
                 extended_string.push_str(&format!("{: >25.1}", dev_diff));
                 sum_of_all_dev_diff += dev_diff;
             }
-            extended_string.push_str("\n");
+            extended_string.push('\n');
         }
 
         extended_string.push_str("\n----------------------------------------\n");
@@ -207,6 +215,211 @@ impl OW2Matchup {
         extended_string
     }
 
+    // Content-derived id of the lineup, the same for every order of the teams
+    pub fn get_id(&self, players: &HashMap<u8, Player>) -> String {
+        let mut team_ids: Vec<String> = Vec::new();
+        for team in &self.extended_teams {
+            let created_team = OW2Team::new(
+                team.0.clone(),
+                players[&team.1].clone(),
+                players[&team.2].clone(),
+                players[&team.3].clone(),
+                players[&team.4].clone(),
+                players[&team.5].clone(),
+            );
+            team_ids.push(created_team.get_id());
+        }
+        scrim_id(team_ids)
+    }
+
+    pub fn get_context(&self, label: &str, players: &HashMap<u8, Player>) -> ScrimContext {
+        let position_vec = vec![Position::Tank, Position::Damage, Position::Support];
+
+        let mut teams: Vec<TeamContext> = Vec::new();
+        for team in &self.extended_teams {
+            let created_team = OW2Team::new(
+                team.0.clone(),
+                players[&team.1].clone(),
+                players[&team.2].clone(),
+                players[&team.3].clone(),
+                players[&team.4].clone(),
+                players[&team.5].clone(),
+            );
+            let slots: [Vec<u8>; 3] = [vec![team.1], vec![team.2, team.3], vec![team.4, team.5]];
+
+            let mut roles: Vec<TeamRoleContext> = Vec::new();
+            for position in &position_vec {
+                roles.push(TeamRoleContext::new(
+                    *position,
+                    slots[*position as usize]
+                        .iter()
+                        .map(|id| &players[id])
+                        .collect(),
+                    created_team.get_average_sr_of_role_duo(*position),
+                    created_team.get_standard_deviation_of_role_duo(*position),
+                    self.full_role_average[*position as usize],
+                    self.average_deviations[*position as usize],
+                ));
+            }
+            let team_players: Vec<&Player> = [team.1, team.2, team.3, team.4, team.5]
+                .iter()
+                .map(|id| &players[id])
+                .collect();
+            let mut team_context = TeamContext::new(team.0.clone(), team.6, roles);
+            team_context.comms = languages::get_comms_string(&team_players);
+            teams.push(team_context);
+        }
+
+        let bench = self
+            .players_left_over
+            .iter()
+            .map(|id| players[id].name.clone())
+            .collect();
+
+        let mut context = ScrimContext::new(
+            label,
+            &self.get_id(players),
+            "OW2Matchup",
+            self.sr_average,
+            self.rating,
+            self.full_role_average,
+            self.average_deviations,
+            teams,
+            bench,
+        );
+        context.hero_conflicts = self.get_hero_conflicts(players);
+        context.sub_role_clashes = self.get_sub_role_clashes(players);
+        context.language_conflicts = self.get_language_conflicts(players);
+        context.has_languages = players.values().any(|player| !player.languages.is_empty());
+        context
+    }
+
+    // Duos whose players can't take both sub-roles of their position
+    pub fn get_sub_role_clashes(&self, players: &HashMap<u8, Player>) -> Vec<String> {
+        let mut clashes: Vec<String> = Vec::new();
+        for team in &self.extended_teams {
+            let duos = [
+                (Position::Damage, vec![&players[&team.2], &players[&team.3]]),
+                (
+                    Position::Support,
+                    vec![&players[&team.4], &players[&team.5]],
+                ),
+            ];
+            for (position, duo) in duos.iter() {
+                let (sub_roles, clash) = duo_sub_roles(duo, *position);
+                if clash {
+                    let sub_role = sub_roles[0]
+                        .or(sub_roles[1])
+                        .map(|sub_role| sub_role.name())
+                        .unwrap_or("");
+                    clashes.push(format!(
+                        "{}: {} and {} are both {}",
+                        team.0, duo[0].name, duo[1].name, sub_role
+                    ));
+                }
+            }
+        }
+        clashes
+    }
+
+    // Teams without a language all of their players speak
+    pub fn get_language_conflicts(&self, players: &HashMap<u8, Player>) -> Vec<String> {
+        self.extended_teams
+            .iter()
+            .filter_map(|team| {
+                let team_players: Vec<&Player> = [team.1, team.2, team.3, team.4, team.5]
+                    .iter()
+                    .map(|id| &players[id])
+                    .collect();
+                languages::team_conflict(&team.0, &team_players)
+            })
+            .collect()
+    }
+
+    // Players of every team
+    fn team_members(&self) -> Vec<Vec<u8>> {
+        self.extended_teams
+            .iter()
+            .map(|team| vec![team.1, team.2, team.3, team.4, team.5])
+            .collect()
+    }
+
+    // Average SR of every role of every team
+    fn role_averages(&self, players: &HashMap<u8, Player>) -> Vec<[f32; 3]> {
+        self.extended_teams
+            .iter()
+            .map(|team| {
+                let created_team = OW2Team::new(
+                    team.0.clone(),
+                    players[&team.1].clone(),
+                    players[&team.2].clone(),
+                    players[&team.3].clone(),
+                    players[&team.4].clone(),
+                    players[&team.5].clone(),
+                );
+                [Position::Tank, Position::Damage, Position::Support]
+                    .map(|position| created_team.get_average_sr_of_role_duo(position))
+            })
+            .collect()
+    }
+
+    // Extra rating for the hero conflicts, sub-role clashes and language conflicts of the scrim,
+    // and the change of the rating once the stack bonuses are added to the SR of the teams
+    pub fn get_penalty(&self, players: &HashMap<u8, Player>, penalties: Penalties) -> i16 {
+        let mut penalty: i16 = 0;
+        if penalties.hero_conflict > 0 {
+            let conflicts = self.get_hero_conflicts(players).len() as i16;
+            penalty = penalty.saturating_add(conflicts.saturating_mul(penalties.hero_conflict));
+        }
+        if penalties.sub_role_clash > 0 {
+            let clashes = self.get_sub_role_clashes(players).len() as i16;
+            penalty = penalty.saturating_add(clashes.saturating_mul(penalties.sub_role_clash));
+        }
+        if penalties.language_conflict > 0 {
+            let conflicts = self.get_language_conflicts(players).len() as i16;
+            penalty = penalty.saturating_add(conflicts.saturating_mul(penalties.language_conflict));
+        }
+        if penalties.stack_bonuses.iter().any(|bonus| *bonus != 0) {
+            penalty = penalty.saturating_add(stack_rating_change(
+                &self.team_members(),
+                &self.role_averages(players),
+                players,
+                &penalties.stack_bonuses,
+            ));
+        }
+        penalty
+    }
+
+    // Adds the penalties to the rating and the stack bonuses to the average SR of the teams
+    pub fn apply_penalties(&mut self, players: &HashMap<u8, Player>, penalties: Penalties) {
+        self.rating = self
+            .rating
+            .saturating_add(self.get_penalty(players, penalties));
+        let bonuses = team_bonuses(&self.team_members(), players, &penalties.stack_bonuses);
+        if bonuses.iter().any(|bonus| *bonus != 0.0) {
+            for (team, bonus) in self.extended_teams.iter_mut().zip(&bonuses) {
+                team.6 += bonus;
+            }
+            self.sr_average += bonuses.iter().sum::<f32>() / bonuses.len() as f32;
+        }
+    }
+
+    // Teams that can't field a distinct hero on every slot with the hero pools of their players
+    pub fn get_hero_conflicts(&self, players: &HashMap<u8, Player>) -> Vec<String> {
+        let mut conflicts: Vec<String> = Vec::new();
+        for team in &self.extended_teams {
+            let slots = [
+                (Position::Tank, &players[&team.1]),
+                (Position::Damage, &players[&team.2]),
+                (Position::Damage, &players[&team.3]),
+                (Position::Support, &players[&team.4]),
+                (Position::Support, &players[&team.5]),
+            ];
+            conflicts.append(&mut team_conflicts(&team.0, &slots));
+        }
+        conflicts
+    }
+
     pub fn get_pretty_string(&self, players: &HashMap<u8, Player>) -> String {
         let mut s = String::new();
         s.push_str("-------------------------------------\n");
@@ -225,54 +438,44 @@ impl OW2Matchup {
         for team in self.extended_teams.clone() {
             team_names.push_str(&format!("{: >25}", &team.0));
             team_sr_averages.push_str(&format!("{: >25.2}", &team.6));
-            tank_line.push_str(
-                &format!(
-                    "{: >25}",
-                    players.get(&team.1).unwrap().print_role(Position::Tank)
-                )
-                .as_str(),
-            );
-            damage_line_1.push_str(
-                &format!(
-                    "{: >25}",
-                    players.get(&team.2).unwrap().print_role(Position::Damage)
-                )
-                .as_str(),
-            );
-            damage_line_2.push_str(
-                &format!(
-                    "{: >25}",
-                    players.get(&team.3).unwrap().print_role(Position::Damage)
-                )
-                .as_str(),
-            );
-            support_line_1.push_str(
-                &format!(
-                    "{: >25}",
-                    players.get(&team.4).unwrap().print_role(Position::Support)
-                )
-                .as_str(),
-            );
-            support_line_2.push_str(
-                &format!(
-                    "{: >25}",
-                    players.get(&team.5).unwrap().print_role(Position::Support)
-                )
-                .as_str(),
-            );
+            tank_line.push_str(&format!(
+                "{: >25}",
+                players.get(&team.1).unwrap().print_role(Position::Tank)
+            ));
+            damage_line_1.push_str(&format!(
+                "{: >25}",
+                players.get(&team.2).unwrap().print_role(Position::Damage)
+            ));
+            damage_line_2.push_str(&format!(
+                "{: >25}",
+                players.get(&team.3).unwrap().print_role(Position::Damage)
+            ));
+            support_line_1.push_str(&format!(
+                "{: >25}",
+                players.get(&team.4).unwrap().print_role(Position::Support)
+            ));
+            support_line_2.push_str(&format!(
+                "{: >25}",
+                players.get(&team.5).unwrap().print_role(Position::Support)
+            ));
         }
 
         let mut leftover_players = String::new();
 
-        if self.players_left_over.len() == 0 {
+        if self.players_left_over.is_empty() {
             leftover_players.push_str("No players are left out");
-        }
-        else{
-            leftover_players.push_str(&format!("Leftover players: {}", players.get(self.players_left_over.first().unwrap()).unwrap().name));
-            
+        } else {
+            leftover_players.push_str(&format!(
+                "Leftover players: {}",
+                players
+                    .get(self.players_left_over.first().unwrap())
+                    .unwrap()
+                    .name
+            ));
+
             let mut skipped = false;
             for player_num in &self.players_left_over {
-                if !skipped { 
+                if !skipped {
                     skipped = true;
                     continue;
                 };
@@ -280,8 +483,6 @@ impl OW2Matchup {
             }
         };
 
-
-
         s.push_str(&format!("         {}\n", team_names));
         s.push_str(&format!("         {}\n", team_sr_averages));
         s.push_str(&format!("Tank:    {}\n", tank_line));
@@ -292,7 +493,7 @@ impl OW2Matchup {
 
         s.push_str("-------------------------------------\n");
         s.push_str(&leftover_players);
-        
+
         s.push_str("\n=====================================\n");
 
         s
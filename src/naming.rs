@@ -0,0 +1,243 @@
+use crate::context::ScrimContext;
+use anyhow::{anyhow, Result};
+use std::collections::HashSet;
+use std::fs;
+
+// Names of the teams. The search always runs with the default names,
+// the scrims are renamed afterwards so the names can depend on the finished lineups.
+
+const DEFAULT_NAMES: [&str; 6] = [
+    "Fighting Foxes",
+    "Dancing Dragons",
+    "Iron Wolves",
+    "Strike Team 404",
+    "Knightly Kittens",
+    "Suwon Tigers",
+];
+
+// (Theme, adjectives, nouns)
+const THEMES: [(&str, &[&str], &[&str]); 3] = [
+    (
+        "animals",
+        &[
+            "Fighting", "Dancing", "Iron", "Knightly", "Sneaky", "Roaring", "Golden", "Fluffy",
+            "Wild", "Grumpy",
+        ],
+        &[
+            "Foxes", "Wolves", "Kittens", "Tigers", "Otters", "Badgers", "Pandas", "Falcons",
+            "Geckos", "Ravens",
+        ],
+    ),
+    (
+        "mythic",
+        &[
+            "Dancing",
+            "Eternal",
+            "Cursed",
+            "Radiant",
+            "Ancient",
+            "Stormborn",
+            "Silent",
+            "Burning",
+            "Frozen",
+            "Hallowed",
+        ],
+        &[
+            "Dragons",
+            "Titans",
+            "Griffins",
+            "Phoenixes",
+            "Krakens",
+            "Valkyries",
+            "Golems",
+            "Sirens",
+            "Hydras",
+            "Wyverns",
+        ],
+    ),
+    (
+        "space",
+        &[
+            "Orbital", "Lunar", "Solar", "Stellar", "Cosmic", "Quantum", "Rogue", "Dark",
+            "Galactic", "Nova",
+        ],
+        &[
+            "Comets",
+            "Pulsars",
+            "Rockets",
+            "Voyagers",
+            "Nebulas",
+            "Quasars",
+            "Astronauts",
+            "Meteors",
+            "Satellites",
+            "Rovers",
+        ],
+    ),
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TeamNaming {
+    Default,
+    File(String),
+    Theme(String, u64),
+    Captains,
+}
+
+pub fn default_names(number_of_teams: usize) -> Vec<String> {
+    let mut team_names: Vec<String> = DEFAULT_NAMES.iter().map(|name| name.to_string()).collect();
+    for _ in team_names.len()..number_of_teams {
+        let team_name = format!("Team {}", team_names.len() + 1);
+        team_names.push(team_name);
+    }
+    team_names
+}
+
+pub fn theme_names() -> Vec<&'static str> {
+    THEMES.iter().map(|(theme, _, _)| *theme).collect()
+}
+
+// SplitMix64, the same seed always gives the same names
+fn next_random(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+pub struct TeamNamer {
+    naming: TeamNaming,
+    // Names that are handed out in order
+    names: Vec<String>,
+    used_names: HashSet<String>,
+}
+
+impl TeamNamer {
+    pub fn new(naming: &TeamNaming) -> Result<TeamNamer> {
+        let names: Vec<String> = match naming {
+            TeamNaming::Default => default_names(0),
+            TeamNaming::Captains => Vec::new(),
+            TeamNaming::File(names_file) => fs::read_to_string(names_file)?
+                .lines()
+                .map(|line| line.trim())
+                .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                .map(|line| line.to_string())
+                .collect(),
+            TeamNaming::Theme(theme, seed) => {
+                let (_, adjectives, nouns) = THEMES
+                    .iter()
+                    .find(|(name, _, _)| name == theme)
+                    .ok_or_else(|| {
+                        anyhow!(
+                            "Unknown theme {}, the themes are {}",
+                            theme,
+                            theme_names().join(", ")
+                        )
+                    })?;
+                let mut names: Vec<String> = Vec::new();
+                for adjective in adjectives.iter() {
+                    for noun in nouns.iter() {
+                        names.push(format!("{} {}", adjective, noun));
+                    }
+                }
+                // Fisher-Yates shuffle with the seeded generator
+                let mut state = *seed;
+                for i in (1..names.len()).rev() {
+                    let j = (next_random(&mut state) % (i as u64 + 1)) as usize;
+                    names.swap(i, j);
+                }
+                names
+            }
+        };
+
+        Ok(TeamNamer {
+            naming: naming.clone(),
+            names,
+            used_names: HashSet::new(),
+        })
+    }
+
+    // The next names of the list that haven't been used in this session yet,
+    // numbered names once the list is used up
+    pub fn next_names(&mut self, number_of_teams: usize) -> Vec<String> {
+        let mut team_names: Vec<String> = Vec::new();
+        let mut number = 1;
+        while team_names.len() < number_of_teams {
+            let name = match self
+                .names
+                .iter()
+                .find(|name| !self.used_names.contains(*name))
+            {
+                Some(name) => name.clone(),
+                None => {
+                    let name = format!("Team {}", number);
+                    number += 1;
+                    name
+                }
+            };
+            if self.used_names.insert(name.clone()) {
+                team_names.push(name);
+            }
+        }
+        team_names
+    }
+
+    // Names every team after its best player that hasn't been captain in this session yet
+    fn captain_names(&mut self, context: &ScrimContext) -> Vec<String> {
+        let mut team_names: Vec<String> = Vec::new();
+        for team in &context.teams {
+            let mut players: Vec<_> = team.players.iter().collect();
+            players.sort_by_key(|player| std::cmp::Reverse(player.sr));
+            let name = players
+                .iter()
+                .map(|player| format!("Team {}", player.name))
+                .find(|name| !self.used_names.contains(name))
+                .unwrap_or_else(|| self.next_names(1).remove(0));
+            self.used_names.insert(name.clone());
+            team_names.push(name);
+        }
+        team_names
+    }
+
+    pub fn is_default(&self) -> bool {
+        self.naming == TeamNaming::Default
+    }
+
+    // Renames the teams of one scrim
+    pub fn name_teams(&mut self, context: &mut ScrimContext) {
+        let team_names = match self.naming {
+            TeamNaming::Captains => self.captain_names(context),
+            _ => self.next_names(context.teams.len()),
+        };
+        for (i, name) in team_names.into_iter().enumerate() {
+            context.rename_team(i, &name);
+        }
+    }
+
+    // Alternative scrims of one session share their names, only the rounds of a series get new ones
+    pub fn name_alternatives(&mut self, contexts: &mut [ScrimContext]) {
+        match self.naming {
+            TeamNaming::Captains => {
+                for context in contexts.iter_mut() {
+                    let used_names = self.used_names.clone();
+                    self.name_teams(context);
+                    self.used_names = used_names;
+                }
+            }
+            _ => {
+                let number_of_teams = contexts
+                    .iter()
+                    .map(|context| context.teams.len())
+                    .max()
+                    .unwrap_or(0);
+                let team_names = self.next_names(number_of_teams);
+                for context in contexts.iter_mut() {
+                    for (i, name) in team_names.iter().enumerate().take(context.teams.len()) {
+                        context.rename_team(i, name);
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,367 @@
+use crate::availability::{parse_time, Session};
+use crate::draft::DraftOrder;
+use crate::naming::TeamNaming;
+use crate::stacks::MAX_STACK_SIZE;
+use crate::types::Penalties;
+use crate::BalanceConfig;
+use anyhow::{anyhow, Result};
+use std::env;
+use std::time::Duration;
+
+// The options that only change the search, the ones a request from outside may give.
+// Everything that reads or writes files or picks a front end is left out.
+pub const SEARCH_OPTIONS: &[&str] = &[
+    "--series",
+    "--hero-penalty",
+    "--sub-role-penalty",
+    "--require-sub-roles",
+    "--language-penalty",
+    "--require-common-language",
+    "--session-start",
+    "--round-length",
+    "--team-theme",
+    "--seed",
+    "--captain-names",
+    "--time-limit",
+    "--stack-bonus",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Options {
+    pub players_file: String,
+    pub output_file: String,
+    pub ow_2: bool,
+    pub number_of_printed_scrims: usize,
+    pub number_of_threads: usize,
+    pub template_file: Option<String>,
+    pub html_file: Option<String>,
+    pub card_directory: Option<String>,
+    pub scrims_file: Option<String>,
+    pub scrim_ids: Vec<String>,
+    pub diff: Option<(String, String)>,
+    pub json: bool,
+    pub schedule: bool,
+    pub number_of_rounds: Option<usize>,
+    pub maps_file: Option<String>,
+    pub maps_per_round: Option<usize>,
+    pub series: Option<usize>,
+    pub draft: bool,
+    pub captains: Vec<String>,
+    pub draft_order: DraftOrder,
+    pub hero_penalty: i16,
+    pub sub_role_penalty: i16,
+    pub require_sub_roles: bool,
+    pub stack_bonuses: [i16; MAX_STACK_SIZE + 1],
+    pub language_penalty: i16,
+    pub require_common_language: bool,
+    pub session: Session,
+    pub team_names_file: Option<String>,
+    pub team_theme: Option<String>,
+    pub seed: u64,
+    pub captain_names: bool,
+    pub time_limit: Option<Duration>,
+    pub serve: Option<String>,
+    // Config of the chat bot, it then talks JSON lines over stdin and stdout
+    pub bot_config_file: Option<String>,
+    // Lets --serve listen on an address other machines can reach
+    pub allow_remote: bool,
+    pub tui: bool,
+    pub rpc: bool,
+}
+
+impl Default for Options {
+    fn default() -> Options {
+        Options {
+            players_file: "players.txt".to_string(),
+            output_file: "scrims.txt".to_string(),
+            ow_2: false,
+            number_of_printed_scrims: 10,
+            number_of_threads: 16,
+            template_file: None,
+            html_file: None,
+            card_directory: None,
+            scrims_file: None,
+            scrim_ids: Vec::new(),
+            diff: None,
+            json: false,
+            schedule: false,
+            number_of_rounds: None,
+            maps_file: None,
+            maps_per_round: None,
+            series: None,
+            draft: false,
+            captains: Vec::new(),
+            draft_order: DraftOrder::Snake,
+            hero_penalty: 0,
+            sub_role_penalty: 0,
+            require_sub_roles: false,
+            stack_bonuses: [0; MAX_STACK_SIZE + 1],
+            language_penalty: 0,
+            require_common_language: false,
+            session: Session {
+                start: None,
+                round_length: 30,
+            },
+            team_names_file: None,
+            team_theme: None,
+            seed: 0,
+            captain_names: false,
+            time_limit: None,
+            serve: None,
+            bot_config_file: None,
+            allow_remote: false,
+            tui: false,
+            rpc: false,
+        }
+    }
+}
+
+impl Options {
+    pub fn from_args() -> Result<Options> {
+        Options::parse(env::args().skip(1).collect())
+    }
+
+    pub fn penalties(&self) -> Penalties {
+        Penalties {
+            hero_conflict: self.hero_penalty,
+            sub_role_clash: self.sub_role_penalty,
+            language_conflict: self.language_penalty,
+            require_common_language: self.require_common_language,
+            stack_bonuses: self.stack_bonuses,
+        }
+    }
+
+    pub fn balance_config(&self) -> BalanceConfig {
+        BalanceConfig {
+            ow_2: self.ow_2,
+            number_of_scrims: self.number_of_printed_scrims,
+            number_of_threads: self.number_of_threads,
+            penalties: self.penalties(),
+            require_sub_roles: self.require_sub_roles,
+            session: self.session,
+            series: self.series,
+            team_naming: self.team_naming(),
+            time_limit: self.time_limit,
+        }
+    }
+
+    pub fn team_naming(&self) -> TeamNaming {
+        if self.captain_names {
+            TeamNaming::Captains
+        } else if let Some(team_names_file) = &self.team_names_file {
+            TeamNaming::File(team_names_file.clone())
+        } else if let Some(team_theme) = &self.team_theme {
+            TeamNaming::Theme(team_theme.clone(), self.seed)
+        } else {
+            TeamNaming::Default
+        }
+    }
+
+    // Like parse, but only with the options in SEARCH_OPTIONS
+    pub fn parse_search(args: Vec<String>) -> Result<Options> {
+        Options::check_search_args(&args)?;
+        Options::parse(args)
+    }
+
+    pub fn check_search_args(args: &[String]) -> Result<()> {
+        match args
+            .iter()
+            .find(|arg| arg.starts_with("--") && !SEARCH_OPTIONS.contains(&arg.as_str()))
+        {
+            Some(arg) => Err(anyhow!("{} is not a search option", arg)),
+            None => Ok(()),
+        }
+    }
+
+    pub fn parse(args: Vec<String>) -> Result<Options> {
+        let mut options = Options::default();
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--template" => {
+                    options.template_file = Some(
+                        args.next()
+                            .ok_or_else(|| anyhow!("--template needs a file name"))?,
+                    );
+                }
+                "--html" => {
+                    options.html_file = Some(
+                        args.next()
+                            .ok_or_else(|| anyhow!("--html needs a file name"))?,
+                    );
+                }
+                "--cards" => {
+                    options.card_directory = Some(
+                        args.next()
+                            .ok_or_else(|| anyhow!("--cards needs a directory"))?,
+                    );
+                }
+                "--from" => {
+                    options.scrims_file = Some(
+                        args.next()
+                            .ok_or_else(|| anyhow!("--from needs a file name"))?,
+                    );
+                }
+                "--scrim" => {
+                    options.scrim_ids.push(
+                        args.next()
+                            .ok_or_else(|| anyhow!("--scrim needs a scrim id"))?,
+                    );
+                }
+                "--diff" => {
+                    let from = args
+                        .next()
+                        .ok_or_else(|| anyhow!("--diff needs two scrims"))?;
+                    let to = args
+                        .next()
+                        .ok_or_else(|| anyhow!("--diff needs two scrims"))?;
+                    options.diff = Some((from, to));
+                }
+                "--json" => options.json = true,
+                "--schedule" => options.schedule = true,
+                "--rounds" => {
+                    let rounds = args
+                        .next()
+                        .ok_or_else(|| anyhow!("--rounds needs a number"))?;
+                    options.number_of_rounds = Some(rounds.parse()?);
+                    options.schedule = true;
+                }
+                "--maps" => {
+                    options.maps_file = Some(
+                        args.next()
+                            .ok_or_else(|| anyhow!("--maps needs a file name or default"))?,
+                    );
+                    options.schedule = true;
+                }
+                "--maps-per-round" => {
+                    let maps = args
+                        .next()
+                        .ok_or_else(|| anyhow!("--maps-per-round needs a number"))?;
+                    options.maps_per_round = Some(maps.parse()?);
+                }
+                "--series" => {
+                    let rounds = args
+                        .next()
+                        .ok_or_else(|| anyhow!("--series needs a number of rounds"))?;
+                    options.series = Some(rounds.parse()?);
+                }
+                "--draft" => options.draft = true,
+                "--captains" => {
+                    let captains = args
+                        .next()
+                        .ok_or_else(|| anyhow!("--captains needs a list of names"))?;
+                    options.captains = captains
+                        .split(',')
+                        .map(|name| name.trim().to_string())
+                        .collect();
+                    options.draft = true;
+                }
+                "--draft-order" => {
+                    let order = args
+                        .next()
+                        .ok_or_else(|| anyhow!("--draft-order needs snake or linear"))?;
+                    options.draft_order = match order.as_str() {
+                        "snake" => DraftOrder::Snake,
+                        "linear" => DraftOrder::Linear,
+                        _ => return Err(anyhow!("Unknown draft order: {}", order)),
+                    };
+                    options.draft = true;
+                }
+                "--hero-penalty" => {
+                    let penalty = args
+                        .next()
+                        .ok_or_else(|| anyhow!("--hero-penalty needs a number"))?;
+                    options.hero_penalty = penalty.parse()?;
+                }
+                "--sub-role-penalty" => {
+                    let penalty = args
+                        .next()
+                        .ok_or_else(|| anyhow!("--sub-role-penalty needs a number"))?;
+                    options.sub_role_penalty = penalty.parse()?;
+                }
+                "--require-sub-roles" => options.require_sub_roles = true,
+                "--language-penalty" => {
+                    let penalty = args
+                        .next()
+                        .ok_or_else(|| anyhow!("--language-penalty needs a number"))?;
+                    options.language_penalty = penalty.parse()?;
+                }
+                "--require-common-language" => options.require_common_language = true,
+                "--session-start" => {
+                    let start = args
+                        .next()
+                        .ok_or_else(|| anyhow!("--session-start needs a time like 19:00"))?;
+                    options.session.start = Some(parse_time(&start)?);
+                }
+                "--round-length" => {
+                    let length = args
+                        .next()
+                        .ok_or_else(|| anyhow!("--round-length needs a number of minutes"))?;
+                    options.session.round_length = length.parse()?;
+                }
+                "--team-names" => {
+                    options.team_names_file = Some(
+                        args.next()
+                            .ok_or_else(|| anyhow!("--team-names needs a file name"))?,
+                    );
+                }
+                "--team-theme" => {
+                    options.team_theme = Some(
+                        args.next()
+                            .ok_or_else(|| anyhow!("--team-theme needs a theme"))?,
+                    );
+                }
+                "--seed" => {
+                    let seed = args
+                        .next()
+                        .ok_or_else(|| anyhow!("--seed needs a number"))?;
+                    options.seed = seed.parse()?;
+                }
+                "--captain-names" => options.captain_names = true,
+                "--serve" => {
+                    options.serve =
+                        Some(args.next().ok_or_else(|| {
+                            anyhow!("--serve needs an address like 127.0.0.1:8080")
+                        })?);
+                }
+                "--bot" => {
+                    options.bot_config_file = Some(
+                        args.next()
+                            .ok_or_else(|| anyhow!("--bot needs a config file"))?,
+                    );
+                }
+                "--allow-remote" => options.allow_remote = true,
+                "--tui" => options.tui = true,
+                "--rpc" => options.rpc = true,
+                "--time-limit" => {
+                    let seconds = args
+                        .next()
+                        .ok_or_else(|| anyhow!("--time-limit needs a number of seconds"))?;
+                    options.time_limit = Some(
+                        Duration::try_from_secs_f64(seconds.parse()?)
+                            .map_err(|_| anyhow!("Invalid time limit: {}", seconds))?,
+                    );
+                }
+                "--stack-bonus" => {
+                    let bonuses = args
+                        .next()
+                        .ok_or_else(|| anyhow!("--stack-bonus needs bonuses like 2=50,3=100"))?;
+                    for bonus in bonuses.split(',') {
+                        let (size, bonus) = bonus
+                            .split_once('=')
+                            .ok_or_else(|| anyhow!("Stack bonus without a size: {}", bonus))?;
+                        let size: usize = size.trim().parse()?;
+                        if !(2..=MAX_STACK_SIZE).contains(&size) {
+                            return Err(anyhow!("Stacks have 2 to {} players", MAX_STACK_SIZE));
+                        }
+                        options.stack_bonuses[size] = bonus.trim().parse()?;
+                    }
+                }
+                _ => return Err(anyhow!("Unknown argument: {}", arg)),
+            }
+        }
+
+        Ok(options)
+    }
+}
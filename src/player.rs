@@ -1,3 +1,5 @@
+use crate::availability::Availability;
+use crate::types::SubRole;
 use crate::Position;
 use anyhow::{anyhow, Result};
 use std::iter::Iterator;
@@ -11,26 +13,100 @@ pub struct Player {
     pub plays_tank: bool,
     pub plays_damage: bool,
     pub plays_support: bool,
+    // Heroes the player plays, empty if the roster doesn't list a hero pool
+    pub heroes: Vec<String>,
+    // Sub-roles the player takes, none listed for a position means the player takes either one
+    pub sub_roles: Vec<SubRole>,
+    // Name of the pre-made stack the player queues with
+    pub stack: Option<String>,
+    // Languages the player speaks in comms, lowercase
+    pub languages: Vec<String>,
+    // Rounds or times the player can play, empty if the player stays for the whole session
+    pub availability: Vec<Availability>,
 }
 
 impl Player {
     pub fn new(player_string: String) -> Result<Player> {
         let split: Vec<&str> = player_string.split(" - ").collect();
         if split.len() < 5 {
-            println!(
+            return Err(anyhow!(
                 "Splitting Error with the following String: {}",
                 player_string
-            );
-            return Err(anyhow!("Splitting Error"));
+            ));
         }
         let name = split[0].to_string();
         // let name = split[0].to_string();
-        let tank_sr = split[1].parse::<i16>().unwrap();
-        let damage_sr = split[2].parse::<i16>().unwrap();
-        let support_sr = split[3].parse::<i16>().unwrap();
+        let parse_sr = |sr: &str| {
+            sr.trim()
+                .parse::<i16>()
+                .map_err(|_| anyhow!("Invalid SR {} of {}", sr, name))
+        };
+        let tank_sr = parse_sr(split[1])?;
+        let damage_sr = parse_sr(split[2])?;
+        let support_sr = parse_sr(split[3])?;
         let plays_tank = split[4].to_string().contains("t");
         let plays_damage = split[4].to_string().contains("d");
         let plays_support = split[4].to_string().contains("s");
+
+        // Optional fields after the roles look like "heroes=Winston,Ana"
+        let mut heroes: Vec<String> = Vec::new();
+        let mut sub_roles: Vec<SubRole> = Vec::new();
+        let mut stack: Option<String> = None;
+        let mut languages: Vec<String> = Vec::new();
+        let mut availability: Vec<Availability> = Vec::new();
+        for field in &split[5..] {
+            if let Some((key, value)) = field.split_once('=') {
+                match key.trim() {
+                    "heroes" => {
+                        heroes = value
+                            .split(',')
+                            .map(|hero| hero.trim().to_string())
+                            .filter(|hero| !hero.is_empty())
+                            .collect();
+                    }
+                    "subroles" => {
+                        for sub_role in value.split(',') {
+                            let sub_role = sub_role.trim();
+                            match SubRole::parse(sub_role) {
+                                Some(sub_role) => sub_roles.push(sub_role),
+                                None => {
+                                    return Err(anyhow!(
+                                        "Unknown sub-role {} of {}",
+                                        sub_role,
+                                        name
+                                    ));
+                                }
+                            }
+                        }
+                    }
+                    "stack" => stack = Some(value.trim().to_string()),
+                    "available" => {
+                        for window in value.split(',') {
+                            match Availability::parse(window) {
+                                Ok(window) => availability.push(window),
+                                Err(error) => {
+                                    return Err(anyhow!(
+                                        "Invalid availability {} of {}: {}",
+                                        window,
+                                        name,
+                                        error
+                                    ));
+                                }
+                            }
+                        }
+                    }
+                    "languages" => {
+                        languages = value
+                            .split(',')
+                            .map(|language| language.trim().to_lowercase())
+                            .filter(|language| !language.is_empty())
+                            .collect();
+                    }
+                    _ => {}
+                }
+            }
+        }
+
         let player = Player {
             name,
             tank_sr,
@@ -39,6 +115,11 @@ impl Player {
             plays_tank,
             plays_damage,
             plays_support,
+            heroes,
+            sub_roles,
+            stack,
+            languages,
+            availability,
         };
         Ok(player)
     }
@@ -51,6 +132,27 @@ impl Player {
         }
     }
 
+    // The sub-roles the player takes on the position, both of them if the roster lists none
+    pub fn get_sub_roles(&self, pos: Position) -> Vec<SubRole> {
+        let listed: Vec<SubRole> = self
+            .sub_roles
+            .iter()
+            .filter(|sub_role| sub_role.position() == pos)
+            .cloned()
+            .collect();
+        if listed.is_empty() {
+            SubRole::of_position(pos).to_vec()
+        } else {
+            listed
+        }
+    }
+
+    pub fn has_sub_role_for(&self, pos: Position) -> bool {
+        self.sub_roles
+            .iter()
+            .any(|sub_role| sub_role.position() == pos)
+    }
+
     pub fn plays_position(&self, pos: Position) -> bool {
         match pos {
             Position::Tank => self.plays_tank,
@@ -82,3 +184,50 @@ impl Player {
         }
     }
 }
+
+// Sub-roles of the two players of a duo, chosen so they complement each other where possible.
+// A player only gets a sub-role if the roster lists one for the position.
+// The flag is true if the duo can't be split into both sub-roles.
+pub fn duo_sub_roles(duo: &[&Player], pos: Position) -> (Vec<Option<SubRole>>, bool) {
+    let shown = |player: &Player, sub_role: SubRole| {
+        if player.has_sub_role_for(pos) {
+            Some(sub_role)
+        } else {
+            None
+        }
+    };
+
+    match duo {
+        [first, second] => {
+            let first_sub_roles = first.get_sub_roles(pos);
+            let second_sub_roles = second.get_sub_roles(pos);
+            for a in &first_sub_roles {
+                for b in &second_sub_roles {
+                    if a != b {
+                        return (vec![shown(first, *a), shown(second, *b)], false);
+                    }
+                }
+            }
+            (
+                vec![
+                    shown(first, first_sub_roles[0]),
+                    shown(second, second_sub_roles[0]),
+                ],
+                true,
+            )
+        }
+        _ => (
+            duo.iter()
+                .map(|player| {
+                    let sub_roles = player.get_sub_roles(pos);
+                    if sub_roles.len() == 1 {
+                        shown(player, sub_roles[0])
+                    } else {
+                        None
+                    }
+                })
+                .collect(),
+            false,
+        ),
+    }
+}
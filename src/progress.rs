@@ -0,0 +1,292 @@
+use crate::context::ScrimContext;
+#[cfg(not(target_arch = "wasm32"))]
+use indicatif::{ProgressBar, ProgressStyle};
+use std::sync::atomic::{AtomicBool, AtomicI16, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use web_time::Instant;
+
+// Progress of the search is reported as events to an observer, so the console, a TUI or a server
+// can show it their own way. The search can be stopped from the outside with a cancellation token
+// or by a deadline, it then returns the best scrims found so far.
+
+// Events of a phase are sent at most this often, the last one of a phase is always sent
+const REPORT_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    // Combining the tank, damage and support duos into matchups
+    Matchups,
+    // Splitting the matchups into teams and rating them
+    Scrims,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProgressEvent {
+    pub phase: Phase,
+    pub done: u64,
+    pub total: u64,
+    // Best rating found so far, only known while searching the scrims
+    pub best_rating: Option<i16>,
+    // Estimated time until the phase is finished, unknown until the first step is done
+    pub eta: Option<Duration>,
+}
+
+impl ProgressEvent {
+    pub fn is_finished(&self) -> bool {
+        self.done >= self.total
+    }
+}
+
+pub trait ProgressObserver: Send + Sync {
+    fn on_progress(&self, event: &ProgressEvent);
+
+    // Called whenever the search finds a scrim better than all before, in the order they are found
+    fn on_best_scrim(&self, _scrim: &ScrimContext) {}
+
+    // What the search has to say about the roster and its steps, like the number of matchups
+    fn on_message(&self, _message: &str) {}
+}
+
+// Ignores all events
+pub struct NoProgress;
+
+impl ProgressObserver for NoProgress {
+    fn on_progress(&self, _event: &ProgressEvent) {}
+}
+
+// A progress bar on the console for every phase
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Default)]
+pub struct ConsoleProgress {
+    bar: Mutex<Option<(Phase, ProgressBar)>>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl ProgressObserver for ConsoleProgress {
+    fn on_progress(&self, event: &ProgressEvent) {
+        let mut bar = self.bar.lock().unwrap();
+        if bar
+            .as_ref()
+            .map(|(phase, _)| *phase != event.phase)
+            .unwrap_or(true)
+        {
+            let progress_bar = ProgressBar::new(event.total);
+            progress_bar.set_style(
+                ProgressStyle::default_bar()
+                    .template("[{elapsed_precise}] {bar:40} {pos}/{len} {msg}"),
+            );
+            *bar = Some((event.phase, progress_bar));
+        }
+        let (_, progress_bar) = bar.as_ref().unwrap();
+        // Late events of other threads can arrive after the last one
+        if progress_bar.is_finished() {
+            return;
+        }
+
+        progress_bar.set_position(event.done);
+        let mut message = String::new();
+        if let Some(best_rating) = event.best_rating {
+            message.push_str(&format!("best rating {}", best_rating));
+        }
+        if let Some(eta) = event.eta.filter(|_| !event.is_finished()) {
+            if !message.is_empty() {
+                message.push_str(", ");
+            }
+            message.push_str(&format!("{}s left", eta.as_secs()));
+        }
+        progress_bar.set_message(message);
+        if event.is_finished() {
+            progress_bar.finish();
+        }
+    }
+
+    fn on_message(&self, message: &str) {
+        println!("{}", message);
+    }
+}
+
+// Shared between the caller and the search, cancelling stops all worker threads after their current step
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    pub fn new() -> CancellationToken {
+        CancellationToken::default()
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+}
+
+// What a search reports to and is stopped by
+#[derive(Clone)]
+pub struct SearchProgress {
+    pub observer: Arc<dyn ProgressObserver>,
+    pub cancel: CancellationToken,
+    // The search stops at this point in time
+    pub deadline: Option<Instant>,
+    // Whether the search stopped before it was done, set the first time it is told to stop
+    stopped: Arc<AtomicBool>,
+}
+
+impl SearchProgress {
+    pub fn new(observer: Arc<dyn ProgressObserver>, cancel: CancellationToken) -> SearchProgress {
+        SearchProgress {
+            observer,
+            cancel,
+            deadline: None,
+            stopped: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn console() -> SearchProgress {
+        SearchProgress::new(
+            Arc::new(ConsoleProgress::default()),
+            CancellationToken::new(),
+        )
+    }
+
+    pub fn silent() -> SearchProgress {
+        SearchProgress::new(Arc::new(NoProgress), CancellationToken::new())
+    }
+
+    // The same observer, token and deadline for a new search that hasn't been stopped yet
+    pub fn search(&self) -> SearchProgress {
+        SearchProgress {
+            stopped: Arc::new(AtomicBool::new(false)),
+            ..self.clone()
+        }
+    }
+
+    // A new search that stops at the deadline at the latest
+    pub fn with_deadline(&self, deadline: Instant) -> SearchProgress {
+        SearchProgress {
+            deadline: Some(self.deadline.map_or(deadline, |d| d.min(deadline))),
+            ..self.search()
+        }
+    }
+
+    // Time left until the deadline
+    pub fn time_left(&self) -> Option<Duration> {
+        self.deadline
+            .map(|deadline| deadline.saturating_duration_since(Instant::now()))
+    }
+
+    // Whether the search has to stop because it was cancelled or ran out of time
+    pub fn should_stop(&self) -> bool {
+        let stop = self.cancel.is_cancelled()
+            || self
+                .deadline
+                .map(|deadline| Instant::now() >= deadline)
+                .unwrap_or(false);
+        if stop {
+            self.stopped.store(true, Ordering::SeqCst);
+        }
+        stop
+    }
+
+    pub fn message(&self, message: &str) {
+        self.observer.on_message(message);
+    }
+
+    // Whether the search left something out because it had to stop
+    pub fn was_stopped(&self) -> bool {
+        self.stopped.load(Ordering::SeqCst)
+    }
+
+    pub fn start_phase(&self, phase: Phase, total: u64) -> PhaseProgress {
+        let phase_progress = PhaseProgress {
+            phase,
+            total,
+            done: AtomicU64::new(0),
+            best_rating: AtomicI16::new(i16::MAX),
+            best_scrim: Mutex::new(()),
+            start: Instant::now(),
+            last_report: AtomicU64::new(0),
+            observer: Arc::clone(&self.observer),
+        };
+        phase_progress.report(0);
+        phase_progress
+    }
+}
+
+// Counts the steps of one phase, shared by the worker threads
+pub struct PhaseProgress {
+    phase: Phase,
+    total: u64,
+    done: AtomicU64,
+    best_rating: AtomicI16,
+    // Held while a new best scrim is published, so they arrive in order
+    best_scrim: Mutex<()>,
+    start: Instant,
+    // Milliseconds after the start of the phase the last event was sent at
+    last_report: AtomicU64,
+    observer: Arc<dyn ProgressObserver>,
+}
+
+impl PhaseProgress {
+    // One step of a worker is done, every step is counted
+    pub fn step(&self) {
+        let done = self.done.fetch_add(1, Ordering::Relaxed) + 1;
+        let elapsed = self.start.elapsed().as_millis() as u64;
+        let last_report = self.last_report.load(Ordering::Relaxed);
+        if done >= self.total
+            || (elapsed >= last_report + REPORT_INTERVAL.as_millis() as u64
+                && self
+                    .last_report
+                    .compare_exchange(last_report, elapsed, Ordering::Relaxed, Ordering::Relaxed)
+                    .is_ok())
+        {
+            self.report(done);
+        }
+    }
+
+    // A worker found a scrim, it is only turned into a context if it is the best one so far
+    pub fn found_scrim(&self, rating: i16, get_context: impl FnOnce() -> ScrimContext) {
+        if rating >= self.best_rating.load(Ordering::Relaxed) {
+            return;
+        }
+        let _lock = self.best_scrim.lock().unwrap();
+        if rating < self.best_rating.load(Ordering::Relaxed) {
+            self.best_rating.store(rating, Ordering::Relaxed);
+            self.observer.on_best_scrim(&get_context());
+        }
+    }
+
+    // Sends the final event, also when the phase was cancelled before all steps were done
+    pub fn finish(&self) {
+        let done = self.done.load(Ordering::Relaxed);
+        if done < self.total {
+            self.report(done);
+        }
+    }
+
+    fn report(&self, done: u64) {
+        let best_rating = match self.best_rating.load(Ordering::Relaxed) {
+            i16::MAX => None,
+            best_rating => Some(best_rating),
+        };
+        let eta = if done == 0 {
+            None
+        } else {
+            let elapsed = self.start.elapsed();
+            Some(elapsed.mul_f64(self.total.saturating_sub(done) as f64 / done as f64))
+        };
+        self.observer.on_progress(&ProgressEvent {
+            phase: self.phase,
+            done,
+            total: self.total,
+            best_rating,
+            eta,
+        });
+    }
+}
@@ -0,0 +1,276 @@
+use crate::context::ScrimContext;
+use crate::matchup::TeamComp;
+use crate::matchup_ow2::OW2TeamComp;
+use crate::Matchup;
+use crate::OW2Matchup;
+use crate::Player;
+use anyhow::{anyhow, Result};
+use std::collections::HashMap;
+use std::fs;
+
+// Reads scrims back from the layout written to scrims.txt
+
+// Width of the label column ("Tank:    ") and of every team column
+const LABEL_WIDTH: usize = 9;
+const COLUMN_WIDTH: usize = 25;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScrimLineup {
+    Matchup(Matchup),
+    OW2Matchup(OW2Matchup),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParsedScrim {
+    pub label: String,
+    pub lineup: ScrimLineup,
+}
+
+impl ParsedScrim {
+    pub fn get_context(&self, players: &HashMap<u8, Player>) -> ScrimContext {
+        match &self.lineup {
+            ScrimLineup::Matchup(matchup) => matchup.get_context(&self.label, players),
+            ScrimLineup::OW2Matchup(matchup) => matchup.get_context(&self.label, players),
+        }
+    }
+}
+
+// Everything collected from the lines of one scrim block
+#[derive(Default)]
+struct ScrimBlock {
+    label: String,
+    title: String,
+    // The line with the team names, it is cut into columns once the number of teams is known
+    team_header: Option<String>,
+    tanks: Vec<Vec<String>>,
+    damage: Vec<Vec<String>>,
+    support: Vec<Vec<String>>,
+    bench: Vec<String>,
+}
+
+pub fn read_scrims_file(
+    scrims_file: &str,
+    players: &HashMap<u8, Player>,
+) -> Result<Vec<ParsedScrim>> {
+    let text = fs::read_to_string(scrims_file)?;
+    read_scrims(&text, players)
+}
+
+pub fn read_scrims(text: &str, players: &HashMap<u8, Player>) -> Result<Vec<ParsedScrim>> {
+    let mut player_ids: HashMap<&str, u8> = HashMap::new();
+    for (id, player) in players {
+        player_ids.insert(player.name.as_str(), *id);
+    }
+
+    let mut scrims: Vec<ParsedScrim> = Vec::new();
+    let mut block: Option<ScrimBlock> = None;
+
+    for line in text.lines() {
+        if let Some(header) = line.strip_prefix("SCRIM ") {
+            if let Some(finished) = block.take() {
+                scrims.push(finished.resolve(&player_ids, players)?);
+            }
+            // "A (7f3a09c1):", older files only have the label
+            let header = header.trim_end().trim_end_matches(':');
+            let label = match header.split_once(" (") {
+                Some((label, _)) => label,
+                None => header,
+            };
+            block = Some(ScrimBlock {
+                label: label.to_string(),
+                ..ScrimBlock::default()
+            });
+            continue;
+        }
+
+        let current = match block.as_mut() {
+            Some(current) => current,
+            None => continue,
+        };
+
+        if line == "Matchup" || line == "OW2Matchup" {
+            current.title = line.to_string();
+        } else if let Some(row) = line.strip_prefix("Tank:") {
+            current.tanks.push(split_player_row(row)?);
+        } else if let Some(row) = line.strip_prefix("DPS:") {
+            current.damage.push(split_player_row(row)?);
+        } else if let Some(row) = line.strip_prefix("Support:") {
+            current.support.push(split_player_row(row)?);
+        } else if let Some(names) = line.strip_prefix("Leftover players: ") {
+            current.bench = names.split(", ").map(|name| name.to_string()).collect();
+        } else if current.team_header.is_none()
+            && line.starts_with(&" ".repeat(LABEL_WIDTH))
+            && !line.trim().is_empty()
+        {
+            // The first indented line holds the team names, the second one their averages
+            current.team_header = Some(line[LABEL_WIDTH..].to_string());
+        }
+    }
+
+    if let Some(finished) = block.take() {
+        scrims.push(finished.resolve(&player_ids, players)?);
+    }
+
+    Ok(scrims)
+}
+
+// Cuts a line into the right-aligned columns of the team names.
+// A name that is too long for its column has no padding in front and pushes the following
+// columns to the right, it ends where the padding of the next column begins.
+fn split_columns(line: &str, number_of_columns: usize) -> Vec<String> {
+    let characters: Vec<char> = line.chars().collect();
+    let mut columns: Vec<String> = Vec::new();
+    let mut start = 0;
+
+    for column in 0..number_of_columns {
+        let end = if column + 1 == number_of_columns {
+            characters.len()
+        } else if characters.get(start) == Some(&' ') {
+            (start + COLUMN_WIDTH).min(characters.len())
+        } else {
+            let shortest_end = (start + COLUMN_WIDTH).min(characters.len());
+            let padding = |width: usize| {
+                characters[shortest_end..]
+                    .windows(width)
+                    .position(|window| window.iter().all(|c| *c == ' '))
+                    .map(|offset| shortest_end + offset)
+            };
+            padding(2)
+                .or_else(|| padding(1))
+                .unwrap_or(characters.len())
+        };
+        columns.push(
+            characters[start..end]
+                .iter()
+                .collect::<String>()
+                .trim()
+                .to_string(),
+        );
+        start = end;
+    }
+
+    columns
+}
+
+// Splits a role row into the player names of every team.
+// Entries look like "Name: 2500", a name that is too long for its column
+// pushes the following columns to the right, so the split is done on the SR instead of the width.
+fn split_player_row(row: &str) -> Result<Vec<String>> {
+    let mut names: Vec<String> = Vec::new();
+    let mut name_parts: Vec<&str> = Vec::new();
+
+    for token in row.split_whitespace() {
+        let previous_is_name_end = name_parts
+            .last()
+            .map(|part| part.ends_with(':'))
+            .unwrap_or(false);
+        if previous_is_name_end && token.parse::<i16>().is_ok() {
+            let name = name_parts.join(" ");
+            let name = name.trim_end_matches(':');
+            // A sub-role is shown behind the name, e.g. "Name [MT]: 2500"
+            let name = match name.rsplit_once(" [") {
+                Some((name, sub_role)) if sub_role.ends_with(']') => name,
+                _ => name,
+            };
+            names.push(name.to_string());
+            name_parts.clear();
+        } else {
+            name_parts.push(token);
+        }
+    }
+
+    if !name_parts.is_empty() {
+        return Err(anyhow!("Could not read the players of the row \"{}\"", row));
+    }
+
+    Ok(names)
+}
+
+impl ScrimBlock {
+    fn resolve(
+        self,
+        player_ids: &HashMap<&str, u8>,
+        players: &HashMap<u8, Player>,
+    ) -> Result<ParsedScrim> {
+        let find = |name: &String| -> Result<u8> {
+            player_ids.get(name.as_str()).copied().ok_or_else(|| {
+                anyhow!(
+                    "Player {} of scrim {} is not in the roster",
+                    name,
+                    self.label
+                )
+            })
+        };
+
+        let number_of_teams = self.tanks.first().map(Vec::len).unwrap_or(0);
+        let is_ow2 = self.title == "OW2Matchup";
+        let expected_tank_rows = if is_ow2 { 1 } else { 2 };
+        let team_names = match &self.team_header {
+            Some(header) => split_columns(header, number_of_teams),
+            None => Vec::new(),
+        };
+
+        if number_of_teams == 0
+            || team_names.len() != number_of_teams
+            || team_names.iter().any(String::is_empty)
+            || self.tanks.len() != expected_tank_rows
+            || self.damage.len() != 2
+            || self.support.len() != 2
+        {
+            return Err(anyhow!("Scrim {} is incomplete", self.label));
+        }
+        for row in self.tanks.iter().chain(&self.damage).chain(&self.support) {
+            if row.len() != number_of_teams {
+                return Err(anyhow!(
+                    "Scrim {} has {} teams but a row with {} players",
+                    self.label,
+                    number_of_teams,
+                    row.len()
+                ));
+            }
+        }
+
+        let mut players_left_over: Vec<u8> = Vec::new();
+        for name in &self.bench {
+            players_left_over.push(find(name)?);
+        }
+
+        let lineup = if is_ow2 {
+            let mut teams: Vec<OW2TeamComp> = Vec::new();
+            for (i, team_name) in team_names.iter().enumerate() {
+                teams.push((
+                    team_name.clone(),
+                    find(&self.tanks[0][i])?,
+                    find(&self.damage[0][i])?,
+                    find(&self.damage[1][i])?,
+                    find(&self.support[0][i])?,
+                    find(&self.support[1][i])?,
+                ));
+            }
+            let mut matchup = OW2Matchup::new(teams, players);
+            matchup.players_left_over = players_left_over;
+            ScrimLineup::OW2Matchup(matchup)
+        } else {
+            let mut teams: Vec<TeamComp> = Vec::new();
+            for (i, team_name) in team_names.iter().enumerate() {
+                teams.push((
+                    team_name.clone(),
+                    find(&self.tanks[0][i])?,
+                    find(&self.tanks[1][i])?,
+                    find(&self.damage[0][i])?,
+                    find(&self.damage[1][i])?,
+                    find(&self.support[0][i])?,
+                    find(&self.support[1][i])?,
+                ));
+            }
+            let mut matchup = Matchup::new(teams, players);
+            matchup.players_left_over = players_left_over;
+            ScrimLineup::Matchup(matchup)
+        };
+
+        Ok(ParsedScrim {
+            label: self.label,
+            lineup,
+        })
+    }
+}
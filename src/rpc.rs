@@ -0,0 +1,388 @@
+use crate::context::ScrimContext;
+use crate::explain::explain_scrim;
+use crate::options::Options;
+use crate::progress::{CancellationToken, ProgressEvent, ProgressObserver, SearchProgress};
+use crate::server::{panic_message, JobProgress};
+use crate::{balance, rebalance, scrim_id, BalanceConfig, Roster, SearchResult};
+use anyhow::{anyhow, Result};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+
+// JSON-RPC 2.0 over stdin and stdout, one message per line, so editors and scripts can drive the
+// balancer without HTTP. Nothing else is written to stdout while it runs.
+//
+// Requests look like {"jsonrpc": "2.0", "id": 1, "method": "generate", "params": {...}}
+//
+// load_roster  {"roster": "..."} or {"file": "players.txt"}      -> {"players": 12, "warnings": []}
+// generate     {"args": ["--time-limit", "30"]}                   -> the scrims, like GET /jobs/<id>/scrims
+// explain      {"scrim": "A"}                                     -> why a scrim got its rating
+// rebalance    {"scrim": "A", "remove": ["name"], "add": ["roster line"]}  -> the new scrims
+// cancel       {}                                                 -> {"cancelled": true}
+//
+// generate and rebalance answer once the search is done, until then requests keep being answered
+// and the search sends notifications without an id:
+//
+// progress     {"phase": "Scrims", "done": 10, "total": 100, "best_rating": 12, "eta_seconds": 1.5}
+// best_scrim   {"id": "...", "label": "A", "rating": 12}
+// message      {"text": "There are 2430 possible matchups"}
+//
+// Only one search runs at a time, cancel stops it and it answers with the best scrims found so far.
+// explain and rebalance work on the scrims of the last search, a scrim is given by its label or id.
+// Errors are answered with {"error": {"code": -32602, "message": "..."}}, a search that panics with
+// code -32000 like any other error of the balancer. Requests without an id are run but not
+// answered. At the end of the input the running search is finished first.
+
+// Error codes of JSON-RPC 2.0
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+// Everything that goes wrong in the balancer itself
+const BALANCER_ERROR: i64 = -32000;
+
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl RpcError {
+    fn new(code: i64, message: &str) -> RpcError {
+        RpcError {
+            code,
+            message: message.to_string(),
+        }
+    }
+}
+
+// Writes whole messages, shared by the requests and the search thread
+#[derive(Clone)]
+struct Output {
+    writer: Arc<Mutex<Box<dyn Write + Send>>>,
+}
+
+impl Output {
+    fn send(&self, message: &Value) -> io::Result<()> {
+        let mut writer = self.writer.lock().unwrap();
+        writeln!(writer, "{}", message)?;
+        writer.flush()
+    }
+
+    fn respond(&self, id: &Value, result: Result<Value, RpcError>) -> io::Result<()> {
+        let message = match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err(error) => json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": { "code": error.code, "message": error.message },
+            }),
+        };
+        self.send(&message)
+    }
+
+    fn notify(&self, method: &str, params: Value) {
+        // A closed output ends the session anyway once the next request is answered
+        let _ = self.send(&json!({ "jsonrpc": "2.0", "method": method, "params": params }));
+    }
+}
+
+// Sends the progress of a search as notifications
+struct RpcObserver {
+    output: Output,
+}
+
+impl ProgressObserver for RpcObserver {
+    fn on_progress(&self, event: &ProgressEvent) {
+        self.output
+            .notify("progress", json!(JobProgress::from(event)));
+    }
+
+    fn on_best_scrim(&self, scrim: &ScrimContext) {
+        self.output.notify(
+            "best_scrim",
+            json!({ "id": scrim.id, "label": scrim.label, "rating": scrim.rating }),
+        );
+    }
+
+    fn on_message(&self, message: &str) {
+        self.output.notify("message", json!({ "text": message }));
+    }
+}
+
+#[derive(Deserialize)]
+struct LoadRosterParams {
+    roster: Option<String>,
+    file: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct GenerateParams {
+    // Command line options of the search, like ["--time-limit", "30"]
+    #[serde(default)]
+    args: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct ExplainParams {
+    scrim: String,
+}
+
+#[derive(Deserialize)]
+struct RebalanceParams {
+    scrim: String,
+    #[serde(default)]
+    remove: Vec<String>,
+    // New players in the format of players.txt
+    #[serde(default)]
+    add: Vec<String>,
+}
+
+// The scrims of a search and what they were found for
+struct Searched {
+    roster: Roster,
+    config: BalanceConfig,
+    result: SearchResult,
+}
+
+#[derive(Default)]
+struct State {
+    roster: Option<Roster>,
+    searched: Option<Searched>,
+    // Stops the search that is running
+    running: Option<CancellationToken>,
+}
+
+struct Session {
+    state: Arc<Mutex<State>>,
+    output: Output,
+    search: Option<JoinHandle<()>>,
+}
+
+fn params<T: DeserializeOwned>(params: Value) -> Result<T, RpcError> {
+    let params = if params.is_null() { json!({}) } else { params };
+    serde_json::from_value(params)
+        .map_err(|error| RpcError::new(INVALID_PARAMS, &format!("Invalid params: {}", error)))
+}
+
+impl Session {
+    fn handle_line(&mut self, line: &str) -> io::Result<()> {
+        let request: Value = match serde_json::from_str(line) {
+            Ok(request) => request,
+            Err(error) => {
+                return self.output.respond(
+                    &Value::Null,
+                    Err(RpcError::new(
+                        PARSE_ERROR,
+                        &format!("Invalid JSON: {}", error),
+                    )),
+                )
+            }
+        };
+        let id = request.get("id").cloned();
+        let method = match request.get("method").and_then(Value::as_str) {
+            Some(method) => method,
+            None => {
+                return self.output.respond(
+                    &id.unwrap_or(Value::Null),
+                    Err(RpcError::new(
+                        INVALID_REQUEST,
+                        "A request is an object with a method",
+                    )),
+                )
+            }
+        };
+        let params = request.get("params").cloned().unwrap_or(Value::Null);
+
+        // The searches answer on their own once they are done
+        let result = match method {
+            "load_roster" => self.load_roster(params).map(Some),
+            "generate" => self.generate(&id, params).map(|_| None),
+            "explain" => self.explain(params).map(Some),
+            "rebalance" => self.rebalance(&id, params).map(|_| None),
+            "cancel" => Ok(Some(self.cancel())),
+            _ => Err(RpcError::new(
+                METHOD_NOT_FOUND,
+                &format!("There is no method {}", method),
+            )),
+        };
+        match (id, result) {
+            (Some(id), Ok(Some(result))) => self.output.respond(&id, Ok(result)),
+            (Some(id), Err(error)) => self.output.respond(&id, Err(error)),
+            _ => Ok(()),
+        }
+    }
+
+    fn load_roster(&mut self, params: Value) -> Result<Value, RpcError> {
+        let params: LoadRosterParams = self::params(params)?;
+        let roster = match (params.roster, params.file) {
+            (Some(roster), None) => roster,
+            (None, Some(file)) => fs::read_to_string(&file).map_err(|error| {
+                RpcError::new(
+                    BALANCER_ERROR,
+                    &format!("Could not read {}: {}", file, error),
+                )
+            })?,
+            _ => {
+                return Err(RpcError::new(
+                    INVALID_PARAMS,
+                    "Give either a roster or a file",
+                ))
+            }
+        };
+        let (roster, warnings) = Roster::parse_with_warnings(&roster)
+            .map_err(|error| RpcError::new(INVALID_PARAMS, &error.to_string()))?;
+        let players = roster.len();
+        self.state.lock().unwrap().roster = Some(roster);
+        Ok(json!({ "players": players, "warnings": warnings }))
+    }
+
+    fn generate(&mut self, id: &Option<Value>, params: Value) -> Result<(), RpcError> {
+        let params: GenerateParams = self::params(params)?;
+        let config = Options::parse_search(params.args)
+            .map_err(|error| RpcError::new(INVALID_PARAMS, &error.to_string()))?
+            .balance_config();
+        let roster =
+            self.state.lock().unwrap().roster.clone().ok_or_else(|| {
+                RpcError::new(BALANCER_ERROR, "Load a roster first with load_roster")
+            })?;
+        if roster.is_empty() {
+            return Err(RpcError::new(BALANCER_ERROR, "The roster has no players"));
+        }
+        self.start_search(id.clone(), roster, config, None)
+    }
+
+    // A scrim of the last search with its roster and options
+    fn find_scrim(&self, scrim: &str) -> Result<(ScrimContext, Roster, BalanceConfig), RpcError> {
+        let state = self.state.lock().unwrap();
+        let searched = state.searched.as_ref().ok_or_else(|| {
+            RpcError::new(
+                BALANCER_ERROR,
+                "There are no scrims yet, generate them first",
+            )
+        })?;
+        let context = scrim_id::find_scrim(&searched.result.scrims, scrim).ok_or_else(|| {
+            RpcError::new(BALANCER_ERROR, &format!("There is no scrim {}", scrim))
+        })?;
+        Ok((
+            context.clone(),
+            searched.roster.clone(),
+            searched.config.clone(),
+        ))
+    }
+
+    fn explain(&self, params: Value) -> Result<Value, RpcError> {
+        let params: ExplainParams = self::params(params)?;
+        let (context, _, _) = self.find_scrim(&params.scrim)?;
+        Ok(json!(explain_scrim(&context)))
+    }
+
+    fn rebalance(&mut self, id: &Option<Value>, params: Value) -> Result<(), RpcError> {
+        let params: RebalanceParams = self::params(params)?;
+        let (current, roster, config) = self.find_scrim(&params.scrim)?;
+        let roster = roster
+            .changed(&params.remove, &params.add)
+            .map_err(|error| RpcError::new(INVALID_PARAMS, &error.to_string()))?;
+        self.start_search(id.clone(), roster, config, Some(current))
+    }
+
+    fn cancel(&self) -> Value {
+        let state = self.state.lock().unwrap();
+        if let Some(cancel) = &state.running {
+            cancel.cancel();
+        }
+        json!({ "cancelled": state.running.is_some() })
+    }
+
+    // Runs the search on its own thread, it answers the request when it is done
+    fn start_search(
+        &mut self,
+        id: Option<Value>,
+        roster: Roster,
+        config: BalanceConfig,
+        current: Option<ScrimContext>,
+    ) -> Result<(), RpcError> {
+        let cancel = CancellationToken::new();
+        {
+            let mut state = self.state.lock().unwrap();
+            if state.running.is_some() {
+                return Err(RpcError::new(
+                    BALANCER_ERROR,
+                    "A search is already running, cancel it first",
+                ));
+            }
+            state.running = Some(cancel.clone());
+        }
+        self.wait();
+
+        let state = Arc::clone(&self.state);
+        let output = self.output.clone();
+        let observer = RpcObserver {
+            output: output.clone(),
+        };
+        self.search = Some(thread::spawn(move || {
+            let progress = SearchProgress::new(Arc::new(observer), cancel);
+            // A panic of the search is answered like an error, so the next search can start
+            let result = panic::catch_unwind(AssertUnwindSafe(|| match &current {
+                Some(current) => rebalance(&roster, current, &config, &progress),
+                None => balance(&roster, &config, &progress),
+            }))
+            .unwrap_or_else(|panic| {
+                Err(anyhow!("The search panicked: {}", panic_message(&*panic)))
+            });
+            // The state is updated before the answer, so the next request already sees the scrims
+            let response = {
+                let mut state = state
+                    .lock()
+                    .unwrap_or_else(|poisoned| poisoned.into_inner());
+                state.running = None;
+                match result {
+                    Ok(result) => {
+                        let scrims = json!(result);
+                        state.searched = Some(Searched {
+                            roster,
+                            config,
+                            result,
+                        });
+                        Ok(scrims)
+                    }
+                    Err(error) => Err(RpcError::new(BALANCER_ERROR, &error.to_string())),
+                }
+            };
+            if let Some(id) = id {
+                let _ = output.respond(&id, response);
+            }
+        }));
+        Ok(())
+    }
+
+    // Until the last search is done
+    fn wait(&mut self) {
+        if let Some(search) = self.search.take() {
+            let _ = search.join();
+        }
+    }
+}
+
+// Answers the requests of the input until it ends
+pub fn run(input: impl BufRead, output: impl Write + Send + 'static) -> Result<()> {
+    let mut session = Session {
+        state: Arc::new(Mutex::new(State::default())),
+        output: Output {
+            writer: Arc::new(Mutex::new(Box::new(output))),
+        },
+        search: None,
+    };
+    for line in input.lines() {
+        let line = line?;
+        if !line.trim().is_empty() {
+            session.handle_line(&line)?;
+        }
+    }
+    session.wait();
+    Ok(())
+}
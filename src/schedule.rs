@@ -0,0 +1,70 @@
+use crate::context::{MatchContext, RoundContext, TeamContext};
+
+// Round-robin schedule for the teams of one scrim, built with the circle method.
+// The teams are seated so that the first round pairs the teams closest in average SR,
+// the remaining rounds follow ordered by the SR differences of their matches.
+
+pub fn round_robin(teams: &[TeamContext], number_of_rounds: Option<usize>) -> Vec<RoundContext> {
+    let mut sorted_teams: Vec<&TeamContext> = teams.iter().collect();
+    sorted_teams.sort_by(|a, b| b.average_sr.partial_cmp(&a.average_sr).unwrap());
+
+    // With an uneven number of teams the empty seat is the bye
+    let mut seeds: Vec<Option<&TeamContext>> = sorted_teams.into_iter().map(Some).collect();
+    if !seeds.len().is_multiple_of(2) {
+        seeds.push(None);
+    }
+    let number_of_seats = seeds.len();
+    if number_of_seats < 2 {
+        return Vec::new();
+    }
+
+    // Neighbours in the SR order face each other in the first round
+    let mut seats: Vec<Option<&TeamContext>> = vec![None; number_of_seats];
+    for i in 0..number_of_seats / 2 {
+        seats[i] = seeds[2 * i];
+        seats[number_of_seats - 1 - i] = seeds[2 * i + 1];
+    }
+
+    let mut rounds: Vec<(f32, Vec<MatchContext>, Option<String>)> = Vec::new();
+    for _ in 0..number_of_seats - 1 {
+        let mut matches: Vec<MatchContext> = Vec::new();
+        let mut bye: Option<String> = None;
+        let mut cost: f32 = 0.0;
+        for i in 0..number_of_seats / 2 {
+            match (seats[i], seats[number_of_seats - 1 - i]) {
+                (Some(home), Some(away)) => {
+                    let sr_difference = (home.average_sr - away.average_sr).abs();
+                    cost += sr_difference;
+                    matches.push(MatchContext {
+                        home: home.name.clone(),
+                        away: away.name.clone(),
+                        sr_difference,
+                    });
+                }
+                (Some(team), None) | (None, Some(team)) => bye = Some(team.name.clone()),
+                (None, None) => {}
+            }
+        }
+        rounds.push((cost, matches, bye));
+
+        // The first seat stays, everybody else moves one seat further
+        let last = seats.pop().unwrap();
+        seats.insert(1, last);
+    }
+
+    rounds.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+    if let Some(number_of_rounds) = number_of_rounds {
+        rounds.truncate(number_of_rounds);
+    }
+
+    rounds
+        .into_iter()
+        .enumerate()
+        .map(|(i, (_, matches, bye))| RoundContext {
+            round: i + 1,
+            matches,
+            bye,
+            maps: Vec::new(),
+        })
+        .collect()
+}
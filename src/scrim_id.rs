@@ -0,0 +1,38 @@
+use crate::context::ScrimContext;
+
+// Stable ids for scrims, derived from the lineup alone.
+// The teams are sorted before hashing, so the id doesn't depend on the team names,
+// the order of the teams or the position the scrim was printed at.
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+// FNV-1a, unlike the std hasher it is guaranteed to stay the same between Rust versions
+fn fnv1a(text: &str) -> u64 {
+    let mut hash = FNV_OFFSET_BASIS;
+    for byte in text.bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(FNV_PRIME);
+    }
+    hash
+}
+
+// Builds the id of a scrim from the ids of its teams, see Team::get_id
+pub fn scrim_id(mut team_ids: Vec<String>) -> String {
+    team_ids.sort();
+    let hash = fnv1a(&team_ids.join("|"));
+    format!("{:08x}", (hash ^ (hash >> 32)) as u32)
+}
+
+// Ids can be shortened as long as they stay unique, "7f3a" refers to "7f3a09c1"
+pub fn id_matches(id: &str, query: &str) -> bool {
+    !query.is_empty() && id.starts_with(&query.to_lowercase())
+}
+
+// A scrim is referenced by its label or its id, labels come first since "b" is also the start of an id
+pub fn find_scrim<'a>(scrims: &'a [ScrimContext], query: &str) -> Option<&'a ScrimContext> {
+    scrims
+        .iter()
+        .find(|scrim| scrim.label == query)
+        .or_else(|| scrims.iter().find(|scrim| id_matches(&scrim.id, query)))
+}
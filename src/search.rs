@@ -0,0 +1,1128 @@
+use crate::context::ScrimContext;
+use crate::matchup::Matchup;
+use crate::matchup_ow2::OW2Matchup;
+use crate::naming;
+use crate::player::{duo_sub_roles, Player};
+use crate::progress::{Phase, SearchProgress};
+use crate::types::{Penalties, Position};
+use crate::{availability, languages, series, stacks, BalanceConfig};
+use anyhow::{anyhow, Result};
+use itertools::Itertools;
+use serde::Serialize;
+use std::cmp;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::iter::FromIterator;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use web_time::Instant;
+
+// The exhaustive search, every way to split the players into teams is rated
+
+// (Tank duos, Damage duos, Support duos) of one possible matchup
+type DuoMatchup = (Vec<(u8, u8)>, Vec<(u8, u8)>, Vec<(u8, u8)>);
+// (Tanks, Damage duos, Support duos) of one possible OW2 matchup
+type OW2DuoMatchup = (Vec<u8>, Vec<(u8, u8)>, Vec<(u8, u8)>);
+// (Damage duos, Support duos) that don't share any players
+type DpsSuppCombination = (Vec<(u8, u8)>, Vec<(u8, u8)>);
+
+// Runs a part of the search on a worker thread
+#[cfg(all(feature = "threads", not(target_arch = "wasm32")))]
+fn spawn_worker(work: impl FnOnce() + Send + 'static) -> thread::JoinHandle<()> {
+    thread::spawn(work)
+}
+
+// Without threads, every part of the search runs right away on the calling thread. WebAssembly
+// can't start threads, so the browser always searches this way, even with the threads feature.
+#[cfg(any(not(feature = "threads"), target_arch = "wasm32"))]
+struct FinishedWorker;
+
+#[cfg(any(not(feature = "threads"), target_arch = "wasm32"))]
+impl FinishedWorker {
+    fn join(self) -> thread::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(any(not(feature = "threads"), target_arch = "wasm32"))]
+fn spawn_worker(work: impl FnOnce() + Send + 'static) -> FinishedWorker {
+    work();
+    FinishedWorker
+}
+
+// Best scrims of a search, labelled A, B, C, ...
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SearchResult {
+    pub scrims: Vec<ScrimContext>,
+    // False if the search was stopped before it tried every scrim
+    pub complete: bool,
+}
+
+// Plans a series, the search runs once for every different set of available players
+pub fn generate_series(
+    config: &BalanceConfig,
+    player_map: &HashMap<u8, Player>,
+    number_of_rounds: usize,
+    progress: &SearchProgress,
+) -> Result<SearchResult> {
+    let round_players: Vec<HashMap<u8, Player>> = (1..=number_of_rounds)
+        .map(|round| availability::players_for_round(player_map, round, &config.session))
+        .collect();
+    let mut player_sets: Vec<Vec<u8>> = round_players
+        .iter()
+        .map(|players| {
+            let mut player_ids: Vec<u8> = players.keys().cloned().collect();
+            player_ids.sort_unstable();
+            player_ids
+        })
+        .collect();
+    let number_of_searches = player_sets.iter().unique().count();
+
+    let mut complete = true;
+    let mut candidates_by_players: HashMap<Vec<u8>, Vec<ScrimContext>> = HashMap::new();
+    let mut round_candidates: Vec<Vec<ScrimContext>> = Vec::new();
+    for (i, players) in round_players.iter().enumerate() {
+        let player_ids = std::mem::take(&mut player_sets[i]);
+        let candidates = match candidates_by_players.get(&player_ids) {
+            Some(candidates) => candidates.clone(),
+            None => {
+                progress.message(&format!("Searching the scrims of round {}", i + 1));
+                // Time that is left is shared evenly by the searches that are left
+                let searches_left = (number_of_searches - candidates_by_players.len()) as u32;
+                let search_progress = match progress.time_left() {
+                    Some(time_left) => {
+                        progress.with_deadline(Instant::now() + time_left / searches_left)
+                    }
+                    None => progress.search(),
+                };
+                let result =
+                    generate_scrims(config, players, series::SERIES_CANDIDATES, &search_progress)?;
+                complete &= result.complete;
+                candidates_by_players.insert(player_ids, result.scrims.clone());
+                result.scrims
+            }
+        };
+        round_candidates.push(candidates);
+    }
+
+    Ok(SearchResult {
+        scrims: series::plan_series(&round_candidates, player_map, &config.session),
+        complete,
+    })
+}
+
+// Runs the search and returns the given number of best scrims.
+// A search that is stopped returns the best scrims found until then.
+pub fn generate_scrims(
+    config: &BalanceConfig,
+    player_map: &HashMap<u8, Player>,
+    number_of_scrims: usize,
+    progress: &SearchProgress,
+) -> Result<SearchResult> {
+    let ow_2 = config.ow_2;
+
+    let number_of_threads = config.number_of_threads;
+
+    let stacks = stacks::get_stacks(player_map);
+
+    let duos = make_duos(player_map, ow_2, config.require_sub_roles, progress);
+
+    let number_of_teams: usize = player_map.len() / if ow_2 { 5 } else { 6 };
+
+    let team_permutations = get_permutations(6);
+
+    let team_names = naming::default_names(number_of_teams);
+
+    // With a deadline the matchups get half of the time, so there is time left to rate them
+    let progress = progress.search();
+    let matchup_progress = match progress.time_left() {
+        Some(time_left) => progress.with_deadline(Instant::now() + time_left / 2),
+        None => progress.search(),
+    };
+
+    let scrims: Vec<ScrimContext> = if ow_2 {
+        let matchups = create_ow2_matchups(
+            &duos,
+            number_of_teams,
+            number_of_threads,
+            &stacks,
+            &matchup_progress,
+        );
+        progress.message(&format!("There are {} possible matchups", matchups.len()));
+        let scrims = create_ow2_scrims(
+            player_map,
+            matchups,
+            number_of_teams,
+            team_names,
+            number_of_threads,
+            team_permutations,
+            config.penalties,
+            &stacks,
+            &progress,
+        );
+
+        scrims
+            .iter()
+            .take(number_of_scrims)
+            .enumerate()
+            .map(|(i, scrim)| scrim.get_context(&scrim_label(i), player_map))
+            .collect()
+    } else {
+        let matchups = create_matchups(
+            &duos,
+            number_of_teams,
+            number_of_threads,
+            &stacks,
+            &matchup_progress,
+        );
+        progress.message(&format!("There are {} possible matchups", matchups.len()));
+        let scrims = create_scrims(
+            player_map,
+            matchups,
+            number_of_teams,
+            team_names,
+            number_of_threads,
+            config.penalties,
+            &stacks,
+            &progress,
+        );
+
+        scrims
+            .iter()
+            .take(number_of_scrims)
+            .enumerate()
+            .map(|(i, scrim)| scrim.get_context(&scrim_label(i), player_map))
+            .collect()
+    };
+
+    let complete = !matchup_progress.was_stopped() && !progress.was_stopped();
+    if scrims.is_empty() && !complete {
+        return Err(anyhow!("The search was stopped before a scrim was found"));
+    }
+    Ok(SearchResult { scrims, complete })
+}
+
+// Scrims are labelled A, B, C, ... in the order of their rating
+// A, B, ..., Z, AA, AB, ...
+pub fn scrim_label(index: usize) -> String {
+    let mut label = String::new();
+    let mut index = index + 1;
+    while index > 0 {
+        index -= 1;
+        label.insert(0, (b'A' + (index % 26) as u8) as char);
+        index /= 26;
+    }
+    label
+}
+
+fn get_permutations(number_of_teams: usize) -> Vec<Vec<Vec<usize>>> {
+    let uneven = !number_of_teams.is_multiple_of(2);
+
+    let last_chunk = number_of_teams / 2;
+
+    let perms = (1..=number_of_teams).permutations(number_of_teams);
+    let mut perms_seen: Vec<String> = Vec::new();
+    let mut perm_vec: Vec<Vec<Vec<usize>>> = Vec::new();
+    for p in perms {
+        let chunks = p.into_iter().chunks(2);
+        let mut sorted_vecs: Vec<Vec<usize>> = Vec::new();
+        let mut last_num: usize = 0;
+        for (i, c) in chunks.into_iter().enumerate() {
+            let mut vec = c.into_iter().collect::<Vec<usize>>();
+            if uneven && i == last_chunk {
+                last_num = vec.pop().unwrap();
+                break;
+            }
+            vec.sort();
+            sorted_vecs.push(vec);
+        }
+
+        if uneven {
+            let last_index = sorted_vecs.len() - 1;
+            let last = &mut sorted_vecs[last_index];
+            last.push(last_num);
+            last.sort();
+        }
+
+        let mut perm_str: String = String::new();
+        for sorted_vec in &sorted_vecs {
+            perm_str.push_str(&sorted_vec.iter().map(|i| i.to_string()).collect::<String>());
+        }
+        if perms_seen.contains(&perm_str) {
+            continue;
+        }
+        perms_seen.push(perm_str.clone());
+
+        let mut perm_vec_i: Vec<Vec<usize>> = Vec::new();
+        for sorted_vec in sorted_vecs {
+            perm_vec_i.push(sorted_vec);
+        }
+
+        perm_vec.push(perm_vec_i);
+    }
+    perm_vec
+}
+
+#[allow(clippy::too_many_arguments)]
+fn create_scrims(
+    players_raw: &HashMap<u8, Player>,
+    matchups: Vec<DuoMatchup>,
+    number_of_teams: usize,
+    team_names_raw: Vec<String>,
+    number_of_threads: usize,
+    penalties: Penalties,
+    stacks: &[Vec<u8>],
+    progress: &SearchProgress,
+) -> Vec<Matchup> {
+    let scrims: Vec<Vec<Matchup>> = Vec::new();
+
+    let all_player_vec: Vec<u8> = players_raw.keys().cloned().collect();
+
+    progress.message(&format!("{:?}", team_names_raw));
+
+    let number_of_matchups = matchups.len();
+
+    let phase_progress = Arc::new(progress.start_phase(Phase::Scrims, number_of_matchups as u64));
+
+    let mut chunk_size = number_of_matchups / number_of_threads;
+
+    if chunk_size == 0 {
+        chunk_size = 1;
+    }
+
+    let matchup_chunks = matchups.into_iter().chunks(chunk_size);
+
+    let mut handles = vec![];
+
+    let mutex = Mutex::new(scrims);
+    let arc = Arc::new(mutex);
+
+    for matchup_chunk in matchup_chunks.into_iter() {
+        let matchup_chunk: Vec<DuoMatchup> = matchup_chunk.collect();
+
+        let all_player_set: HashSet<u8> = HashSet::from_iter(all_player_vec.clone());
+        let cloned_arc = Arc::clone(&arc);
+        let phase_progress = Arc::clone(&phase_progress);
+        let search_progress = progress.clone();
+        let players = players_raw.clone();
+        let team_names = team_names_raw.clone();
+        let stacks = stacks.to_vec();
+
+        let mut best_rating: i16 = i16::MAX;
+
+        let handle = spawn_worker(move || {
+            let mut all_scrims: Vec<Matchup> = Vec::new();
+
+            for possible_matchup in matchup_chunk {
+                if search_progress.should_stop() {
+                    break;
+                }
+                let tank_vec = &possible_matchup.0;
+                let damage_vec = &possible_matchup.1;
+                let support_vec = &possible_matchup.2;
+
+                let dps_iter = (0..number_of_teams).permutations(number_of_teams);
+                let supp_iter = (0..number_of_teams).permutations(number_of_teams);
+
+                // Stacks are placed as a whole, so a stack split by the tanks and damage players
+                // rules out every pairing with the supports
+                if !stacks.is_empty() {
+                    let tank_members: Vec<Vec<u8>> =
+                        tank_vec.iter().map(|duo| vec![duo.0, duo.1]).collect();
+                    if stacks::splits_teams(&stacks, &tank_members) {
+                        phase_progress.step();
+                        continue;
+                    }
+                }
+
+                for dps_perm in dps_iter {
+                    if !stacks.is_empty() {
+                        let damage_members: Vec<Vec<u8>> = (0..number_of_teams)
+                            .map(|i| {
+                                let duo = damage_vec[dps_perm[i]];
+                                vec![tank_vec[i].0, tank_vec[i].1, duo.0, duo.1]
+                            })
+                            .collect();
+                        if stacks::splits_teams(&stacks, &damage_members) {
+                            continue;
+                        }
+                    }
+                    for supp_perm in supp_iter.clone() {
+                        let mut matchup_teams: Vec<(String, u8, u8, u8, u8, u8, u8)> = Vec::new();
+                        for i in 0..number_of_teams {
+                            matchup_teams.push((
+                                team_names[i].clone(),
+                                tank_vec.get(i).unwrap().0,
+                                tank_vec.get(i).unwrap().1,
+                                damage_vec.get(dps_perm[i]).unwrap().0,
+                                damage_vec.get(dps_perm[i]).unwrap().1,
+                                support_vec.get(supp_perm[i]).unwrap().0,
+                                support_vec.get(supp_perm[i]).unwrap().1,
+                            ));
+                        }
+                        if !stacks.is_empty() {
+                            let team_members: Vec<Vec<u8>> = matchup_teams
+                                .iter()
+                                .map(|t| vec![t.1, t.2, t.3, t.4, t.5, t.6])
+                                .collect();
+                            if stacks::splits_teams(&stacks, &team_members) {
+                                continue;
+                            }
+                        }
+                        if penalties.require_common_language
+                            && matchup_teams.iter().any(|t| {
+                                languages::team_conflict(
+                                    &t.0,
+                                    &[
+                                        &players[&t.1],
+                                        &players[&t.2],
+                                        &players[&t.3],
+                                        &players[&t.4],
+                                        &players[&t.5],
+                                        &players[&t.6],
+                                    ],
+                                )
+                                .is_some()
+                            })
+                        {
+                            continue;
+                        }
+                        let m_clone = matchup_teams.clone();
+                        let mut matchup = Matchup::new(matchup_teams, &players);
+                        if penalties != Penalties::default() {
+                            matchup.apply_penalties(&players, penalties);
+                        }
+                        let rating = matchup.rating;
+                        if rating < (best_rating as f32 * 1.1) as i16 {
+                            let mut players_playing: Vec<u8> = Vec::new();
+
+                            for m_team in m_clone {
+                                players_playing.push(m_team.1);
+                                players_playing.push(m_team.2);
+                                players_playing.push(m_team.3);
+                                players_playing.push(m_team.4);
+                                players_playing.push(m_team.5);
+                                players_playing.push(m_team.6);
+                            }
+
+                            // println!("all_player_set: {:?}", all_player_set);
+
+                            let playing_players_set: HashSet<u8> =
+                                HashSet::from_iter(players_playing);
+                            // println!("playing_players_set: {:?}", playing_players_set);
+
+                            let players_on_bench: HashSet<_> =
+                                all_player_set.difference(&playing_players_set).collect();
+                            // println!("players_on_bench: {:?}", players_on_bench);
+                            // println!();
+
+                            let players_left_over_vec: Vec<_> =
+                                players_on_bench.into_iter().cloned().collect();
+
+                            matchup.players_left_over = players_left_over_vec;
+
+                            phase_progress
+                                .found_scrim(rating, || matchup.get_context("A", &players));
+                            all_scrims.push(matchup);
+                            best_rating = cmp::min(rating, best_rating);
+                        }
+                    }
+                }
+                phase_progress.step();
+            }
+            // println!("Adding {} scrims to the shared vector", all_scrims.len());
+            cloned_arc.lock().unwrap().push(all_scrims);
+        });
+        handles.push(handle);
+    }
+
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    let scrim_vector = arc.lock().unwrap().clone();
+    let mut scrims: Vec<Matchup> = Vec::new();
+    for mut scrim_chunk in scrim_vector {
+        scrims.append(&mut scrim_chunk);
+    }
+
+    phase_progress.finish();
+
+    progress.message(&format!("\nTotal number of scrims: {}", scrims.len()));
+    scrims.sort_by(|a, b| a.rating.partial_cmp(&b.rating).unwrap());
+
+    scrims
+}
+
+fn make_duos(
+    players: &HashMap<u8, Player>,
+    ow_2: bool,
+    require_sub_roles: bool,
+    progress: &SearchProgress,
+) -> HashMap<Position, Vec<(u8, u8)>> {
+    let position_vec = vec![Position::Tank, Position::Damage, Position::Support];
+    let mut duos: HashMap<Position, Vec<(u8, u8)>> = HashMap::new();
+
+    for position in position_vec {
+        let mut role_list: Vec<(u8, &Player)> = Vec::new();
+        progress.message(&format!("All players with {:?} role", position));
+        for (id, player) in players.iter() {
+            if player.plays_position(position) {
+                role_list.push((*id, player));
+                progress.message(&format!("{}: {}", id, player.name));
+            }
+        }
+
+        let tuples: Vec<(u8, u8)> = if ow_2 && position == Position::Tank {
+            // collect the role list into a list of tuples with (id, 0)
+            role_list.iter().map(|(id, _)| (*id, 0)).collect()
+        } else {
+            role_list
+                .iter()
+                .combinations(2)
+                // only keep duos that split into both sub-roles if that is required
+                .filter(|pair| {
+                    !require_sub_roles || !duo_sub_roles(&[pair[0].1, pair[1].1], position).1
+                })
+                .map(|pair| (pair[0].0, pair[1].0))
+                .collect()
+        };
+        // println!("Position: {:?}, number of pairs: {}", position, tuples.len());
+        // for t in &tuples {
+        //     println!("({}, {})", players.get(&t.0).unwrap().name, players.get(&t.1).unwrap().name);
+        // }
+        duos.insert(position, tuples);
+    }
+
+    duos
+}
+
+fn create_matchups(
+    player_duos: &HashMap<Position, Vec<(u8, u8)>>,
+    number_of_teams: usize,
+    number_of_threads: usize,
+    stacks: &[Vec<u8>],
+    progress: &SearchProgress,
+) -> Vec<DuoMatchup> {
+    let position_vec = vec![Position::Tank, Position::Damage, Position::Support];
+
+    progress.message(&format!("Number of teams: {}", number_of_teams));
+
+    let mut combination_map: HashMap<Position, Vec<Vec<(u8, u8)>>> = HashMap::new();
+
+    for position in &position_vec {
+        let combinations_crowded = player_duos[position].iter().combinations(number_of_teams);
+
+        // Large rosters have a lot of combinations, so this can be stopped as well
+        let mut number_of_combinations = 0;
+        let combinations_vec: Vec<Vec<&(u8, u8)>> = combinations_crowded
+            .take_while(|_| !progress.should_stop())
+            .inspect(|_| number_of_combinations += 1)
+            .filter(|c| {
+                let mut player_ids: Vec<u8> = Vec::new();
+                for player in c {
+                    player_ids.push(player.0);
+                    player_ids.push(player.1);
+                }
+                player_ids.iter().unique().count() == player_ids.len()
+            })
+            .collect();
+        progress.message(&format!(
+            "There are {} possible {:?} combinations",
+            number_of_combinations, position
+        ));
+        progress.message(&format!("{} of those are unique", combinations_vec.len()));
+
+        let mut clean_combinations: Vec<Vec<(u8, u8)>> = Vec::new();
+
+        for combination in &combinations_vec {
+            let mut clean_combination: Vec<(u8, u8)> = Vec::new();
+            for player_vec in combination {
+                clean_combination.push((player_vec.0, player_vec.1));
+            }
+            clean_combinations.push(clean_combination);
+        }
+        progress.message("");
+
+        combination_map.insert(*position, clean_combinations);
+    }
+
+    let number_of_tank_combinations = combination_map[&Position::Tank].len();
+
+    let mut all_dps_supp_combinations: Vec<DpsSuppCombination> = Vec::new();
+
+    // First calculating all possible Damage & Support pairings
+    'damage_outer_loop: for damage_pairs_this_matchup in &combination_map[&Position::Damage] {
+        if progress.should_stop() {
+            break;
+        }
+        let mut dps_players_seen_this_run: Vec<u8> = Vec::new();
+        for dps_duo in damage_pairs_this_matchup {
+            dps_players_seen_this_run.push(dps_duo.0);
+            dps_players_seen_this_run.push(dps_duo.1);
+        }
+        // Check if all names are unique
+        if dps_players_seen_this_run.iter().unique().count() != dps_players_seen_this_run.len() {
+            continue 'damage_outer_loop;
+        }
+        // All players are unique so far, we can now pair these with Support players
+        'support_inner_loop: for support_pairs_this_matchup in &combination_map[&Position::Support]
+        {
+            let mut dps_and_support_players_seen_this_run = dps_players_seen_this_run.clone();
+            for support_duo in support_pairs_this_matchup {
+                dps_and_support_players_seen_this_run.push(support_duo.0);
+                dps_and_support_players_seen_this_run.push(support_duo.1);
+            }
+            // Check if all names are unique
+            if dps_and_support_players_seen_this_run
+                .iter()
+                .unique()
+                .count()
+                != dps_and_support_players_seen_this_run.len()
+            {
+                continue 'support_inner_loop;
+            }
+
+            // Looks like a possible tank / support matchup has been found, it can now be created and appended to the list of all combinations
+            let mut dps_supp_combination: DpsSuppCombination = (Vec::new(), Vec::new());
+            dps_supp_combination.0 = damage_pairs_this_matchup.clone();
+            dps_supp_combination.1 = support_pairs_this_matchup.clone();
+            all_dps_supp_combinations.push(dps_supp_combination);
+        }
+    }
+
+    progress.message("Starting to combine the tank matchups");
+
+    let matchup_vec: Vec<Vec<DuoMatchup>> = Vec::new();
+    //                          | Tank Duos      DPS Duos       Support Duos  |
+    //                          +---------------------------------------------+
+    //                          |  One Matchup                                |
+
+    let phase_progress =
+        Arc::new(progress.start_phase(Phase::Matchups, number_of_tank_combinations as u64));
+
+    let mut chunk_size = number_of_tank_combinations / number_of_threads;
+
+    if chunk_size == 0 {
+        chunk_size = 1;
+    }
+
+    let tank_chunks = &combination_map[&Position::Tank]
+        .clone()
+        .into_iter()
+        .chunks(chunk_size);
+
+    let mut handles = vec![];
+
+    let mutex = Mutex::new(matchup_vec);
+    let arc = Arc::new(mutex);
+
+    for tank_chunk in tank_chunks.into_iter() {
+        let tank_chunk: Vec<Vec<(u8, u8)>> = tank_chunk.collect();
+
+        let dps_supp_combinations = all_dps_supp_combinations.clone();
+        let stacks = stacks.to_vec();
+
+        // println!("length of matchup chunk: {}", matchup_chunk.len());
+
+        let cloned_arc = Arc::clone(&arc);
+        let phase_progress = Arc::clone(&phase_progress);
+        let search_progress = progress.clone();
+
+        let handle = spawn_worker(move || {
+            let mut matchups_this_thread: Vec<DuoMatchup> = Vec::new();
+
+            for tank_pairs_in_this_matchup in tank_chunk {
+                if search_progress.should_stop() {
+                    break;
+                }
+                let mut tank_names: Vec<u8> = Vec::new();
+                for pair in &tank_pairs_in_this_matchup {
+                    tank_names.push(pair.0);
+                    tank_names.push(pair.1);
+                }
+
+                'dps_support_loop: for dps_support_pairs in &dps_supp_combinations {
+                    let mut names_in_this_matchup = tank_names.clone();
+                    // Add the DPS pairs
+                    for pair in &dps_support_pairs.0 {
+                        names_in_this_matchup.push(pair.0);
+                        names_in_this_matchup.push(pair.1);
+                    }
+                    // Add the Support pairs
+                    for pair in &dps_support_pairs.1 {
+                        names_in_this_matchup.push(pair.0);
+                        names_in_this_matchup.push(pair.1);
+                    }
+
+                    // Check if any names are double
+                    if names_in_this_matchup.iter().unique().count() != names_in_this_matchup.len()
+                    {
+                        continue 'dps_support_loop;
+                    }
+
+                    // Stacks play as a whole or sit on the bench as a whole
+                    if stacks::splits_bench(&stacks, &names_in_this_matchup) {
+                        continue 'dps_support_loop;
+                    }
+
+                    // No names are double, this is a possibly valid matchup
+                    let dps_pairs: Vec<(u8, u8)> = dps_support_pairs.0.clone();
+                    let support_pairs: Vec<(u8, u8)> = dps_support_pairs.1.clone();
+
+                    let full_matchup: DuoMatchup =
+                        (tank_pairs_in_this_matchup.clone(), dps_pairs, support_pairs);
+
+                    matchups_this_thread.push(full_matchup);
+                }
+                phase_progress.step();
+            }
+            cloned_arc.lock().unwrap().push(matchups_this_thread);
+        });
+        handles.push(handle);
+    }
+
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    phase_progress.finish();
+
+    let matchup_vector = arc.lock().unwrap().clone();
+    let mut matchups: Vec<DuoMatchup> = Vec::new();
+    for mut matchup_chunk in matchup_vector {
+        matchups.append(&mut matchup_chunk);
+    }
+
+    // println!("There are {} possible matchups", matchups.len());
+
+    matchups
+}
+
+#[allow(clippy::too_many_arguments)]
+fn create_ow2_scrims(
+    players_raw: &HashMap<u8, Player>,
+    matchups: Vec<OW2DuoMatchup>,
+    number_of_teams: usize,
+    team_names_raw: Vec<String>,
+    number_of_threads: usize,
+    team_permutations: Vec<Vec<Vec<usize>>>,
+    penalties: Penalties,
+    stacks: &[Vec<u8>],
+    progress: &SearchProgress,
+) -> Vec<OW2Matchup> {
+    let scrims: Vec<Vec<OW2Matchup>> = Vec::new();
+
+    let all_player_vec: Vec<u8> = players_raw.keys().cloned().collect();
+
+    progress.message(&format!("{:?}", team_names_raw));
+
+    let number_of_matchups = matchups.len();
+
+    let phase_progress = Arc::new(progress.start_phase(Phase::Scrims, number_of_matchups as u64));
+
+    let mut chunk_size = number_of_matchups / number_of_threads;
+
+    if chunk_size == 0 {
+        chunk_size = 1;
+    }
+
+    let matchup_chunks = matchups.into_iter().chunks(chunk_size);
+
+    let mut handles = vec![];
+
+    let mutex = Mutex::new(scrims);
+    let arc = Arc::new(mutex);
+
+    for matchup_chunk in matchup_chunks.into_iter() {
+        let matchup_chunk: Vec<OW2DuoMatchup> = matchup_chunk.collect();
+
+        let all_player_set: HashSet<u8> = HashSet::from_iter(all_player_vec.clone());
+        let cloned_arc = Arc::clone(&arc);
+        let phase_progress = Arc::clone(&phase_progress);
+        let search_progress = progress.clone();
+        let players = players_raw.clone();
+        let team_names = team_names_raw.clone();
+        let stacks = stacks.to_vec();
+        let team_perms = team_permutations.clone();
+
+        let handle = spawn_worker(move || {
+            let mut all_scrims: Vec<OW2Matchup> = Vec::new();
+
+            let mut best_rating: i16 = i16::MAX;
+
+            for possible_matchup in matchup_chunk {
+                if search_progress.should_stop() {
+                    break;
+                }
+                let tank_vec = &possible_matchup.0;
+                let damage_vec = &possible_matchup.1;
+                let support_vec = &possible_matchup.2;
+
+                let dps_iter = (0..number_of_teams).permutations(number_of_teams);
+                let supp_iter = (0..number_of_teams).permutations(number_of_teams);
+
+                for dps_perm in dps_iter {
+                    // Stacks are placed as a whole, so a stack split by the tanks and damage players
+                    // rules out every pairing with the supports
+                    if !stacks.is_empty() {
+                        let damage_members: Vec<Vec<u8>> = (0..number_of_teams)
+                            .map(|i| {
+                                let duo = damage_vec[dps_perm[i]];
+                                vec![tank_vec[i], duo.0, duo.1]
+                            })
+                            .collect();
+                        if stacks::splits_teams(&stacks, &damage_members) {
+                            continue;
+                        }
+                    }
+                    for supp_perm in supp_iter.clone() {
+                        let mut matchup_teams: Vec<(String, u8, u8, u8, u8, u8)> = Vec::new();
+                        for i in 0..number_of_teams {
+                            matchup_teams.push((
+                                team_names[i].clone(),
+                                *tank_vec.get(i).unwrap(),
+                                damage_vec.get(dps_perm[i]).unwrap().0,
+                                damage_vec.get(dps_perm[i]).unwrap().1,
+                                support_vec.get(supp_perm[i]).unwrap().0,
+                                support_vec.get(supp_perm[i]).unwrap().1,
+                            ));
+                        }
+                        if !stacks.is_empty() {
+                            let team_members: Vec<Vec<u8>> = matchup_teams
+                                .iter()
+                                .map(|t| vec![t.1, t.2, t.3, t.4, t.5])
+                                .collect();
+                            if stacks::splits_teams(&stacks, &team_members) {
+                                continue;
+                            }
+                        }
+                        if penalties.require_common_language
+                            && matchup_teams.iter().any(|t| {
+                                languages::team_conflict(
+                                    &t.0,
+                                    &[
+                                        &players[&t.1],
+                                        &players[&t.2],
+                                        &players[&t.3],
+                                        &players[&t.4],
+                                        &players[&t.5],
+                                    ],
+                                )
+                                .is_some()
+                            })
+                        {
+                            continue;
+                        }
+
+                        let mut players_playing: Vec<u8> = Vec::new();
+                        let m_clone = matchup_teams.clone();
+
+                        for m_team in m_clone {
+                            players_playing.push(m_team.1);
+                            players_playing.push(m_team.2);
+                            players_playing.push(m_team.3);
+                            players_playing.push(m_team.4);
+                            players_playing.push(m_team.5);
+                        }
+
+                        // println!("all_player_set: {:?}", all_player_set);
+
+                        let playing_players_set: HashSet<u8> = HashSet::from_iter(players_playing);
+                        // println!("playing_players_set: {:?}", playing_players_set);
+
+                        let players_on_bench: HashSet<_> =
+                            all_player_set.difference(&playing_players_set).collect();
+                        // println!("players_on_bench: {:?}", players_on_bench);
+                        // println!();
+
+                        let players_left_over_vec: Vec<_> =
+                            players_on_bench.into_iter().cloned().collect();
+
+                        if number_of_teams <= 3 {
+                            let mut matchup = OW2Matchup::new(matchup_teams, &players);
+                            if penalties != Penalties::default() {
+                                matchup.apply_penalties(&players, penalties);
+                            }
+                            let rating = matchup.rating;
+
+                            matchup.players_left_over = players_left_over_vec;
+
+                            if rating < (best_rating as f32) as i16 {
+                                phase_progress
+                                    .found_scrim(rating, || matchup.get_context("A", &players));
+                                all_scrims.push(matchup);
+                                best_rating = cmp::min(rating, best_rating);
+                            }
+                        } else {
+                            // The penalties only depend on the teams, not on how they are paired
+                            let penalty = if penalties != Penalties::default() {
+                                OW2Matchup::new(matchup_teams.clone(), &players)
+                                    .get_penalty(&players, penalties)
+                            } else {
+                                0
+                            };
+                            for permutation in &team_perms {
+                                let mut rating = penalty;
+                                let mut this_permutation_matchups: Vec<(
+                                    String,
+                                    u8,
+                                    u8,
+                                    u8,
+                                    u8,
+                                    u8,
+                                )> = Vec::new();
+
+                                for perm in permutation {
+                                    let mut this_matchup_teams: Vec<(String, u8, u8, u8, u8, u8)> =
+                                        Vec::new();
+                                    for i in perm {
+                                        this_matchup_teams.push(matchup_teams[*i - 1].clone());
+                                        this_permutation_matchups
+                                            .push(matchup_teams[*i - 1].clone());
+                                    }
+                                    let mut matchup = OW2Matchup::new(this_matchup_teams, &players);
+
+                                    matchup.players_left_over = players_left_over_vec.clone();
+                                    rating += matchup.rating;
+                                }
+                                if rating < (best_rating as f32) as i16 {
+                                    let mut matchup =
+                                        OW2Matchup::new(matchup_teams.clone(), &players);
+                                    matchup.apply_penalties(&players, penalties);
+                                    phase_progress
+                                        .found_scrim(rating, || matchup.get_context("A", &players));
+                                    all_scrims.push(matchup);
+                                    best_rating = cmp::min(rating, best_rating);
+                                }
+                            }
+                        }
+                    }
+                }
+                phase_progress.step();
+            }
+            cloned_arc.lock().unwrap().push(all_scrims);
+        });
+        handles.push(handle);
+    }
+
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    let scrim_vector = arc.lock().unwrap().clone();
+    let mut scrims: Vec<OW2Matchup> = Vec::new();
+    for mut scrim_chunk in scrim_vector {
+        scrims.append(&mut scrim_chunk);
+    }
+
+    phase_progress.finish();
+
+    progress.message(&format!("\nTotal number of scrims: {}", scrims.len()));
+    scrims.sort_by(|a, b| a.rating.partial_cmp(&b.rating).unwrap());
+
+    scrims
+}
+
+fn create_ow2_matchups(
+    player_duos: &HashMap<Position, Vec<(u8, u8)>>,
+    number_of_teams: usize,
+    number_of_threads: usize,
+    stacks: &[Vec<u8>],
+    progress: &SearchProgress,
+) -> Vec<OW2DuoMatchup> {
+    let position_vec = vec![Position::Tank, Position::Damage, Position::Support];
+
+    progress.message(&format!("Number of teams: {}", number_of_teams));
+
+    let mut combination_map: HashMap<Position, Vec<Vec<(u8, u8)>>> = HashMap::new();
+
+    for position in &position_vec {
+        let combinations_crowded = player_duos[position].iter().combinations(number_of_teams);
+
+        // Large rosters have a lot of combinations, so this can be stopped as well
+        let mut number_of_combinations = 0;
+        let combinations_vec: Vec<Vec<&(u8, u8)>> = combinations_crowded
+            .take_while(|_| !progress.should_stop())
+            .inspect(|_| number_of_combinations += 1)
+            .filter(|c| {
+                let mut player_ids: Vec<u8> = Vec::new();
+                for player in c {
+                    player_ids.push(player.0);
+                    player_ids.push(player.1);
+                }
+                position == &Position::Tank
+                    || (player_ids.iter().unique().count() == player_ids.len())
+            })
+            .collect();
+        progress.message(&format!(
+            "There are {} possible {:?} combinations",
+            number_of_combinations, position
+        ));
+        progress.message(&format!("{} of those are unique", combinations_vec.len()));
+
+        let mut clean_combinations: Vec<Vec<(u8, u8)>> = Vec::new();
+
+        for combination in &combinations_vec {
+            let mut clean_combination: Vec<(u8, u8)> = Vec::new();
+            for player_vec in combination {
+                clean_combination.push((player_vec.0, player_vec.1));
+            }
+            clean_combinations.push(clean_combination);
+        }
+        progress.message("");
+
+        combination_map.insert(*position, clean_combinations);
+    }
+
+    let number_of_tank_combinations = combination_map[&Position::Tank].len();
+
+    let mut all_dps_supp_combinations: Vec<DpsSuppCombination> = Vec::new();
+
+    // First calculating all possible Damage & Support pairings
+    'damage_outer_loop: for damage_pairs_this_matchup in &combination_map[&Position::Damage] {
+        if progress.should_stop() {
+            break;
+        }
+        let mut dps_players_seen_this_run: Vec<u8> = Vec::new();
+        for dps_duo in damage_pairs_this_matchup {
+            dps_players_seen_this_run.push(dps_duo.0);
+            dps_players_seen_this_run.push(dps_duo.1);
+        }
+        // Check if all names are unique
+        if dps_players_seen_this_run.iter().unique().count() != dps_players_seen_this_run.len() {
+            continue 'damage_outer_loop;
+        }
+        // All players are unique so far, we can now pair these with Support players
+        'support_inner_loop: for support_pairs_this_matchup in &combination_map[&Position::Support]
+        {
+            let mut dps_and_support_players_seen_this_run = dps_players_seen_this_run.clone();
+            for support_duo in support_pairs_this_matchup {
+                dps_and_support_players_seen_this_run.push(support_duo.0);
+                dps_and_support_players_seen_this_run.push(support_duo.1);
+            }
+            // Check if all names are unique
+            if dps_and_support_players_seen_this_run
+                .iter()
+                .unique()
+                .count()
+                != dps_and_support_players_seen_this_run.len()
+            {
+                continue 'support_inner_loop;
+            }
+
+            // Looks like a possible tank / support matchup has been found, it can now be created and appended to the list of all combinations
+            let mut dps_supp_combination: DpsSuppCombination = (Vec::new(), Vec::new());
+            dps_supp_combination.0 = damage_pairs_this_matchup.clone();
+            dps_supp_combination.1 = support_pairs_this_matchup.clone();
+            all_dps_supp_combinations.push(dps_supp_combination);
+        }
+    }
+
+    let matchup_vec: Vec<Vec<OW2DuoMatchup>> = Vec::new();
+    //                      | Tanks    DPS Duos       Support Duos  |
+    //                      +---------------------------------------+
+    //                      |  One Matchup                          |
+
+    let phase_progress =
+        Arc::new(progress.start_phase(Phase::Matchups, number_of_tank_combinations as u64));
+
+    let mut chunk_size = number_of_tank_combinations / number_of_threads;
+
+    if chunk_size == 0 {
+        chunk_size = 1;
+    }
+
+    // Copy all tanks from combination_map into a Vector, only retaining the first player of each duo
+    let clean_tank_vec: Vec<Vec<u8>> = combination_map[&Position::Tank]
+        .iter()
+        .map(|c| c.iter().map(|d| d.0).collect())
+        .collect();
+
+    progress.message(&format!("Chunk size: {}", chunk_size));
+
+    let tank_chunks = clean_tank_vec.into_iter().chunks(chunk_size);
+
+    let mut handles = vec![];
+
+    let mutex = Mutex::new(matchup_vec);
+    let arc = Arc::new(mutex);
+
+    for tank_chunk in tank_chunks.into_iter() {
+        let tank_chunk: Vec<Vec<u8>> = tank_chunk.collect();
+
+        let dps_supp_combinations = all_dps_supp_combinations.clone();
+        let stacks = stacks.to_vec();
+
+        let cloned_arc = Arc::clone(&arc);
+        let phase_progress = Arc::clone(&phase_progress);
+        let search_progress = progress.clone();
+
+        let handle = spawn_worker(move || {
+            let mut matchups_this_thread: Vec<OW2DuoMatchup> = Vec::new();
+
+            for tank_pairs_in_this_matchup in tank_chunk {
+                if search_progress.should_stop() {
+                    break;
+                }
+                let mut tank_names: Vec<u8> = Vec::new();
+                for tank in &tank_pairs_in_this_matchup {
+                    tank_names.push(*tank);
+                }
+
+                'dps_support_loop: for dps_support_pairs in &dps_supp_combinations {
+                    let mut names_in_this_matchup = tank_names.clone();
+                    // Add the DPS pairs
+                    for pair in &dps_support_pairs.0 {
+                        names_in_this_matchup.push(pair.0);
+                        names_in_this_matchup.push(pair.1);
+                    }
+                    // Add the Support pairs
+                    for pair in &dps_support_pairs.1 {
+                        names_in_this_matchup.push(pair.0);
+                        names_in_this_matchup.push(pair.1);
+                    }
+
+                    // Check if any names are double
+                    if names_in_this_matchup.iter().unique().count() != names_in_this_matchup.len()
+                    {
+                        continue 'dps_support_loop;
+                    }
+
+                    // Stacks play as a whole or sit on the bench as a whole
+                    if stacks::splits_bench(&stacks, &names_in_this_matchup) {
+                        continue 'dps_support_loop;
+                    }
+
+                    // No names are double, this is a possibly valid matchup
+                    let dps_pairs: Vec<(u8, u8)> = dps_support_pairs.0.clone();
+                    let support_pairs: Vec<(u8, u8)> = dps_support_pairs.1.clone();
+
+                    let full_matchup: OW2DuoMatchup =
+                        (tank_pairs_in_this_matchup.clone(), dps_pairs, support_pairs);
+
+                    matchups_this_thread.push(full_matchup);
+                }
+                phase_progress.step();
+            }
+            cloned_arc.lock().unwrap().push(matchups_this_thread);
+        });
+        handles.push(handle);
+    }
+
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    phase_progress.finish();
+
+    let matchup_vector = arc.lock().unwrap().clone();
+    let mut matchups: Vec<OW2DuoMatchup> = Vec::new();
+    for mut matchup_chunk in matchup_vector {
+        matchups.append(&mut matchup_chunk);
+    }
+
+    // println!("There are {} possible matchups", matchups.len());
+
+    matchups
+}
@@ -0,0 +1,270 @@
+use crate::availability::{is_available, Session};
+use crate::context::ScrimContext;
+use crate::Player;
+use crate::Position;
+use std::collections::HashMap;
+
+// A series plays several scrims in a row with the same roster.
+// Every round is taken from the best scrims of the search, but players should get new teammates,
+// the off-role slots and the bench should rotate instead of hitting the same players every round.
+
+// Number of the best scrims that are considered for every round
+pub const SERIES_CANDIDATES: usize = 500;
+
+// Penalties added to the rating of a candidate, per repetition
+const REPEATED_TEAMMATES_PENALTY: i32 = 40;
+const REPEATED_OFF_ROLE_PENALTY: i32 = 120;
+const REPEATED_BENCH_PENALTY: i32 = 250;
+// Added for every further team with players that leave before the end of the series
+const SPLIT_LEAVERS_PENALTY: i32 = 200;
+
+#[derive(Default)]
+struct SeriesHistory {
+    teammates: HashMap<(String, String), usize>,
+    off_role: HashMap<String, usize>,
+    bench: HashMap<String, usize>,
+}
+
+// The role a player is best at, the first one in the order Tank, Damage, Support on a tie
+pub fn main_role(player: &Player) -> Position {
+    let position_vec = vec![Position::Tank, Position::Damage, Position::Support];
+    let mut main_role = Position::Tank;
+    let mut best_sr: Option<i16> = None;
+    for position in position_vec {
+        if !player.plays_position(position) {
+            continue;
+        }
+        let sr = player.get_sr(position);
+        if best_sr.map(|best| sr > best).unwrap_or(true) {
+            best_sr = Some(sr);
+            main_role = position;
+        }
+    }
+    main_role
+}
+
+fn teammate_pairs(scrim: &ScrimContext) -> Vec<(String, String)> {
+    let mut pairs: Vec<(String, String)> = Vec::new();
+    for team in &scrim.teams {
+        let mut names: Vec<&String> = team.players.iter().map(|player| &player.name).collect();
+        names.sort();
+        for i in 0..names.len() {
+            for j in i + 1..names.len() {
+                pairs.push((names[i].clone(), names[j].clone()));
+            }
+        }
+    }
+    pairs
+}
+
+// Names of the players that are not on their main role in this scrim
+fn off_role_players(scrim: &ScrimContext, main_roles: &HashMap<&str, String>) -> Vec<String> {
+    scrim
+        .teams
+        .iter()
+        .flat_map(|team| team.players.iter())
+        .filter(|player| {
+            main_roles
+                .get(player.name.as_str())
+                .map(|role| role != &player.role)
+                .unwrap_or(false)
+        })
+        .map(|player| player.name.clone())
+        .collect()
+}
+
+impl SeriesHistory {
+    fn penalty(&self, scrim: &ScrimContext, main_roles: &HashMap<&str, String>) -> i32 {
+        let repeated_teammates: usize = teammate_pairs(scrim)
+            .iter()
+            .map(|pair| self.teammates.get(pair).copied().unwrap_or(0))
+            .sum();
+        let repeated_off_role: usize = off_role_players(scrim, main_roles)
+            .iter()
+            .map(|name| self.off_role.get(name).copied().unwrap_or(0))
+            .sum();
+        let repeated_bench: usize = scrim
+            .bench
+            .iter()
+            .map(|name| self.bench.get(name).copied().unwrap_or(0))
+            .sum();
+
+        repeated_teammates as i32 * REPEATED_TEAMMATES_PENALTY
+            + repeated_off_role as i32 * REPEATED_OFF_ROLE_PENALTY
+            + repeated_bench as i32 * REPEATED_BENCH_PENALTY
+    }
+
+    fn add(&mut self, scrim: &ScrimContext, main_roles: &HashMap<&str, String>) {
+        for pair in teammate_pairs(scrim) {
+            *self.teammates.entry(pair).or_insert(0) += 1;
+        }
+        for name in off_role_players(scrim, main_roles) {
+            *self.off_role.entry(name).or_insert(0) += 1;
+        }
+        for name in &scrim.bench {
+            *self.bench.entry(name.clone()).or_insert(0) += 1;
+        }
+    }
+
+    // Whether the bench only takes players who sat out no more often than everybody playing,
+    // so nobody sits out twice before everyone sat out once
+    fn rotates_bench(&self, scrim: &ScrimContext) -> bool {
+        let times_benched = |name: &String| self.bench.get(name).copied().unwrap_or(0);
+        let most_benched = scrim.bench.iter().map(times_benched).max();
+        let least_benched_playing = scrim
+            .teams
+            .iter()
+            .flat_map(|team| team.players.iter())
+            .map(|player| times_benched(&player.name))
+            .min();
+        match (most_benched, least_benched_playing) {
+            (Some(bench), Some(playing)) => bench <= playing,
+            _ => true,
+        }
+    }
+}
+
+fn main_roles(players: &HashMap<u8, Player>) -> HashMap<&str, String> {
+    players
+        .values()
+        .map(|player| (player.name.as_str(), format!("{:?}", main_role(player))))
+        .collect()
+}
+
+// Names of the players of the round that are missing in a later round of the series
+fn early_leavers(
+    players: &HashMap<u8, Player>,
+    round: usize,
+    number_of_rounds: usize,
+    session: &Session,
+) -> Vec<String> {
+    players
+        .values()
+        .filter(|player| {
+            is_available(player, round, session)
+                && (round + 1..=number_of_rounds).any(|later| !is_available(player, later, session))
+        })
+        .map(|player| player.name.clone())
+        .collect()
+}
+
+// Early leavers spread over several teams mean several teams have to be rebalanced once they leave
+fn split_leavers_penalty(scrim: &ScrimContext, leavers: &[String]) -> i32 {
+    let teams_with_leavers = scrim
+        .teams
+        .iter()
+        .filter(|team| {
+            team.players
+                .iter()
+                .any(|player| leavers.contains(&player.name))
+        })
+        .count() as i32;
+    (teams_with_leavers - 1).max(0) * SPLIT_LEAVERS_PENALTY
+}
+
+// Picks the scrims of a series, round_candidates holds the candidates of every round sorted by rating.
+// Each round takes the candidate with the lowest rating plus penalties for everything
+// that already happened in the rounds before. Candidates that keep the bench rotating go first,
+// so nobody sits out twice before everyone sat out once. The rounds are labelled 1, 2, 3, ...
+pub fn plan_series(
+    round_candidates: &[Vec<ScrimContext>],
+    players: &HashMap<u8, Player>,
+    session: &Session,
+) -> Vec<ScrimContext> {
+    let main_roles = main_roles(players);
+    let mut history = SeriesHistory::default();
+    let mut rounds: Vec<ScrimContext> = Vec::new();
+    let number_of_rounds = round_candidates.len();
+
+    for (round, candidates) in round_candidates.iter().enumerate() {
+        let leavers = early_leavers(players, round + 1, number_of_rounds, session);
+        let unplayed: Vec<&ScrimContext> = candidates
+            .iter()
+            .filter(|candidate| rounds.iter().all(|scrim| scrim.id != candidate.id))
+            .collect();
+        // The bench penalty alone can lose against new teammates, so rotating the bench comes first
+        let rotating: Vec<&ScrimContext> = unplayed
+            .iter()
+            .copied()
+            .filter(|candidate| history.rotates_bench(candidate))
+            .collect();
+        let eligible = if rotating.is_empty() {
+            unplayed
+        } else {
+            rotating
+        };
+        let best = eligible.into_iter().min_by_key(|candidate| {
+            candidate.rating as i32
+                + history.penalty(candidate, &main_roles)
+                + split_leavers_penalty(candidate, &leavers)
+        });
+        let mut scrim = match best {
+            Some(scrim) => scrim.clone(),
+            None => break,
+        };
+        history.add(&scrim, &main_roles);
+        scrim.label = (round + 1).to_string();
+        rounds.push(scrim);
+    }
+
+    rounds
+}
+
+// Summary of the whole series: how often everybody played off-role or sat out
+// and how many teammate pairs came up more than once
+pub fn get_summary_string(
+    rounds: &[ScrimContext],
+    players: &HashMap<u8, Player>,
+    session: &Session,
+) -> String {
+    let main_roles = main_roles(players);
+    let mut history = SeriesHistory::default();
+    for scrim in rounds {
+        history.add(scrim, &main_roles);
+    }
+
+    let mut names: Vec<&str> = players
+        .values()
+        .map(|player| player.name.as_str())
+        .collect();
+    names.sort_unstable();
+
+    let mut s = String::new();
+    s.push_str("-------------------------------------\n");
+    s.push_str(&format!("SERIES of {} rounds\n", rounds.len()));
+    for scrim in rounds {
+        s.push_str(&format!(
+            "Round {}: {} (Rating: {})\n",
+            scrim.label, scrim.id, scrim.rating
+        ));
+    }
+
+    let repeated_pairs = history
+        .teammates
+        .values()
+        .filter(|count| **count > 1)
+        .count();
+    s.push_str(&format!(
+        "\nTeammate pairs: {}, repeated: {}\n",
+        history.teammates.len(),
+        repeated_pairs
+    ));
+
+    s.push_str("\nPlayer          Main     Rounds  Off-role  Bench\n");
+    for name in names {
+        let player = players.values().find(|player| player.name == name).unwrap();
+        let available_rounds = (1..=rounds.len())
+            .filter(|round| is_available(player, *round, session))
+            .count();
+        s.push_str(&format!(
+            "{: <16}{: <9}{: <8}{: <10}{}\n",
+            name,
+            main_roles[name],
+            available_rounds,
+            history.off_role.get(name).copied().unwrap_or(0),
+            history.bench.get(name).copied().unwrap_or(0)
+        ));
+    }
+    s.push_str("=====================================\n");
+    s
+}
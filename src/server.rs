@@ -0,0 +1,567 @@
+use crate::context::ScrimContext;
+use crate::explain::explain_scrim;
+use crate::lineup::{rate_lineup, Lineup};
+use crate::options::Options;
+use crate::progress::{CancellationToken, ProgressEvent, ProgressObserver, SearchProgress};
+use crate::template::ScrimRenderer;
+use crate::{balance, rebalance, scrim_id, BalanceConfig, Roster, SearchResult};
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::fs;
+use std::net::{SocketAddr, ToSocketAddrs};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use tiny_http::{Header, Method, Request, Response};
+
+// A local HTTP server with a JSON API, so several front ends can share one balancer.
+// GET / serves the web UI, which only uses this API.
+//
+// POST   /jobs                                {"roster": "...", "args": ["--time-limit", "30"]}  starts a search
+// GET    /jobs                                all jobs and their state
+// GET    /jobs/<id>                           state and progress of a job
+// DELETE /jobs/<id>                           stops a job, it keeps the best scrims found so far
+// GET    /jobs/<id>/scrims                    the scrims of a finished job
+// GET    /jobs/<id>/scrims/<scrim>/explain    why a scrim got its rating
+// POST   /jobs/<id>/scrims/<scrim>/rebalance  {"remove": ["name"], "add": ["roster line"]}  starts a rebalance
+// POST   /lineups                             {"roster": "...", "args": [], "lineup": {...}}  rates a lineup
+// GET    /roster                              the players file the server was started with
+// PUT    /roster                              {"roster": "..."}  overwrites the players file
+//
+// A scrim is given by its label or by its id. Errors are answered with {"error": "..."}.
+// Only the options in SEARCH_OPTIONS can be given as args, the server doesn't read files for a request.
+
+// Finished jobs are kept for their scrims until there are more than this many of them
+pub const MAX_FINISHED_JOBS: usize = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobState {
+    Running,
+    Done,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct JobProgress {
+    pub phase: String,
+    pub done: u64,
+    pub total: u64,
+    pub best_rating: Option<i16>,
+    pub eta_seconds: Option<f64>,
+}
+
+impl From<&ProgressEvent> for JobProgress {
+    fn from(event: &ProgressEvent) -> JobProgress {
+        JobProgress {
+            phase: format!("{:?}", event.phase),
+            done: event.done,
+            total: event.total,
+            best_rating: event.best_rating,
+            eta_seconds: event.eta.map(|eta| eta.as_secs_f64()),
+        }
+    }
+}
+
+struct Job {
+    state: JobState,
+    roster: Roster,
+    config: BalanceConfig,
+    progress: Option<JobProgress>,
+    best_rating: Option<i16>,
+    result: Option<SearchResult>,
+    error: Option<String>,
+    cancel: CancellationToken,
+}
+
+impl Job {
+    fn status(&self, id: u64) -> Value {
+        json!({
+            "id": id,
+            "state": self.state,
+            "progress": self.progress,
+            "best_rating": self.best_rating,
+            "complete": self.result.as_ref().map(|result| result.complete),
+            "error": self.error,
+        })
+    }
+}
+
+type Jobs = Arc<Mutex<HashMap<u64, Job>>>;
+
+// Writes the progress of a search into its job
+struct JobObserver {
+    jobs: Jobs,
+    id: u64,
+}
+
+impl ProgressObserver for JobObserver {
+    fn on_progress(&self, event: &ProgressEvent) {
+        if let Some(job) = self.jobs.lock().unwrap().get_mut(&self.id) {
+            job.progress = Some(JobProgress::from(event));
+        }
+    }
+
+    fn on_best_scrim(&self, scrim: &ScrimContext) {
+        if let Some(job) = self.jobs.lock().unwrap().get_mut(&self.id) {
+            job.best_rating = Some(scrim.rating);
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct JobRequest {
+    roster: String,
+    // Command line options of the search, like ["--time-limit", "30"]
+    #[serde(default)]
+    args: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct LineupRequest {
+    roster: String,
+    #[serde(default)]
+    args: Vec<String>,
+    lineup: Lineup,
+}
+
+#[derive(Deserialize)]
+struct RosterRequest {
+    roster: String,
+}
+
+#[derive(Deserialize)]
+struct RebalanceRequest {
+    #[serde(default)]
+    remove: Vec<String>,
+    // New players in the format of players.txt
+    #[serde(default)]
+    add: Vec<String>,
+}
+
+pub struct ApiResponse {
+    pub status: u16,
+    pub body: Value,
+}
+
+impl ApiResponse {
+    fn ok(body: Value) -> ApiResponse {
+        ApiResponse { status: 200, body }
+    }
+
+    fn accepted(body: Value) -> ApiResponse {
+        ApiResponse { status: 202, body }
+    }
+
+    fn error(status: u16, message: &str) -> ApiResponse {
+        ApiResponse {
+            status,
+            body: json!({ "error": message }),
+        }
+    }
+}
+
+// The jobs and the routes, without the HTTP around them
+#[derive(Clone, Default)]
+pub struct Api {
+    jobs: Jobs,
+    next_id: Arc<Mutex<u64>>,
+    // Read and written by /roster, the roster can't be saved without one
+    players_file: Option<String>,
+}
+
+impl Api {
+    pub fn new() -> Api {
+        Api::default()
+    }
+
+    pub fn with_players_file(players_file: &str) -> Api {
+        Api {
+            players_file: Some(players_file.to_string()),
+            ..Api::default()
+        }
+    }
+
+    pub fn handle(&self, method: &str, path: &str, body: &str) -> ApiResponse {
+        let segments: Vec<&str> = path
+            .split('?')
+            .next()
+            .unwrap_or("")
+            .split('/')
+            .filter(|segment| !segment.is_empty())
+            .collect();
+        let response = match (method, segments.as_slice()) {
+            ("POST", ["jobs"]) => self.create_job(body),
+            ("GET", ["jobs"]) => Ok(self.list_jobs()),
+            ("GET", ["jobs", id]) => self.job_status(id),
+            ("DELETE", ["jobs", id]) => self.cancel_job(id),
+            ("GET", ["jobs", id, "scrims"]) => self.job_scrims(id),
+            ("GET", ["jobs", id, "scrims", scrim, "explain"]) => self.explain(id, scrim),
+            ("POST", ["jobs", id, "scrims", scrim, "rebalance"]) => self.rebalance(id, scrim, body),
+            ("POST", ["lineups"]) => Api::rate_lineup(body),
+            ("GET", ["roster"]) => self.read_roster(),
+            ("PUT", ["roster"]) => self.write_roster(body),
+            _ => Err(ApiResponse::error(
+                404,
+                &format!("No route for {} {}", method, path),
+            )),
+        };
+        response.unwrap_or_else(|error| error)
+    }
+
+    // Runs the search on its own thread, the job is updated while it runs
+    fn start_job(
+        &self,
+        roster: Roster,
+        config: BalanceConfig,
+        current: Option<ScrimContext>,
+    ) -> u64 {
+        let id = {
+            let mut next_id = self.next_id.lock().unwrap();
+            *next_id += 1;
+            *next_id
+        };
+        let cancel = CancellationToken::new();
+        self.evict_finished_jobs();
+        self.jobs.lock().unwrap().insert(
+            id,
+            Job {
+                state: JobState::Running,
+                roster: roster.clone(),
+                config: config.clone(),
+                progress: None,
+                best_rating: None,
+                result: None,
+                error: None,
+                cancel: cancel.clone(),
+            },
+        );
+
+        let jobs = Arc::clone(&self.jobs);
+        let observer = JobObserver {
+            jobs: Arc::clone(&jobs),
+            id,
+        };
+        thread::spawn(move || {
+            let progress = SearchProgress::new(Arc::new(observer), cancel);
+            // A panic of the search fails the job instead of leaving it running forever
+            let result = panic::catch_unwind(AssertUnwindSafe(|| match &current {
+                Some(current) => rebalance(&roster, current, &config, &progress),
+                None => balance(&roster, &config, &progress),
+            }))
+            .unwrap_or_else(|panic| {
+                Err(anyhow!("The search panicked: {}", panic_message(&*panic)))
+            });
+            let mut jobs = jobs.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            if let Some(job) = jobs.get_mut(&id) {
+                match result {
+                    Ok(result) => {
+                        job.state = JobState::Done;
+                        job.result = Some(result);
+                    }
+                    Err(error) => {
+                        job.state = JobState::Failed;
+                        job.error = Some(error.to_string());
+                    }
+                }
+            }
+        });
+        id
+    }
+
+    // Drops the oldest finished jobs so there is room for one more, running jobs are kept
+    fn evict_finished_jobs(&self) {
+        let mut jobs = self.jobs.lock().unwrap();
+        let mut finished: Vec<u64> = jobs
+            .iter()
+            .filter(|(_, job)| job.state != JobState::Running)
+            .map(|(id, _)| *id)
+            .collect();
+        if finished.len() >= MAX_FINISHED_JOBS {
+            finished.sort_unstable();
+            for id in &finished[..=finished.len() - MAX_FINISHED_JOBS] {
+                jobs.remove(id);
+            }
+        }
+    }
+
+    fn create_job(&self, body: &str) -> Result<ApiResponse, ApiResponse> {
+        let request: JobRequest = serde_json::from_str(body)
+            .map_err(|error| ApiResponse::error(400, &format!("Invalid job: {}", error)))?;
+        let config = Options::parse_search(request.args)
+            .map_err(|error| ApiResponse::error(400, &error.to_string()))?
+            .balance_config();
+        let roster = Roster::parse(&request.roster)
+            .map_err(|error| ApiResponse::error(400, &error.to_string()))?;
+        if roster.is_empty() {
+            return Err(ApiResponse::error(400, "The roster has no players"));
+        }
+        let id = self.start_job(roster, config, None);
+        Ok(ApiResponse::accepted(json!({ "id": id })))
+    }
+
+    fn list_jobs(&self) -> ApiResponse {
+        let jobs = self.jobs.lock().unwrap();
+        let mut ids: Vec<&u64> = jobs.keys().collect();
+        ids.sort();
+        ApiResponse::ok(Value::Array(
+            ids.into_iter().map(|id| jobs[id].status(*id)).collect(),
+        ))
+    }
+
+    fn with_job<T>(&self, id: &str, f: impl FnOnce(u64, &Job) -> T) -> Result<T, ApiResponse> {
+        let id: u64 = id
+            .parse()
+            .map_err(|_| ApiResponse::error(400, &format!("Invalid job id {}", id)))?;
+        let jobs = self.jobs.lock().unwrap();
+        let job = jobs
+            .get(&id)
+            .ok_or_else(|| ApiResponse::error(404, &format!("There is no job {}", id)))?;
+        Ok(f(id, job))
+    }
+
+    fn job_status(&self, id: &str) -> Result<ApiResponse, ApiResponse> {
+        self.with_job(id, |id, job| ApiResponse::ok(job.status(id)))
+    }
+
+    fn cancel_job(&self, id: &str) -> Result<ApiResponse, ApiResponse> {
+        self.with_job(id, |id, job| {
+            job.cancel.cancel();
+            ApiResponse::ok(job.status(id))
+        })
+    }
+
+    fn finished_result(job: &Job) -> Result<&SearchResult, ApiResponse> {
+        match job.state {
+            JobState::Running => Err(ApiResponse::error(409, "The job is still running")),
+            JobState::Failed => Err(ApiResponse::error(
+                409,
+                job.error.as_deref().unwrap_or("The job failed"),
+            )),
+            JobState::Done => Ok(job.result.as_ref().unwrap()),
+        }
+    }
+
+    fn job_scrims(&self, id: &str) -> Result<ApiResponse, ApiResponse> {
+        self.with_job(id, |_, job| {
+            Api::finished_result(job).map(|result| ApiResponse::ok(json!(result)))
+        })?
+    }
+
+    fn find_scrim(job: &Job, scrim: &str) -> Result<ScrimContext, ApiResponse> {
+        let result = Api::finished_result(job)?;
+        scrim_id::find_scrim(&result.scrims, scrim)
+            .cloned()
+            .ok_or_else(|| ApiResponse::error(404, &format!("There is no scrim {}", scrim)))
+    }
+
+    fn explain(&self, id: &str, scrim: &str) -> Result<ApiResponse, ApiResponse> {
+        self.with_job(id, |_, job| {
+            Api::find_scrim(job, scrim)
+                .map(|context| ApiResponse::ok(json!(explain_scrim(&context))))
+        })?
+    }
+
+    fn rebalance(&self, id: &str, scrim: &str, body: &str) -> Result<ApiResponse, ApiResponse> {
+        let request: RebalanceRequest = serde_json::from_str(body)
+            .map_err(|error| ApiResponse::error(400, &format!("Invalid rebalance: {}", error)))?;
+        let (current, roster, config) = self.with_job(id, |_, job| {
+            Api::find_scrim(job, scrim)
+                .map(|current| (current, job.roster.clone(), job.config.clone()))
+        })??;
+
+        let roster = roster
+            .changed(&request.remove, &request.add)
+            .map_err(|error| ApiResponse::error(400, &error.to_string()))?;
+        let id = self.start_job(roster, config, Some(current));
+        Ok(ApiResponse::accepted(json!({ "id": id })))
+    }
+
+    // The rating of the lineup and the scrim as it would be written to scrims.txt
+    fn rate_lineup(body: &str) -> Result<ApiResponse, ApiResponse> {
+        let request: LineupRequest = serde_json::from_str(body)
+            .map_err(|error| ApiResponse::error(400, &format!("Invalid lineup: {}", error)))?;
+        let penalties = Options::parse_search(request.args)
+            .map_err(|error| ApiResponse::error(400, &error.to_string()))?
+            .penalties();
+        let roster = Roster::parse(&request.roster)
+            .map_err(|error| ApiResponse::error(400, &error.to_string()))?;
+        let context = rate_lineup(&request.lineup, &roster, penalties)
+            .map_err(|error| ApiResponse::error(400, &error.to_string()))?;
+        let text = ScrimRenderer::new(None)
+            .and_then(|renderer| renderer.render(&context))
+            .map_err(|error| ApiResponse::error(500, &error.to_string()))?;
+        Ok(ApiResponse::ok(json!({
+            "scrim": context,
+            "explanation": explain_scrim(&context),
+            "text": text,
+        })))
+    }
+
+    fn players_file(&self) -> Result<&str, ApiResponse> {
+        self.players_file
+            .as_deref()
+            .ok_or_else(|| ApiResponse::error(404, "The server was started without a players file"))
+    }
+
+    fn read_roster(&self) -> Result<ApiResponse, ApiResponse> {
+        let players_file = self.players_file()?;
+        let roster = fs::read_to_string(players_file).map_err(|error| {
+            ApiResponse::error(404, &format!("Could not read {}: {}", players_file, error))
+        })?;
+        Ok(ApiResponse::ok(
+            json!({ "file": players_file, "roster": roster }),
+        ))
+    }
+
+    fn write_roster(&self, body: &str) -> Result<ApiResponse, ApiResponse> {
+        let request: RosterRequest = serde_json::from_str(body)
+            .map_err(|error| ApiResponse::error(400, &format!("Invalid roster: {}", error)))?;
+        let players_file = self.players_file()?;
+        // Only a roster that reads back the same is saved, a bad request can't wipe the players file
+        let (roster, warnings) = Roster::parse_with_warnings(&request.roster)
+            .map_err(|error| ApiResponse::error(400, &error.to_string()))?;
+        if roster.is_empty() {
+            return Err(ApiResponse::error(400, "The roster has no players"));
+        }
+        let players = request
+            .roster
+            .lines()
+            .filter(|line| !line.trim().is_empty() && !line.starts_with('#'))
+            .count();
+        if roster.len() != players {
+            return Err(ApiResponse::error(
+                400,
+                &format!("Not every player could be read: {}", warnings.join(", ")),
+            ));
+        }
+        fs::write(players_file, &request.roster).map_err(|error| {
+            ApiResponse::error(500, &format!("Could not write {}: {}", players_file, error))
+        })?;
+        Ok(ApiResponse::ok(
+            json!({ "file": players_file, "players": roster.len() }),
+        ))
+    }
+}
+
+// The message a panic was started with
+pub(crate) fn panic_message(panic: &(dyn std::any::Any + Send)) -> String {
+    panic
+        .downcast_ref::<&str>()
+        .map(|message| message.to_string())
+        .or_else(|| panic.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown error".to_string())
+}
+
+const WEB_PAGE: &str = include_str!("../templates/web.html");
+
+// The API over HTTP, bound to a local address
+pub struct Server {
+    http: tiny_http::Server,
+    api: Api,
+    allow_remote: bool,
+}
+
+impl Server {
+    pub fn bind(address: &str) -> Result<Server> {
+        Server::with_api(address, Api::new(), false)
+    }
+
+    // The API has no authentication, so it only listens on a loopback address unless allow_remote is set.
+    // Requests also need a local Host, or a page that rebinds its domain to 127.0.0.1 could call the API.
+    pub fn with_api(address: &str, api: Api, allow_remote: bool) -> Result<Server> {
+        if !allow_remote {
+            let addresses: Vec<SocketAddr> = address
+                .to_socket_addrs()
+                .map_err(|error| anyhow!("Invalid address {}: {}", address, error))?
+                .collect();
+            if addresses.is_empty() || addresses.iter().any(|address| !address.ip().is_loopback()) {
+                return Err(anyhow!(
+                    "{} can be reached from other machines, use --allow-remote to listen on it anyway",
+                    address
+                ));
+            }
+        }
+        let http = tiny_http::Server::http(address)
+            .map_err(|error| anyhow!("Could not listen on {}: {}", address, error))?;
+        Ok(Server {
+            http,
+            api,
+            allow_remote,
+        })
+    }
+
+    pub fn local_address(&self) -> Option<SocketAddr> {
+        self.http.server_addr().to_ip()
+    }
+
+    // Answers requests until the server is shut down
+    pub fn run(&self) {
+        for request in self.http.incoming_requests() {
+            if let Err(error) = self.respond(request) {
+                println!("Error while answering a request: {}", error);
+            }
+        }
+    }
+
+    pub fn shutdown(&self) {
+        self.http.unblock();
+    }
+
+    // The Host of the request names this server on a loopback address
+    fn is_local_host(&self, request: &Request) -> bool {
+        let port = match self.local_address() {
+            Some(address) => address.port(),
+            None => return false,
+        };
+        let host = match request
+            .headers()
+            .iter()
+            .find(|header| header.field.equiv("Host"))
+        {
+            Some(header) => header.value.as_str().to_ascii_lowercase(),
+            None => return false,
+        };
+        ["localhost", "127.0.0.1", "[::1]"]
+            .iter()
+            .any(|name| host == format!("{}:{}", name, port) || (port == 80 && host == *name))
+    }
+
+    fn respond(&self, mut request: Request) -> Result<()> {
+        if !self.allow_remote && !self.is_local_host(&request) {
+            let content_type = Header::from_bytes("Content-Type", "application/json").unwrap();
+            let body = json!({ "error": "Only requests to localhost are answered" });
+            request.respond(
+                Response::from_string(body.to_string())
+                    .with_status_code(403)
+                    .with_header(content_type),
+            )?;
+            return Ok(());
+        }
+        let mut body = String::new();
+        request.as_reader().read_to_string(&mut body)?;
+        let method = match request.method() {
+            Method::Get => "GET",
+            Method::Post => "POST",
+            Method::Put => "PUT",
+            Method::Delete => "DELETE",
+            _ => "",
+        };
+        if method == "GET" && (request.url() == "/" || request.url() == "/index.html") {
+            let content_type =
+                Header::from_bytes("Content-Type", "text/html; charset=utf-8").unwrap();
+            request.respond(Response::from_string(WEB_PAGE).with_header(content_type))?;
+            return Ok(());
+        }
+        let response = self.api.handle(method, request.url(), &body);
+        let content_type = Header::from_bytes("Content-Type", "application/json").unwrap();
+        request.respond(
+            Response::from_string(response.body.to_string())
+                .with_status_code(response.status)
+                .with_header(content_type),
+        )?;
+        Ok(())
+    }
+}
@@ -0,0 +1,129 @@
+use crate::Player;
+use anyhow::{anyhow, Result};
+use std::collections::HashMap;
+
+// Pre-made stacks are groups of players that queue together, marked with "stack=<name>" in the roster.
+// A stack either plays on one team or sits on the bench as a whole.
+
+// Largest stack size a bonus can be set for, a full 6v6 team
+pub const MAX_STACK_SIZE: usize = 6;
+
+// Player ids of every stack with at least two players
+pub fn get_stacks(players: &HashMap<u8, Player>) -> Vec<Vec<u8>> {
+    let mut stack_map: HashMap<&str, Vec<u8>> = HashMap::new();
+    for (id, player) in players {
+        if let Some(stack) = &player.stack {
+            stack_map.entry(stack.as_str()).or_default().push(*id);
+        }
+    }
+    let mut stacks: Vec<Vec<u8>> = stack_map
+        .into_values()
+        .filter(|stack| stack.len() > 1)
+        .map(|mut stack| {
+            stack.sort_unstable();
+            stack
+        })
+        .collect();
+    stacks.sort();
+    stacks
+}
+
+// A stack that doesn't fit on one team can't be kept together, so the roster is rejected
+pub fn check_stacks(players: &HashMap<u8, Player>, team_size: usize) -> Result<()> {
+    for stack in get_stacks(players) {
+        if stack.len() > team_size {
+            let name = players[&stack[0]].stack.as_deref().unwrap_or_default();
+            return Err(anyhow!(
+                "Stack {} has {} players, more than fit on a team of {}",
+                name,
+                stack.len(),
+                team_size
+            ));
+        }
+    }
+    Ok(())
+}
+
+// Whether some players of a stack play while others are on the bench
+pub fn splits_bench(stacks: &[Vec<u8>], playing: &[u8]) -> bool {
+    stacks.iter().any(|stack| {
+        let number_playing = stack.iter().filter(|id| playing.contains(id)).count();
+        number_playing != 0 && number_playing != stack.len()
+    })
+}
+
+// Whether the players of a stack are spread over more than one team
+pub fn splits_teams(stacks: &[Vec<u8>], teams: &[Vec<u8>]) -> bool {
+    stacks.iter().any(|stack| {
+        teams
+            .iter()
+            .filter(|team| stack.iter().any(|id| team.contains(id)))
+            .count()
+            > 1
+    })
+}
+
+// Extra SR a team gets for playing together, the bonus of every stack on the team by its size
+fn team_bonus(
+    team: &[u8],
+    players: &HashMap<u8, Player>,
+    bonuses: &[i16; MAX_STACK_SIZE + 1],
+) -> f32 {
+    let mut stack_sizes: HashMap<&str, usize> = HashMap::new();
+    for id in team {
+        if let Some(stack) = &players[id].stack {
+            *stack_sizes.entry(stack.as_str()).or_insert(0) += 1;
+        }
+    }
+    stack_sizes
+        .values()
+        .filter(|size| **size > 1)
+        .map(|size| bonuses[(*size).min(MAX_STACK_SIZE)] as f32)
+        .sum()
+}
+
+// The stack bonus of every team
+pub fn team_bonuses(
+    teams: &[Vec<u8>],
+    players: &HashMap<u8, Player>,
+    bonuses: &[i16; MAX_STACK_SIZE + 1],
+) -> Vec<f32> {
+    teams
+        .iter()
+        .map(|team| team_bonus(team, players, bonuses))
+        .collect()
+}
+
+// Sum of the differences of the role averages to the averages over all teams
+fn sum_of_average_differences(role_averages: &[[f32; 3]]) -> f32 {
+    let number_of_teams = role_averages.len() as f32;
+    (0..3)
+        .map(|role| {
+            let average =
+                role_averages.iter().map(|team| team[role]).sum::<f32>() / number_of_teams;
+            role_averages
+                .iter()
+                .map(|team| (team[role] - average).abs())
+                .sum::<f32>()
+        })
+        .sum()
+}
+
+// Change of the rating once the stack bonus of every team is added to its SR.
+// The bonus raises all role averages of the team, which are weighed like in the rating,
+// so a stack can make up for a weaker lineup as well as tip an even one.
+pub fn stack_rating_change(
+    teams: &[Vec<u8>],
+    role_averages: &[[f32; 3]],
+    players: &HashMap<u8, Player>,
+    bonuses: &[i16; MAX_STACK_SIZE + 1],
+) -> i16 {
+    let effective_averages: Vec<[f32; 3]> = role_averages
+        .iter()
+        .zip(team_bonuses(teams, players, bonuses))
+        .map(|(averages, bonus)| averages.map(|average| average + bonus))
+        .collect();
+    let change =
+        sum_of_average_differences(&effective_averages) - sum_of_average_differences(role_averages);
+    (change * 5.0).round() as i16
+}
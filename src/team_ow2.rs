@@ -97,7 +97,7 @@ impl OW2Team {
             }
         }
 
-        (standard_deviation as f32).sqrt()
+        standard_deviation.sqrt()
     }
 
     pub fn get_average_sr(&self) -> f32 {
@@ -109,29 +109,29 @@ impl OW2Team {
         let average_sr = self.get_average_sr() as i32;
         let mut standard_deviation: i32 = 0;
         for player in self.get_all_player_srs() {
-            standard_deviation += (player as i32 - average_sr as i32).pow(2);
+            standard_deviation += (player as i32 - average_sr).pow(2);
         }
         (standard_deviation as f32).sqrt()
     }
 
     pub fn get_all_player_srs(&self) -> Vec<i16> {
-        let mut all_player_srs: Vec<i16> = Vec::new();
-        all_player_srs.push(self.tank.as_ref().unwrap().get_sr(Position::Tank));
-        all_player_srs.push(self.damage_1.as_ref().unwrap().get_sr(Position::Damage));
-        all_player_srs.push(self.damage_2.as_ref().unwrap().get_sr(Position::Damage));
-        all_player_srs.push(self.support_1.as_ref().unwrap().get_sr(Position::Support));
-        all_player_srs.push(self.support_2.as_ref().unwrap().get_sr(Position::Support));
-        all_player_srs
+        vec![
+            self.tank.as_ref().unwrap().get_sr(Position::Tank),
+            self.damage_1.as_ref().unwrap().get_sr(Position::Damage),
+            self.damage_2.as_ref().unwrap().get_sr(Position::Damage),
+            self.support_1.as_ref().unwrap().get_sr(Position::Support),
+            self.support_2.as_ref().unwrap().get_sr(Position::Support),
+        ]
     }
 
     pub fn _get_all_players(&self) -> Vec<Player> {
-        let mut all_players: Vec<Player> = Vec::new();
-        all_players.push(self.tank.as_ref().unwrap().clone());
-        all_players.push(self.damage_1.as_ref().unwrap().clone());
-        all_players.push(self.damage_2.as_ref().unwrap().clone());
-        all_players.push(self.support_1.as_ref().unwrap().clone());
-        all_players.push(self.support_2.as_ref().unwrap().clone());
-        all_players
+        vec![
+            self.tank.as_ref().unwrap().clone(),
+            self.damage_1.as_ref().unwrap().clone(),
+            self.damage_2.as_ref().unwrap().clone(),
+            self.support_1.as_ref().unwrap().clone(),
+            self.support_2.as_ref().unwrap().clone(),
+        ]
     }
 
     pub fn _to_full_string(&self) -> String {
@@ -170,11 +170,11 @@ impl OW2Team {
         s
     }
 
-    pub fn _get_id(&self) -> String {
+    pub fn get_id(&self) -> String {
         let mut s = String::new();
         // Append tanks to s sorted by name
         s.push_str(self.tank.as_ref().unwrap().name.as_str());
-        s.push_str(",");
+        s.push(',');
 
         // Append damage to s sorted by name
         let mut damage = vec![
@@ -184,7 +184,7 @@ impl OW2Team {
         damage.sort_by(|a, b| a.name.cmp(&b.name));
         for dam in damage {
             s.push_str(&dam.name);
-            s.push_str(",");
+            s.push(',');
         }
         // Append support to s sorted by name
         let mut support = vec![
@@ -194,7 +194,7 @@ impl OW2Team {
         support.sort_by(|a, b| a.name.cmp(&b.name));
         for sup in support {
             s.push_str(&sup.name);
-            s.push_str(",");
+            s.push(',');
         }
         s
     }
@@ -0,0 +1,87 @@
+use crate::context::ScrimContext;
+use anyhow::Result;
+use handlebars::{handlebars_helper, no_escape, Handlebars, JsonValue};
+use std::fs;
+
+// Layout of scrims.txt, used whenever no template file is given
+const DEFAULT_TEMPLATE: &str = include_str!("../templates/scrim.hbs");
+
+const TEMPLATE_NAME: &str = "scrim";
+
+fn to_text(value: &JsonValue) -> String {
+    match value {
+        JsonValue::String(s) => s.clone(),
+        JsonValue::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+// {{rjust value 25}} right-aligns the value in a column of the given width
+handlebars_helper!(rjust: |value: Json, width: u64| {
+    format!("{: >width$}", to_text(value), width = width as usize)
+});
+
+// {{ljust value 9}} left-aligns the value in a column of the given width
+handlebars_helper!(ljust: |value: Json, width: u64| {
+    format!("{: <width$}", to_text(value), width = width as usize)
+});
+
+// {{number value}} prints an SR value like the rest of the tool does,
+// {{number value digits=2}} prints it with a fixed number of decimals.
+// An average of no players is NaN, which comes out of the JSON as null and is printed as -
+handlebars_helper!(number: |value: Json, {digits: i64 = -1}| {
+    match value.as_f64() {
+        None => "-".to_string(),
+        Some(value) if digits < 0 => format!("{}", value as f32),
+        Some(value) => format!("{:.digits$}", value as f32, digits = digits as usize),
+    }
+});
+
+// {{concat a b c}} glues all parameters together into one string
+handlebars_helper!(concat: |*args| {
+    args.iter().map(|value| to_text(value)).collect::<String>()
+});
+
+// {{percent value max}} gives the share of value in max, used for the width of bars
+handlebars_helper!(percent: |value: Json, max: Json| {
+    match (value.as_f64(), max.as_f64()) {
+        (Some(value), Some(max)) if max > 0.0 => {
+            format!("{:.1}", (value / max * 100.0).clamp(0.0, 100.0))
+        }
+        _ => "0".to_string(),
+    }
+});
+
+// Registers the formatting helpers every scrim template can use
+pub fn register_helpers(handlebars: &mut Handlebars) {
+    handlebars.register_helper("rjust", Box::new(rjust));
+    handlebars.register_helper("ljust", Box::new(ljust));
+    handlebars.register_helper("number", Box::new(number));
+    handlebars.register_helper("concat", Box::new(concat));
+    handlebars.register_helper("percent", Box::new(percent));
+}
+
+pub struct ScrimRenderer<'a> {
+    handlebars: Handlebars<'a>,
+}
+
+impl<'a> ScrimRenderer<'a> {
+    // Loads the template from the given file, falls back to the default scrims.txt layout
+    pub fn new(template_file: Option<&str>) -> Result<ScrimRenderer<'a>> {
+        let template = match template_file {
+            Some(path) => fs::read_to_string(path)?,
+            None => DEFAULT_TEMPLATE.to_string(),
+        };
+
+        let mut handlebars = Handlebars::new();
+        handlebars.register_escape_fn(no_escape);
+        register_helpers(&mut handlebars);
+        handlebars.register_template_string(TEMPLATE_NAME, template)?;
+
+        Ok(ScrimRenderer { handlebars })
+    }
+
+    pub fn render(&self, context: &ScrimContext) -> Result<String> {
+        Ok(self.handlebars.render(TEMPLATE_NAME, context)?)
+    }
+}
@@ -0,0 +1,482 @@
+use crate::context::{ScrimContext, TeamContext};
+use crate::explain::explain_scrim;
+use crate::lineup::{rate_lineup, Lineup, Place};
+use crate::{Penalties, Roster};
+use anyhow::{anyhow, Result};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap};
+use ratatui::{DefaultTerminal, Frame};
+
+// Terminal UI to tweak the scrims by hand: pick one of them, swap players between teams, roles
+// and the bench, and see the rating and the role averages after every move. The scrims are saved
+// the same way the command line writes them.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Screen {
+    Scrims,
+    Lineup,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Continue,
+    Save,
+    Quit,
+}
+
+pub struct Editor {
+    pub scrims: Vec<ScrimContext>,
+    // The ratings before anything was moved
+    original_ratings: Vec<i16>,
+    roster: Roster,
+    penalties: Penalties,
+    pub screen: Screen,
+    pub selected: usize,
+    pub lineup: Lineup,
+    // Column and row of the cursor, the column after the last team is the bench
+    cursor: (usize, usize),
+    // The player that is swapped with the one under the cursor next
+    pub picked: Option<Place>,
+    // Lineups of the selected scrim before every move
+    history: Vec<Lineup>,
+    unsaved: bool,
+    // Quitting with unsaved changes needs a second q, esc or Ctrl-C
+    confirm_quit: bool,
+    pub message: String,
+}
+
+impl Editor {
+    pub fn new(scrims: Vec<ScrimContext>, roster: Roster, penalties: Penalties) -> Result<Editor> {
+        let lineup = Lineup::from_context(
+            scrims
+                .first()
+                .ok_or_else(|| anyhow!("There are no scrims to edit"))?,
+        );
+        Ok(Editor {
+            original_ratings: scrims.iter().map(|scrim| scrim.rating).collect(),
+            scrims,
+            roster,
+            penalties,
+            screen: Screen::Scrims,
+            selected: 0,
+            lineup,
+            cursor: (0, 0),
+            picked: None,
+            history: Vec::new(),
+            unsaved: false,
+            confirm_quit: false,
+            message: String::new(),
+        })
+    }
+
+    pub fn scrim(&self) -> &ScrimContext {
+        &self.scrims[self.selected]
+    }
+
+    pub fn handle_key(&mut self, key: KeyCode) -> Action {
+        let confirm_quit = self.confirm_quit;
+        self.confirm_quit = false;
+        self.message.clear();
+        match (self.screen, key) {
+            (_, KeyCode::Char('s')) => return Action::Save,
+            (_, KeyCode::Char('q')) => return self.quit(confirm_quit, "q"),
+            (Screen::Scrims, KeyCode::Up | KeyCode::Char('k')) => {
+                self.selected = self.selected.saturating_sub(1);
+            }
+            (Screen::Scrims, KeyCode::Down | KeyCode::Char('j')) => {
+                self.selected = (self.selected + 1).min(self.scrims.len() - 1);
+            }
+            (Screen::Scrims, KeyCode::Enter) => {
+                self.lineup = Lineup::from_context(self.scrim());
+                self.history.clear();
+                self.picked = None;
+                self.cursor = (0, 0);
+                self.screen = Screen::Lineup;
+            }
+            (Screen::Scrims, KeyCode::Esc) => return self.quit(confirm_quit, "esc"),
+            (Screen::Lineup, KeyCode::Left | KeyCode::Char('h')) => {
+                self.move_cursor(self.cursor.0.saturating_sub(1), self.cursor.1);
+            }
+            (Screen::Lineup, KeyCode::Right | KeyCode::Char('l')) => {
+                self.move_cursor(self.cursor.0 + 1, self.cursor.1);
+            }
+            (Screen::Lineup, KeyCode::Up | KeyCode::Char('k')) => {
+                self.move_cursor(self.cursor.0, self.cursor.1.saturating_sub(1));
+            }
+            (Screen::Lineup, KeyCode::Down | KeyCode::Char('j')) => {
+                self.move_cursor(self.cursor.0, self.cursor.1 + 1);
+            }
+            (Screen::Lineup, KeyCode::Char(' ') | KeyCode::Enter) => {
+                let place = self.place_at(self.cursor);
+                match self.picked.take() {
+                    None => self.picked = place,
+                    Some(picked) => {
+                        if let Some(place) = place.filter(|place| *place != picked) {
+                            self.swap(picked, place);
+                        }
+                    }
+                }
+            }
+            (Screen::Lineup, KeyCode::Char('u')) => self.undo(),
+            (Screen::Lineup, KeyCode::Esc) if self.picked.is_some() => self.picked = None,
+            (Screen::Lineup, KeyCode::Esc) => self.screen = Screen::Scrims,
+            _ => {}
+        }
+        Action::Continue
+    }
+
+    // Ctrl-C quits like q
+    pub fn interrupt(&mut self) -> Action {
+        let confirm_quit = self.confirm_quit;
+        self.confirm_quit = false;
+        self.message.clear();
+        self.quit(confirm_quit, "Ctrl-C")
+    }
+
+    fn quit(&mut self, confirm_quit: bool, key: &str) -> Action {
+        if !self.unsaved || confirm_quit {
+            return Action::Quit;
+        }
+        self.confirm_quit = true;
+        self.message = format!(
+            "Unsaved changes, press {} again to quit without saving",
+            key
+        );
+        Action::Continue
+    }
+
+    pub fn saved(&mut self) {
+        self.unsaved = false;
+    }
+
+    fn number_of_columns(&self) -> usize {
+        self.lineup.teams.len() + if self.lineup.bench.is_empty() { 0 } else { 1 }
+    }
+
+    fn number_of_rows(&self, column: usize) -> usize {
+        match self.lineup.teams.get(column) {
+            Some(team) => team.slots().len(),
+            None => self.lineup.bench.len(),
+        }
+    }
+
+    fn move_cursor(&mut self, column: usize, row: usize) {
+        let column = column.min(self.number_of_columns() - 1);
+        let row = row.min(self.number_of_rows(column).saturating_sub(1));
+        self.cursor = (column, row);
+    }
+
+    fn place_at(&self, (column, row): (usize, usize)) -> Option<Place> {
+        match self.lineup.teams.get(column) {
+            Some(team) => team
+                .slots()
+                .get(row)
+                .map(|(position, slot)| Place::Team(column, *position, *slot)),
+            None if row < self.lineup.bench.len() => Some(Place::Bench(row)),
+            None => None,
+        }
+    }
+
+    // Rates the lineup and puts it in place of the selected scrim
+    fn apply(&mut self, lineup: Lineup) -> bool {
+        // Players that can't play the round are in the roster, but not in the lineup
+        let names = lineup.names();
+        let roster = Roster {
+            players: self
+                .roster
+                .players
+                .iter()
+                .filter(|(_, player)| names.contains(&&player.name))
+                .map(|(id, player)| (*id, player.clone()))
+                .collect(),
+        };
+        match rate_lineup(&lineup, &roster, self.penalties) {
+            Ok(context) => {
+                self.scrims[self.selected] = context;
+                self.lineup = lineup;
+                self.unsaved = true;
+                true
+            }
+            Err(error) => {
+                self.message = error.to_string();
+                false
+            }
+        }
+    }
+
+    fn swap(&mut self, a: Place, b: Place) {
+        let mut lineup = self.lineup.clone();
+        if let Err(error) = lineup.swap(a, b) {
+            self.message = error.to_string();
+            return;
+        }
+        let previous = self.lineup.clone();
+        if self.apply(lineup) {
+            self.history.push(previous);
+        }
+    }
+
+    fn undo(&mut self) {
+        match self.history.pop() {
+            Some(lineup) => {
+                self.apply(lineup);
+            }
+            None => self.message = "Nothing to undo".to_string(),
+        }
+    }
+}
+
+pub fn run(
+    scrims: Vec<ScrimContext>,
+    roster: &Roster,
+    penalties: Penalties,
+    mut save: impl FnMut(&[ScrimContext]) -> Result<()>,
+) -> Result<()> {
+    let mut editor = Editor::new(scrims, roster.clone(), penalties)?;
+    let mut terminal = ratatui::init();
+    let result = run_editor(&mut terminal, &mut editor, &mut save);
+    ratatui::restore();
+    result
+}
+
+fn run_editor(
+    terminal: &mut DefaultTerminal,
+    editor: &mut Editor,
+    save: &mut impl FnMut(&[ScrimContext]) -> Result<()>,
+) -> Result<()> {
+    loop {
+        terminal.draw(|frame| draw(frame, editor))?;
+        let key = match event::read()? {
+            Event::Key(key) if key.kind == KeyEventKind::Press => key,
+            _ => continue,
+        };
+        // Ctrl-C doesn't send a signal while the terminal is in raw mode
+        let action =
+            if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+                editor.interrupt()
+            } else {
+                editor.handle_key(key.code)
+            };
+        match action {
+            Action::Continue => {}
+            Action::Save => {
+                editor.message = match save(&editor.scrims) {
+                    Ok(()) => {
+                        editor.saved();
+                        "Saved the scrims".to_string()
+                    }
+                    Err(error) => format!("Could not save the scrims: {}", error),
+                };
+                // Saving can print to the console, everything is drawn again
+                terminal.clear()?;
+            }
+            Action::Quit => return Ok(()),
+        }
+    }
+}
+
+fn draw(frame: &mut Frame, editor: &Editor) {
+    let [main, message, help] = Layout::vertical([
+        Constraint::Min(10),
+        Constraint::Length(1),
+        Constraint::Length(1),
+    ])
+    .areas(frame.area());
+
+    let help_text = match editor.screen {
+        Screen::Scrims => "↑↓ select  enter edit  s save  q quit",
+        Screen::Lineup => "←↑↓→ move  space pick up / swap  u undo  esc back  s save  q quit",
+    };
+    frame.render_widget(
+        Paragraph::new(editor.message.as_str()).style(Style::default().fg(Color::Yellow)),
+        message,
+    );
+    frame.render_widget(
+        Paragraph::new(help_text).style(Style::default().fg(Color::DarkGray)),
+        help,
+    );
+
+    match editor.screen {
+        Screen::Scrims => draw_scrims(frame, editor, main),
+        Screen::Lineup => draw_lineup(frame, editor, main),
+    }
+}
+
+fn rating_change(editor: &Editor, index: usize) -> String {
+    let change = editor.scrims[index].rating as i32 - editor.original_ratings[index] as i32;
+    match change {
+        0 => String::new(),
+        _ => format!(" ({:+})", change),
+    }
+}
+
+fn draw_scrims(frame: &mut Frame, editor: &Editor, area: ratatui::layout::Rect) {
+    let [list_area, preview_area] =
+        Layout::horizontal([Constraint::Length(36), Constraint::Min(20)]).areas(area);
+
+    let items: Vec<ListItem> = editor
+        .scrims
+        .iter()
+        .enumerate()
+        .map(|(i, scrim)| {
+            ListItem::new(format!(
+                "{} {}  rating {}{}",
+                scrim.label,
+                scrim.id,
+                scrim.rating,
+                rating_change(editor, i)
+            ))
+        })
+        .collect();
+    let mut state = ListState::default().with_selected(Some(editor.selected));
+    frame.render_stateful_widget(
+        List::new(items)
+            .block(Block::default().borders(Borders::ALL).title("Scrims"))
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED)),
+        list_area,
+        &mut state,
+    );
+
+    let scrim = editor.scrim();
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(format!("Scrim {}", scrim.label));
+    let inner = block.inner(preview_area);
+    frame.render_widget(block, preview_area);
+    let columns = Layout::horizontal(vec![Constraint::Fill(1); scrim.teams.len()]).split(inner);
+    for (team, column) in scrim.teams.iter().zip(columns.iter()) {
+        frame.render_widget(Paragraph::new(team_lines(team, None, None)), *column);
+    }
+}
+
+fn draw_lineup(frame: &mut Frame, editor: &Editor, area: ratatui::layout::Rect) {
+    let scrim = editor.scrim();
+    let [header, board, explanation] = Layout::vertical([
+        Constraint::Length(1),
+        Constraint::Length(14),
+        Constraint::Min(3),
+    ])
+    .areas(area);
+
+    frame.render_widget(
+        Paragraph::new(format!(
+            "Scrim {} {}  rating {}{}  average SR {:.2}",
+            scrim.label,
+            scrim.id,
+            scrim.rating,
+            rating_change(editor, editor.selected),
+            scrim.sr_average
+        ))
+        .style(Style::default().add_modifier(Modifier::BOLD)),
+        header,
+    );
+
+    let mut constraints = vec![Constraint::Fill(1); scrim.teams.len()];
+    if !editor.lineup.bench.is_empty() {
+        constraints.push(Constraint::Length(24));
+    }
+    let columns = Layout::horizontal(constraints).split(board);
+    let row_in = |column: usize, place: Option<Place>| -> Option<usize> {
+        (0..editor.number_of_rows(column)).find(|row| editor.place_at((column, *row)) == place)
+    };
+    for (i, team) in scrim.teams.iter().enumerate() {
+        let cursor = Some(editor.cursor.1).filter(|_| editor.cursor.0 == i);
+        let picked = editor.picked.and_then(|picked| row_in(i, Some(picked)));
+        frame.render_widget(
+            Paragraph::new(team_lines(team, cursor, picked)).block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(team.name.clone()),
+            ),
+            columns[i],
+        );
+    }
+    if !editor.lineup.bench.is_empty() {
+        let column = scrim.teams.len();
+        let picked = editor
+            .picked
+            .and_then(|picked| row_in(column, Some(picked)));
+        let lines: Vec<Line> = editor
+            .lineup
+            .bench
+            .iter()
+            .enumerate()
+            .map(|(row, name)| {
+                let style = slot_style(editor.cursor == (column, row), picked == Some(row));
+                Line::from(Span::styled(name.clone(), style))
+            })
+            .collect();
+        frame.render_widget(
+            Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title("Bench")),
+            columns[column],
+        );
+    }
+
+    let lines: Vec<Line> = explain_scrim(scrim)
+        .lines
+        .into_iter()
+        .map(Line::from)
+        .collect();
+    frame.render_widget(
+        Paragraph::new(lines)
+            .wrap(Wrap { trim: true })
+            .block(Block::default().borders(Borders::ALL).title("Rating")),
+        explanation,
+    );
+}
+
+fn slot_style(is_cursor: bool, is_picked: bool) -> Style {
+    let mut style = Style::default();
+    if is_picked {
+        style = style.fg(Color::Yellow).add_modifier(Modifier::BOLD);
+    }
+    if is_cursor {
+        style = style.add_modifier(Modifier::REVERSED);
+    }
+    style
+}
+
+// The players of a team by role with their SR, then the role averages
+fn team_lines(
+    team: &TeamContext,
+    cursor: Option<usize>,
+    picked: Option<usize>,
+) -> Vec<Line<'static>> {
+    let mut lines: Vec<Line> = Vec::new();
+    let mut row = 0;
+    for role in &team.roles {
+        for player in &role.players {
+            let name = match &player.sub_role {
+                Some(sub_role) => format!("{} [{}]", player.name, sub_role),
+                None => player.name.clone(),
+            };
+            lines.push(Line::from(vec![
+                Span::styled(
+                    format!("{:<8}", role.label),
+                    Style::default().fg(Color::DarkGray),
+                ),
+                Span::styled(
+                    format!("{:<18}", name),
+                    slot_style(cursor == Some(row), picked == Some(row)),
+                ),
+                Span::raw(format!("{:>5}", player.sr)),
+            ]));
+            row += 1;
+        }
+    }
+    lines.push(Line::from(""));
+    for role in &team.roles {
+        lines.push(Line::from(Span::styled(
+            format!("{:<8}{:>23.2}", role.label, role.average_sr),
+            Style::default().fg(Color::DarkGray),
+        )));
+    }
+    lines.push(Line::from(format!(
+        "{:<8}{:>23.2}",
+        "Average", team.average_sr
+    )));
+    lines
+}
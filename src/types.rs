@@ -1,6 +1,97 @@
+use crate::stacks::MAX_STACK_SIZE;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Position {
     Tank = 0,
     Damage = 1,
     Support = 2,
 }
+
+impl Position {
+    // Short name used for the row labels of the scrim output
+    pub fn label(self) -> &'static str {
+        match self {
+            Position::Tank => "Tank",
+            Position::Damage => "DPS",
+            Position::Support => "Support",
+        }
+    }
+}
+
+// Finer split of a position, two players of the same position should complement each other
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SubRole {
+    MainTank,
+    OffTank,
+    Hitscan,
+    Projectile,
+    MainSupport,
+    FlexSupport,
+}
+
+impl SubRole {
+    // Name used in the roster, e.g. "subroles=off_tank,hitscan"
+    pub fn parse(name: &str) -> Option<SubRole> {
+        match name {
+            "main_tank" => Some(SubRole::MainTank),
+            "off_tank" => Some(SubRole::OffTank),
+            "hitscan" => Some(SubRole::Hitscan),
+            "projectile" => Some(SubRole::Projectile),
+            "main_support" => Some(SubRole::MainSupport),
+            "flex_support" => Some(SubRole::FlexSupport),
+            _ => None,
+        }
+    }
+
+    pub fn position(self) -> Position {
+        match self {
+            SubRole::MainTank | SubRole::OffTank => Position::Tank,
+            SubRole::Hitscan | SubRole::Projectile => Position::Damage,
+            SubRole::MainSupport | SubRole::FlexSupport => Position::Support,
+        }
+    }
+
+    // Both sub-roles of a position
+    pub fn of_position(position: Position) -> [SubRole; 2] {
+        match position {
+            Position::Tank => [SubRole::MainTank, SubRole::OffTank],
+            Position::Damage => [SubRole::Hitscan, SubRole::Projectile],
+            Position::Support => [SubRole::MainSupport, SubRole::FlexSupport],
+        }
+    }
+
+    // Short name shown next to the player in the scrim output
+    pub fn label(self) -> &'static str {
+        match self {
+            SubRole::MainTank => "MT",
+            SubRole::OffTank => "OT",
+            SubRole::Hitscan => "HS",
+            SubRole::Projectile => "PJ",
+            SubRole::MainSupport => "MS",
+            SubRole::FlexSupport => "FS",
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            SubRole::MainTank => "Main Tank",
+            SubRole::OffTank => "Off Tank",
+            SubRole::Hitscan => "Hitscan",
+            SubRole::Projectile => "Projectile",
+            SubRole::MainSupport => "Main Support",
+            SubRole::FlexSupport => "Flex Support",
+        }
+    }
+}
+
+// Extra rating added to a scrim during the search for every conflict the scrim has
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Penalties {
+    pub hero_conflict: i16,
+    pub sub_role_clash: i16,
+    pub language_conflict: i16,
+    // Scrims with a team without a common language are dropped instead
+    pub require_common_language: bool,
+    // SR bonus for the coordination of a stack, by the size of the stack
+    pub stack_bonuses: [i16; MAX_STACK_SIZE + 1],
+}
@@ -0,0 +1,46 @@
+use crate::options::Options;
+use crate::{balance, Roster, SearchProgress};
+use anyhow::{anyhow, Result};
+use serde_json::json;
+
+// The balancer for a static page without a backend, built to WebAssembly with wasm-pack:
+//
+//     wasm-pack build --target web --out-dir www/pkg --no-default-features
+//
+// JavaScript calls balance(roster, args) with a roster in the format of players.txt and the
+// command line options of the search, like "--time-limit 10", and gets back JSON. Only the options in
+// SEARCH_OPTIONS are taken, like by the server:
+//
+//     {"scrims": [...], "complete": true, "warnings": ["Unknown hero Bob in the pool of Ana"]}
+//
+// The scrims look like the ones of GET /jobs/<id>/scrims. Browsers don't start threads for
+// WebAssembly, so the search runs on the calling thread with or without the threads feature, a
+// Web Worker keeps the page responsive meanwhile. www/ is such a page.
+
+// The search behind balance(), also usable without WebAssembly
+pub fn balance_json(roster: &str, args: &str) -> Result<String> {
+    let args: Vec<String> = args.split_whitespace().map(str::to_string).collect();
+    let config = Options::parse_search(args)?.balance_config();
+    let (roster, warnings) = Roster::parse_with_warnings(roster)?;
+    if roster.is_empty() {
+        return Err(anyhow!("The roster has no players"));
+    }
+    let result = balance(&roster, &config, &SearchProgress::silent())?;
+    Ok(json!({
+        "scrims": result.scrims,
+        "complete": result.complete,
+        "warnings": warnings,
+    })
+    .to_string())
+}
+
+#[cfg(target_arch = "wasm32")]
+mod bindings {
+    use wasm_bindgen::prelude::*;
+
+    // Errors are thrown as JavaScript errors with the message of the balancer
+    #[wasm_bindgen]
+    pub fn balance(roster: &str, args: &str) -> Result<String, JsError> {
+        super::balance_json(roster, args).map_err(|error| JsError::new(&error.to_string()))
+    }
+}
@@ -0,0 +1,151 @@
+mod common;
+
+use autophil::availability::{
+    check_session, is_available, parse_time, players_for_round, Availability, Session,
+};
+use autophil::{balance, BalanceConfig, Player, Roster, SearchProgress};
+
+const ROSTER: &str = "Always - 2500 - 2500 - 2500 - tds
+Leaver - 2500 - 2500 - 2500 - tds - available=1-2
+Latecomer - 2500 - 2500 - 2500 - tds - available=3-
+Breaks - 2500 - 2500 - 2500 - tds - available=1,3
+EarlyBird - 2500 - 2500 - 2500 - tds - available=19:00-20:00
+NightOwl - 2500 - 2500 - 2500 - tds - available=19:30-
+";
+
+fn session(start: Option<&str>) -> Session {
+    Session {
+        start: start.map(|start| parse_time(start).unwrap()),
+        round_length: 30,
+    }
+}
+
+// Names of the players of the round, sorted
+fn playing(roster: &Roster, round: usize, session: &Session) -> Vec<String> {
+    let mut names: Vec<String> = players_for_round(&roster.players, round, session)
+        .into_values()
+        .map(|player| player.name)
+        .collect();
+    names.sort();
+    names
+}
+
+#[test]
+fn rounds_leave_out_late_joiners_and_early_leavers() {
+    let roster = Roster::parse(ROSTER).unwrap();
+    let session = session(Some("19:00"));
+
+    // 19:00-19:30
+    assert_eq!(
+        playing(&roster, 1, &session),
+        ["Always", "Breaks", "EarlyBird", "Leaver"]
+    );
+    // 19:30-20:00
+    assert_eq!(
+        playing(&roster, 2, &session),
+        ["Always", "EarlyBird", "Leaver", "NightOwl"]
+    );
+    // 20:00-20:30
+    assert_eq!(
+        playing(&roster, 3, &session),
+        ["Always", "Breaks", "Latecomer", "NightOwl"]
+    );
+    assert_eq!(
+        playing(&roster, 10, &session),
+        ["Always", "Latecomer", "NightOwl"]
+    );
+}
+
+#[test]
+fn times_need_the_whole_round() {
+    let roster = Roster::parse("Short - 2500 - 2500 - 2500 - tds - available=19:10-20:00").unwrap();
+    let player = roster.players.values().next().unwrap();
+    let session = session(Some("19:00"));
+
+    // The first round starts before the player arrives, the third one ends after they left
+    assert!(!is_available(player, 1, &session));
+    assert!(is_available(player, 2, &session));
+    assert!(!is_available(player, 3, &session));
+    // 19:40-20:00 with shorter rounds
+    let shorter = Session {
+        round_length: 20,
+        ..session
+    };
+    assert!(is_available(player, 3, &shorter));
+}
+
+#[test]
+fn times_need_the_session_start() {
+    let roster = Roster::parse(ROSTER).unwrap();
+
+    let error = check_session(&roster.players, &session(None)).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "The roster has availability times, the session start is needed (--session-start 19:00)"
+    );
+    assert!(check_session(&roster.players, &session(Some("19:00"))).is_ok());
+
+    // Rounds alone work without it
+    let rounds_only =
+        Roster::parse(&ROSTER.lines().take(4).collect::<Vec<_>>().join("\n")).unwrap();
+    assert!(check_session(&rounds_only.players, &session(None)).is_ok());
+    assert_eq!(
+        playing(&rounds_only, 2, &session(None)),
+        ["Always", "Leaver"]
+    );
+}
+
+#[test]
+fn windows_are_parsed_from_rounds_and_times() {
+    assert_eq!(
+        Availability::parse("2-").unwrap(),
+        Availability::Rounds(2, None)
+    );
+    assert_eq!(
+        Availability::parse("3").unwrap(),
+        Availability::Rounds(3, Some(3))
+    );
+    assert_eq!(
+        Availability::parse("18:30-19:15").unwrap(),
+        Availability::Time(18 * 60 + 30, Some(19 * 60 + 15))
+    );
+    assert!(Availability::parse("0-2").is_err());
+    assert!(Availability::parse("soon").is_err());
+}
+
+#[test]
+fn windows_that_end_before_they_start_are_an_error() {
+    assert!(Availability::parse("3-1").is_err());
+    assert!(Availability::parse("23:00-01:00").is_err());
+    assert!(Availability::parse("19:00-19:00").is_ok());
+
+    let line = "Backwards - 2500 - 2500 - 2500 - tds - available=3-1";
+    let error = Player::new(line.to_string()).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Invalid availability 3-1 of Backwards: The window ends before it starts: 3-1"
+    );
+    let (roster, warnings) = Roster::parse_with_warnings(line).unwrap();
+    assert!(roster.is_empty());
+    assert!(warnings[0].contains("Backwards"));
+}
+
+#[test]
+fn every_round_needs_a_full_team() {
+    // Only five players stay for the second round
+    let leavers = [
+        "Edgers", "TrixCold", "KingBoo", "Fool", "Starboy", "NootNoot", "michealw",
+    ];
+    let fields: Vec<(&str, &str)> = leavers.iter().map(|name| (*name, "available=1")).collect();
+    let roster = common::roster_with(&fields);
+    let config = BalanceConfig {
+        series: Some(2),
+        ..common::config()
+    };
+
+    let error = balance(&roster, &config, &SearchProgress::silent()).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Round 2 has 5 available players, a team needs 6"
+    );
+}
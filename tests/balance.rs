@@ -0,0 +1,208 @@
+mod common;
+
+use autophil::naming::TeamNaming;
+use autophil::progress::Phase;
+use autophil::{
+    balance, scrim_id, BalanceConfig, CancellationToken, ProgressEvent, ProgressObserver,
+    ScrimContext, SearchProgress,
+};
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+// Every player of the roster plays exactly once
+fn assert_everyone_plays(scrim: &ScrimContext, number_of_teams: usize, team_size: usize) {
+    assert_eq!(scrim.teams.len(), number_of_teams);
+    let mut names: HashSet<&str> = HashSet::new();
+    for team in &scrim.teams {
+        assert_eq!(team.players.len(), team_size);
+        for player in &team.players {
+            assert!(names.insert(&player.name), "{} plays twice", player.name);
+        }
+    }
+}
+
+#[test]
+fn six_v_six_scrims_are_ranked_by_rating() {
+    let result = balance(
+        &common::roster(),
+        &common::config(),
+        &SearchProgress::silent(),
+    )
+    .unwrap();
+
+    assert!(result.complete);
+    assert_eq!(result.scrims.len(), 5);
+    let labels: Vec<&str> = result
+        .scrims
+        .iter()
+        .map(|scrim| scrim.label.as_str())
+        .collect();
+    assert_eq!(labels, vec!["A", "B", "C", "D", "E"]);
+    for pair in result.scrims.windows(2) {
+        assert!(pair[0].rating <= pair[1].rating);
+    }
+    for scrim in &result.scrims {
+        assert_everyone_plays(scrim, 2, 6);
+        assert!(scrim.bench.is_empty());
+    }
+}
+
+#[test]
+fn ow2_scrims_have_teams_of_five() {
+    let config = BalanceConfig {
+        ow_2: true,
+        ..common::config()
+    };
+    let result = balance(&common::roster_of(10), &config, &SearchProgress::silent()).unwrap();
+
+    assert!(result.complete);
+    assert!(!result.scrims.is_empty());
+    for scrim in &result.scrims {
+        assert_everyone_plays(scrim, 2, 5);
+    }
+}
+
+#[test]
+fn roster_smaller_than_a_team_is_an_error() {
+    let ow2 = BalanceConfig {
+        ow_2: true,
+        ..common::config()
+    };
+
+    assert!(balance(
+        &common::roster_of(5),
+        &common::config(),
+        &SearchProgress::silent()
+    )
+    .is_err());
+    assert!(balance(&common::roster_of(4), &ow2, &SearchProgress::silent()).is_err());
+}
+
+#[test]
+fn cancelled_search_stops_without_scrims() {
+    let cancel = CancellationToken::new();
+    cancel.cancel();
+    let progress = SearchProgress::new(SearchProgress::silent().observer, cancel);
+
+    assert!(balance(&common::roster(), &common::config(), &progress).is_err());
+}
+
+#[test]
+fn search_without_time_is_stopped() {
+    let config = BalanceConfig {
+        time_limit: Some(Duration::from_secs(0)),
+        ..common::config()
+    };
+
+    assert!(balance(&common::roster(), &config, &SearchProgress::silent()).is_err());
+}
+
+#[derive(Default)]
+struct Recorder {
+    events: Mutex<Vec<ProgressEvent>>,
+    best_ratings: Mutex<Vec<i16>>,
+}
+
+impl ProgressObserver for Recorder {
+    fn on_progress(&self, event: &ProgressEvent) {
+        self.events.lock().unwrap().push(event.clone());
+    }
+
+    fn on_best_scrim(&self, scrim: &ScrimContext) {
+        self.best_ratings.lock().unwrap().push(scrim.rating);
+    }
+}
+
+#[test]
+fn observer_sees_every_phase_and_the_best_scrims() {
+    let recorder = Arc::new(Recorder::default());
+    let progress = SearchProgress::new(recorder.clone(), CancellationToken::new());
+    let result = balance(&common::roster(), &common::config(), &progress).unwrap();
+
+    let events = recorder.events.lock().unwrap();
+    for phase in [Phase::Matchups, Phase::Scrims] {
+        let last = events
+            .iter()
+            .rev()
+            .find(|event| event.phase == phase)
+            .unwrap();
+        assert!(last.is_finished());
+        assert_eq!(last.done, last.total);
+    }
+
+    // Every published scrim is better than the one before, the last one is the best
+    let best_ratings = recorder.best_ratings.lock().unwrap();
+    for pair in best_ratings.windows(2) {
+        assert!(pair[1] < pair[0]);
+    }
+    assert_eq!(best_ratings.last(), Some(&result.scrims[0].rating));
+}
+
+#[test]
+fn alternatives_share_their_team_names() {
+    let config = BalanceConfig {
+        team_naming: TeamNaming::Theme("space".to_string(), 7),
+        ..common::config()
+    };
+    let result = balance(&common::roster(), &config, &SearchProgress::silent()).unwrap();
+
+    let names: Vec<&str> = result.scrims[0]
+        .teams
+        .iter()
+        .map(|team| team.name.as_str())
+        .collect();
+    assert!(!names.contains(&"Fighting Foxes"));
+    for scrim in &result.scrims {
+        let scrim_names: Vec<&str> = scrim.teams.iter().map(|team| team.name.as_str()).collect();
+        assert_eq!(scrim_names, names);
+    }
+}
+
+#[test]
+fn series_plans_one_scrim_per_round() {
+    let config = BalanceConfig {
+        series: Some(3),
+        ..common::config()
+    };
+    let result = balance(&common::roster(), &config, &SearchProgress::silent()).unwrap();
+
+    assert_eq!(result.scrims.len(), 3);
+    for scrim in &result.scrims {
+        assert_everyone_plays(scrim, 2, 6);
+    }
+    // Every round gets new names, starting with the default ones
+    let names: Vec<&str> = result
+        .scrims
+        .iter()
+        .flat_map(|scrim| scrim.teams.iter().map(|team| team.name.as_str()))
+        .collect();
+    assert_eq!(
+        names,
+        [
+            "Fighting Foxes",
+            "Dancing Dragons",
+            "Iron Wolves",
+            "Strike Team 404",
+            "Knightly Kittens",
+            "Suwon Tigers",
+        ]
+    );
+}
+
+#[test]
+fn scrims_are_found_by_label_before_id() {
+    let mut scrims = balance(
+        &common::roster(),
+        &common::config(),
+        &SearchProgress::silent(),
+    )
+    .unwrap()
+    .scrims;
+    // "b" is also the start of an id
+    scrims[0].id = "b0a1c2d3".to_string();
+
+    assert_eq!(scrim_id::find_scrim(&scrims, "B").unwrap().label, "B");
+    assert_eq!(scrim_id::find_scrim(&scrims, "b0a1").unwrap().label, "A");
+    assert!(scrim_id::find_scrim(&scrims, "Z").is_none());
+}
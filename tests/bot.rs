@@ -0,0 +1,377 @@
+mod common;
+
+use anyhow::{anyhow, Result};
+use autophil::bot::{
+    self, Bot, BotConfig, BotEvent, Gateway, DAMAGE_EMOJI, SUPPORT_EMOJI, TANK_EMOJI,
+};
+use autophil::Position;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+const CHANNEL: &str = "scrims";
+
+// Keeps everything the bot does in memory instead of sending it to Discord
+#[derive(Default)]
+struct FakeGateway {
+    messages: Vec<(String, String)>,
+    reactions: Vec<(String, String)>,
+    voice_channels: HashMap<String, String>,
+    // Users that aren't connected to voice, moving them fails
+    offline: Vec<String>,
+}
+
+impl FakeGateway {
+    fn last_message(&self) -> &str {
+        &self.messages.last().unwrap().1
+    }
+}
+
+impl Gateway for FakeGateway {
+    fn send_message(&mut self, channel: &str, text: &str) -> Result<String> {
+        self.messages.push((channel.to_string(), text.to_string()));
+        Ok(format!("message-{}", self.messages.len()))
+    }
+
+    fn add_reaction(&mut self, _channel: &str, message_id: &str, emoji: &str) -> Result<()> {
+        self.reactions
+            .push((message_id.to_string(), emoji.to_string()));
+        Ok(())
+    }
+
+    fn move_to_voice_channel(&mut self, user: &str, voice_channel: &str) -> Result<()> {
+        if self.offline.iter().any(|offline| offline == user) {
+            return Err(anyhow!("{} is not connected to voice", user));
+        }
+        self.voice_channels
+            .insert(user.to_string(), voice_channel.to_string());
+        Ok(())
+    }
+}
+
+fn config() -> BotConfig {
+    BotConfig {
+        team_channels: vec!["Team 1".to_string(), "Team 2".to_string()],
+        lobby_channel: Some("Lobby".to_string()),
+        organisers: vec!["Phil".to_string()],
+        balance_args: Vec::new(),
+    }
+}
+
+fn say(bot: &mut Bot, gateway: &mut FakeGateway, user: &str, text: &str) {
+    let event = BotEvent::Message {
+        channel: CHANNEL.to_string(),
+        user: user.to_string(),
+        text: text.to_string(),
+    };
+    bot.handle(&event, gateway).unwrap();
+}
+
+fn react(bot: &mut Bot, gateway: &mut FakeGateway, user: &str, emoji: &str, added: bool) {
+    let message_id = bot.signup_message.clone().unwrap();
+    let event = if added {
+        BotEvent::ReactionAdded {
+            message_id,
+            user: user.to_string(),
+            emoji: emoji.to_string(),
+        }
+    } else {
+        BotEvent::ReactionRemoved {
+            message_id,
+            user: user.to_string(),
+            emoji: emoji.to_string(),
+        }
+    };
+    bot.handle(&event, gateway).unwrap();
+}
+
+// Every player of the roster signs up for the roles they play in the roster
+fn sign_up_everyone(bot: &mut Bot, gateway: &mut FakeGateway) {
+    say(bot, gateway, "Phil", "!signup");
+    let roster = common::roster();
+    let mut ids: Vec<&u8> = roster.players.keys().collect();
+    ids.sort();
+    for id in ids {
+        let player = &roster.players[id];
+        for position in [Position::Tank, Position::Damage, Position::Support] {
+            if player.plays_position(position) {
+                let emoji = match position {
+                    Position::Tank => TANK_EMOJI,
+                    Position::Damage => DAMAGE_EMOJI,
+                    Position::Support => SUPPORT_EMOJI,
+                };
+                react(bot, gateway, &player.name, emoji, true);
+            }
+        }
+    }
+}
+
+#[test]
+fn signup_message_gets_a_reaction_per_role() {
+    let mut bot = Bot::new(&common::roster(), config());
+    let mut gateway = FakeGateway::default();
+
+    say(&mut bot, &mut gateway, "Phil", "!signup");
+
+    assert_eq!(bot.signup_message.as_deref(), Some("message-1"));
+    assert!(gateway.last_message().starts_with("Signups are open"));
+    let emoji: Vec<&str> = gateway.reactions.iter().map(|(_, e)| e.as_str()).collect();
+    assert_eq!(emoji, vec![TANK_EMOJI, DAMAGE_EMOJI, SUPPORT_EMOJI]);
+}
+
+#[test]
+fn reactions_pick_the_roles() {
+    let mut bot = Bot::new(&common::roster(), config());
+    let mut gateway = FakeGateway::default();
+    say(&mut bot, &mut gateway, "Phil", "!signup");
+
+    react(&mut bot, &mut gateway, "KingBoo", TANK_EMOJI, true);
+    // Some clients send the emoji with a variation selector
+    react(&mut bot, &mut gateway, "KingBoo", "💉\u{fe0f}", true);
+    react(&mut bot, &mut gateway, "Juun", SUPPORT_EMOJI, true);
+    react(&mut bot, &mut gateway, "Juun", "🎉", true);
+    assert_eq!(bot.signups.len(), 2);
+    assert!(bot.signups[0].tank && !bot.signups[0].damage && bot.signups[0].support);
+
+    react(&mut bot, &mut gateway, "KingBoo", TANK_EMOJI, false);
+    assert!(!bot.signups[0].tank && bot.signups[0].support);
+    // Taking back the last role takes back the signup
+    react(&mut bot, &mut gateway, "Juun", SUPPORT_EMOJI, false);
+    assert_eq!(bot.signups.len(), 1);
+
+    // Reactions to other messages don't count
+    bot.handle(
+        &BotEvent::ReactionAdded {
+            message_id: "another message".to_string(),
+            user: "Fool".to_string(),
+            emoji: TANK_EMOJI.to_string(),
+        },
+        &mut gateway,
+    )
+    .unwrap();
+    assert_eq!(bot.signups.len(), 1);
+
+    say(&mut bot, &mut gateway, "KingBoo", "!signups");
+    assert_eq!(
+        gateway.last_message(),
+        "1 players signed up:\nKingBoo (Support)"
+    );
+}
+
+#[test]
+fn roster_of_the_signups_uses_the_picked_roles() {
+    let mut bot = Bot::new(&common::roster(), config());
+    let mut gateway = FakeGateway::default();
+    say(&mut bot, &mut gateway, "Phil", "!signup");
+    // KingBoo plays every role in the roster but only signs up for damage
+    react(&mut bot, &mut gateway, "KingBoo", DAMAGE_EMOJI, true);
+    react(&mut bot, &mut gateway, "Edgers", TANK_EMOJI, true);
+
+    let roster = bot.signed_up_roster().unwrap();
+    assert_eq!(roster.len(), 2);
+    assert_eq!(roster.players[&1].name, "KingBoo");
+    assert!(!roster.players[&1].plays_tank);
+    assert!(roster.players[&1].plays_damage);
+    assert_eq!(roster.players[&1].damage_sr, 2600);
+}
+
+#[test]
+fn unknown_players_register_before_balancing() {
+    let mut bot = Bot::new(&common::roster(), config());
+    let mut gateway = FakeGateway::default();
+    say(&mut bot, &mut gateway, "Phil", "!signup");
+    react(&mut bot, &mut gateway, "Newcomer", SUPPORT_EMOJI, true);
+
+    say(&mut bot, &mut gateway, "Phil", "!balance");
+    assert!(gateway
+        .last_message()
+        .starts_with("Not registered yet: Newcomer"));
+
+    say(&mut bot, &mut gateway, "Newcomer", "!register 0 0 banana");
+    assert!(gateway.last_message().starts_with("Register with"));
+    say(&mut bot, &mut gateway, "Newcomer", "!register 0 0 2400");
+    assert_eq!(
+        gateway.last_message(),
+        "Registered Newcomer with 0 tank, 0 damage and 2400 support SR"
+    );
+    assert_eq!(bot.signed_up_roster().unwrap().players[&1].support_sr, 2400);
+}
+
+#[test]
+fn more_signups_than_a_roster_holds_are_an_error() {
+    let mut bot = Bot::new(&common::roster(), config());
+    let mut gateway = FakeGateway::default();
+    say(&mut bot, &mut gateway, "Phil", "!signup");
+    for i in 0..256 {
+        let user = format!("Player{}", i);
+        say(&mut bot, &mut gateway, &user, "!register 2500 2500 2500");
+        react(&mut bot, &mut gateway, &user, TANK_EMOJI, true);
+    }
+
+    assert!(bot.signed_up_roster().is_err());
+    say(&mut bot, &mut gateway, "Phil", "!balance");
+    assert_eq!(
+        gateway.last_message(),
+        "A roster can't have more than 255 players"
+    );
+}
+
+#[test]
+fn only_organisers_run_the_scrims() {
+    let mut bot = Bot::new(&common::roster(), config());
+    let mut gateway = FakeGateway::default();
+
+    say(&mut bot, &mut gateway, "Fool", "!signup");
+    assert_eq!(gateway.last_message(), "Only organisers can use !signup");
+    assert!(bot.signup_message.is_none());
+
+    // Messages that aren't commands are ignored
+    say(&mut bot, &mut gateway, "Fool", "gg");
+    assert_eq!(gateway.messages.len(), 1);
+}
+
+#[test]
+fn balance_announce_and_move_to_voice_channels() {
+    let mut bot = Bot::new(&common::roster(), config());
+    let mut gateway = FakeGateway::default();
+
+    say(&mut bot, &mut gateway, "Phil", "!announce");
+    assert_eq!(
+        gateway.last_message(),
+        "There are no scrims yet, generate them with !balance"
+    );
+    say(&mut bot, &mut gateway, "Phil", "!move");
+    assert!(gateway
+        .last_message()
+        .starts_with("No scrim was announced yet"));
+
+    sign_up_everyone(&mut bot, &mut gateway);
+    assert_eq!(bot.signups.len(), 12);
+    say(&mut bot, &mut gateway, "Phil", "!balance");
+    assert!(gateway.last_message().starts_with("Found 10 scrims:\nA "));
+    assert_eq!(bot.scrims.len(), 10);
+
+    say(&mut bot, &mut gateway, "Phil", "!announce Z");
+    assert_eq!(gateway.last_message(), "There is no scrim Z");
+    say(&mut bot, &mut gateway, "Phil", "!announce B");
+    assert!(gateway.last_message().starts_with("```\nSCRIM B"));
+    let announced = bot.announced.clone().unwrap();
+    assert_eq!(announced.label, "B");
+
+    say(&mut bot, &mut gateway, "Phil", "!move");
+    assert_eq!(
+        gateway.last_message(),
+        "Moved the teams of scrim B to their channels"
+    );
+    assert_eq!(gateway.voice_channels.len(), 12);
+    for (team, channel) in announced.teams.iter().zip(&["Team 1", "Team 2"]) {
+        for player in &team.players {
+            assert_eq!(gateway.voice_channels[&player.name], *channel);
+        }
+    }
+
+    say(&mut bot, &mut gateway, "Phil", "!lobby");
+    assert_eq!(gateway.last_message(), "Moved everyone to the lobby");
+    assert!(gateway
+        .voice_channels
+        .values()
+        .all(|channel| channel == "Lobby"));
+}
+
+#[test]
+fn failed_moves_are_reported() {
+    let mut bot = Bot::new(
+        &common::roster(),
+        BotConfig {
+            team_channels: vec!["Team 1".to_string()],
+            ..config()
+        },
+    );
+    let mut gateway = FakeGateway::default();
+    sign_up_everyone(&mut bot, &mut gateway);
+    say(&mut bot, &mut gateway, "Phil", "!balance --time-limit 30");
+    say(&mut bot, &mut gateway, "Phil", "!announce");
+
+    say(&mut bot, &mut gateway, "Phil", "!move");
+    assert_eq!(
+        gateway.last_message(),
+        "There are 2 teams but only 1 team voice channels"
+    );
+
+    bot.config.team_channels.push("Team 2".to_string());
+    gateway.offline.push("Fool".to_string());
+    say(&mut bot, &mut gateway, "Phil", "!move");
+    assert_eq!(gateway.last_message(), "Fool is not connected to voice");
+}
+
+#[test]
+fn chat_only_gives_search_options() {
+    let mut bot = Bot::new(&common::roster(), config());
+    let mut gateway = FakeGateway::default();
+    sign_up_everyone(&mut bot, &mut gateway);
+
+    for text in &[
+        "!balance --team-names /etc/passwd",
+        "!balance --time-limit 1 --template scrims.hbs",
+    ] {
+        say(&mut bot, &mut gateway, "Phil", text);
+        assert!(
+            gateway.last_message().ends_with("is not a search option"),
+            "{}",
+            gateway.last_message()
+        );
+    }
+    assert!(bot.scrims.is_empty());
+}
+
+#[test]
+fn json_gateway_relays_events_and_actions() {
+    let input = [
+        json!({ "event": "message", "channel": CHANNEL, "user": "Phil", "text": "!signup" }),
+        // Comes in while the signup message is waited for, it's handled afterwards
+        json!({ "event": "reaction_added", "message_id": "42", "user": "Fool", "emoji": TANK_EMOJI }),
+        json!({ "reply_to": 1, "message_id": "42" }),
+        json!({ "reply_to": 2 }),
+        // An answer to no action that is waited for is dropped
+        json!({ "reply_to": 7 }),
+        json!({ "reply_to": 3, "error": "Missing permissions" }),
+        json!({ "event": "message", "channel": CHANNEL, "user": "Phil", "text": "!signups" }),
+        json!({ "reply_to": 4, "message_id": "43" }),
+        json!({ "event": "unknown" }),
+    ]
+    .iter()
+    .map(|line| line.to_string())
+    .collect::<Vec<String>>()
+    .join("\n");
+    let mut output = Vec::new();
+    bot::run(&common::roster(), config(), input.as_bytes(), &mut output).unwrap();
+
+    let lines: Vec<Value> = String::from_utf8(output)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(lines.len(), 6);
+    assert_eq!(lines[0]["action"], "send_message");
+    assert_eq!(lines[0]["id"], 1);
+    assert_eq!(lines[1]["action"], "add_reaction");
+    assert_eq!(lines[1]["id"], 2);
+    assert_eq!(lines[1]["message_id"], "42");
+    // A failed reaction stops the other ones, the signups are open anyway
+    assert_eq!(lines[2]["action"], "add_reaction");
+    assert_eq!(lines[3]["error"], "Missing permissions");
+    assert_eq!(lines[4]["text"], "1 players signed up:\nFool (Tank)");
+    assert!(lines[5]["error"]
+        .as_str()
+        .unwrap()
+        .starts_with("Invalid event"));
+}
+
+#[test]
+fn bot_config_is_read_from_json() {
+    let config: BotConfig =
+        serde_json::from_value(json!({ "team_channels": ["Team 1", "Team 2"] })).unwrap();
+
+    assert_eq!(config.team_channels.len(), 2);
+    assert_eq!(config.lobby_channel, None);
+    assert!(config.organisers.is_empty());
+}
@@ -0,0 +1,33 @@
+mod common;
+
+use autophil::card::render_card;
+
+#[test]
+fn card_is_a_png_sized_to_the_lineup() {
+    let png = render_card(&common::scrim()).unwrap();
+
+    let decoder = png::Decoder::new(png.as_slice());
+    let mut reader = decoder.read_info().unwrap();
+    let mut pixels = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut pixels).unwrap();
+    // A column per team and a row per player of a team
+    assert_eq!(
+        (info.width, info.height),
+        (2 * 20 + 2 * 280, 2 * 20 + 64 + 52 + 6 * 28 + 36)
+    );
+    assert_eq!(info.color_type, png::ColorType::Rgb);
+    // The corner is background, the team panels are drawn on top of it
+    assert_eq!(&pixels[..3], &[29, 31, 36]);
+    assert!(pixels.chunks(3).any(|pixel| pixel == [42, 45, 52]));
+}
+
+#[test]
+fn a_card_is_as_wide_as_its_teams() {
+    let mut scrim = common::scrim();
+    let wide = render_card(&scrim).unwrap();
+    scrim.teams.truncate(1);
+    let narrow = render_card(&scrim).unwrap();
+
+    let width = |png: &[u8]| png::Decoder::new(png).read_info().unwrap().info().width;
+    assert_eq!(width(&wide) - width(&narrow), 280);
+}
@@ -0,0 +1,105 @@
+use autophil::lineup::{rate_lineup, Lineup, LineupTeam};
+use autophil::{BalanceConfig, Penalties, Player, Roster};
+
+// Twelve players for two 6v6 teams, ten of them make two OW2 teams
+pub const ROSTER: &str = "# Tank
+Edgers - 2900 - 2200 - 2100 - t
+TrixCold - 3300 - 3300 - 3000 - td
+KingBoo - 2600 - 2600 - 2600 - tds
+Fool - 1400 - 1400 - 1400 - tds
+# DPS
+Starboy - 0 - 1800 - 0 - d
+NootNoot - 3000 - 3000 - 0 - d
+michealw - 3175 - 3368 - 3500 - ds
+Eyoldaith - 3300 - 3000 - 3200 - tds
+# Support
+Juun - 2100 - 1100 - 2500 - s
+Siruker - 0 - 0 - 1500 - s
+Hardsider - 2550 - 2450 - 2550 - tds
+freaker - 2500 - 2300 - 2300 - tds
+";
+
+#[allow(dead_code)]
+pub fn roster() -> Roster {
+    Roster::parse(ROSTER).unwrap()
+}
+
+// The first players of the roster, comments don't count
+#[allow(dead_code)]
+pub fn roster_of(number_of_players: usize) -> Roster {
+    let lines: Vec<&str> = ROSTER
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .take(number_of_players)
+        .collect();
+    Roster::parse(&lines.join("\n")).unwrap()
+}
+
+// The roster with optional fields behind some players, e.g. ("Edgers", "heroes=Winston")
+#[allow(dead_code)]
+pub fn roster_with(fields: &[(&str, &str)]) -> Roster {
+    let lines: Vec<String> = ROSTER
+        .lines()
+        .map(|line| {
+            let mut line = line.to_string();
+            for (name, field) in fields {
+                if line.starts_with(&format!("{} - ", name)) {
+                    line.push_str(&format!(" - {}", field));
+                }
+            }
+            line
+        })
+        .collect();
+    Roster::parse(&lines.join("\n")).unwrap()
+}
+
+// A short search, five scrims on four threads
+#[allow(dead_code)]
+pub fn config() -> BalanceConfig {
+    BalanceConfig {
+        number_of_scrims: 5,
+        number_of_threads: 4,
+        ..BalanceConfig::default()
+    }
+}
+
+// A player from one line of a roster
+#[allow(dead_code)]
+pub fn player(line: &str) -> Player {
+    Player::new(line.to_string()).unwrap()
+}
+
+// A fixed lineup of the roster
+#[allow(dead_code)]
+pub fn lineup() -> Lineup {
+    let team = |name: &str, tank: [&str; 2], damage: [&str; 2], support: [&str; 2]| LineupTeam {
+        name: name.to_string(),
+        tank: tank.iter().map(|name| name.to_string()).collect(),
+        damage: damage.iter().map(|name| name.to_string()).collect(),
+        support: support.iter().map(|name| name.to_string()).collect(),
+    };
+    Lineup {
+        label: "A".to_string(),
+        teams: vec![
+            team(
+                "Fighting Foxes",
+                ["Edgers", "TrixCold"],
+                ["Starboy", "michealw"],
+                ["Juun", "Hardsider"],
+            ),
+            team(
+                "Dancing Dragons",
+                ["KingBoo", "Eyoldaith"],
+                ["NootNoot", "Fool"],
+                ["Siruker", "freaker"],
+            ),
+        ],
+        bench: Vec::new(),
+    }
+}
+
+// The fixed lineup, rated without running a search
+#[allow(dead_code)]
+pub fn scrim() -> autophil::ScrimContext {
+    rate_lineup(&lineup(), &roster(), Penalties::default()).unwrap()
+}
@@ -0,0 +1,138 @@
+mod common;
+
+use autophil::diff::{diff_scrims, PlayerChange};
+use autophil::lineup::{rate_lineup, Lineup};
+use autophil::{Penalties, ScrimContext};
+use serde_json::json;
+
+// The roster of common::scrim() in other teams
+fn rated(teams: serde_json::Value) -> ScrimContext {
+    let lineup: Lineup = serde_json::from_value(json!({ "label": "B", "teams": teams })).unwrap();
+    rate_lineup(&lineup, &common::roster(), Penalties::default()).unwrap()
+}
+
+fn change(name: &str, from: (&str, &str), to: (&str, &str)) -> PlayerChange {
+    PlayerChange {
+        name: name.to_string(),
+        from_team: Some(from.0.to_string()),
+        to_team: Some(to.0.to_string()),
+        from_role: Some(from.1.to_string()),
+        to_role: Some(to.1.to_string()),
+    }
+}
+
+#[test]
+fn swapped_players_are_moved() {
+    let from = common::scrim();
+    let to = rated(json!([
+        {
+            "name": "Fighting Foxes",
+            "tank": ["Edgers", "TrixCold"],
+            "damage": ["NootNoot", "michealw"],
+            "support": ["Juun", "Hardsider"],
+        },
+        {
+            "name": "Dancing Dragons",
+            "tank": ["KingBoo", "freaker"],
+            "damage": ["Starboy", "Fool"],
+            "support": ["Siruker", "Eyoldaith"],
+        },
+    ]));
+
+    let diff = diff_scrims(&from, &to);
+    assert_eq!(
+        diff.moved_players,
+        [
+            change(
+                "Starboy",
+                ("Fighting Foxes", "Damage"),
+                ("Dancing Dragons", "Damage")
+            ),
+            change(
+                "NootNoot",
+                ("Dancing Dragons", "Damage"),
+                ("Fighting Foxes", "Damage")
+            ),
+        ]
+    );
+    // Staying in the team on another role isn't a move
+    assert_eq!(
+        diff.role_changes,
+        [
+            change(
+                "Eyoldaith",
+                ("Dancing Dragons", "Tank"),
+                ("Dancing Dragons", "Support")
+            ),
+            change(
+                "freaker",
+                ("Dancing Dragons", "Support"),
+                ("Dancing Dragons", "Tank")
+            ),
+        ]
+    );
+    assert_eq!(diff.rating_delta, to.rating - from.rating);
+}
+
+#[test]
+fn renamed_teams_are_paired_by_their_players() {
+    let from = common::scrim();
+    let to = rated(json!([
+        {
+            "name": "Red",
+            "tank": ["KingBoo", "Eyoldaith"],
+            "damage": ["NootNoot", "Fool"],
+            "support": ["Siruker", "Hardsider"],
+        },
+        {
+            "name": "Blue",
+            "tank": ["Edgers", "TrixCold"],
+            "damage": ["Starboy", "michealw"],
+            "support": ["Juun", "freaker"],
+        },
+    ]));
+
+    let diff = diff_scrims(&from, &to);
+    let pairs: Vec<(&str, &str)> = diff
+        .teams
+        .iter()
+        .map(|team| (team.from_team.as_str(), team.to_team.as_str()))
+        .collect();
+    assert_eq!(
+        pairs,
+        [("Fighting Foxes", "Blue"), ("Dancing Dragons", "Red")]
+    );
+    // Only the swapped supports moved, everyone else kept their team under its new name
+    assert_eq!(
+        diff.moved_players,
+        [
+            change(
+                "Hardsider",
+                ("Fighting Foxes", "Support"),
+                ("Red", "Support")
+            ),
+            change(
+                "freaker",
+                ("Dancing Dragons", "Support"),
+                ("Blue", "Support")
+            ),
+        ]
+    );
+    assert!(diff.role_changes.is_empty());
+    let foxes = &diff.teams[0];
+    assert_eq!(
+        foxes.average_sr_delta,
+        to.teams[1].average_sr - from.teams[0].average_sr
+    );
+}
+
+#[test]
+fn the_same_lineup_has_no_changes() {
+    let scrim = common::scrim();
+    let diff = diff_scrims(&scrim, &scrim);
+
+    assert!(diff.moved_players.is_empty());
+    assert!(diff.role_changes.is_empty());
+    assert_eq!(diff.rating_delta, 0);
+    assert!(diff.teams.iter().all(|team| team.average_sr_delta == 0.0));
+}
@@ -0,0 +1,111 @@
+mod common;
+
+use autophil::draft::{choose_captains, find_captains, Draft, DraftOrder};
+use autophil::Player;
+use std::collections::HashMap;
+
+fn name(players: &HashMap<u8, Player>, id: u8) -> &str {
+    &players[&id].name
+}
+
+// The team on the clock for every pick, always taking the first eligible player
+fn pick_order(draft: &mut Draft) -> Vec<usize> {
+    let mut order = Vec::new();
+    while let Some(team) = draft.team_on_the_clock() {
+        order.push(team);
+        let id = draft.eligible_picks(team)[0];
+        draft.pick(id).unwrap();
+    }
+    order
+}
+
+#[test]
+fn captains_are_the_best_on_their_main_role() {
+    let roster = common::roster();
+    let captains = choose_captains(&roster.players, 2);
+
+    let names: Vec<&str> = captains
+        .iter()
+        .map(|id| name(&roster.players, *id))
+        .collect();
+    // TrixCold and Eyoldaith tie on 3300, the first one in the roster goes first
+    assert_eq!(names, ["michealw", "TrixCold"]);
+}
+
+#[test]
+fn captains_are_given_by_name_once() {
+    let roster = common::roster();
+    let names =
+        |names: &[&str]| -> Vec<String> { names.iter().map(|name| name.to_string()).collect() };
+
+    let captains = find_captains(&roster.players, &names(&["edgers", "Fool"])).unwrap();
+    assert_eq!(name(&roster.players, captains[0]), "Edgers");
+    assert_eq!(name(&roster.players, captains[1]), "Fool");
+    assert_eq!(
+        find_captains(&roster.players, &names(&["Edgers", "edgers"]))
+            .unwrap_err()
+            .to_string(),
+        "Edgers is given as captain twice"
+    );
+    assert!(find_captains(&roster.players, &names(&["Edgers", "Nobody"])).is_err());
+}
+
+#[test]
+fn snake_order_turns_around_every_round() {
+    let roster = common::roster();
+    let captains = choose_captains(&roster.players, 2);
+    let mut draft = Draft::new(&roster.players, captains, DraftOrder::Snake, false);
+
+    assert_eq!(pick_order(&mut draft), [0, 1, 1, 0, 0, 1, 1, 0, 0, 1]);
+    assert!(draft.pool.is_empty());
+    assert!(draft.teams.iter().all(|team| team.len() == 6));
+    assert_eq!(draft.team_names, ["Team michealw", "Team TrixCold"]);
+}
+
+#[test]
+fn linear_order_repeats_every_round() {
+    let roster = common::roster();
+    let captains = choose_captains(&roster.players, 2);
+    let mut draft = Draft::new(&roster.players, captains, DraftOrder::Linear, false);
+
+    assert_eq!(pick_order(&mut draft), [0, 1, 0, 1, 0, 1, 0, 1, 0, 1]);
+}
+
+#[test]
+fn only_players_in_the_pool_can_be_picked() {
+    let roster = common::roster();
+    let captains = choose_captains(&roster.players, 2);
+    let captain = captains[1];
+    let mut draft = Draft::new(&roster.players, captains, DraftOrder::Snake, false);
+
+    assert!(draft.pick(captain).is_err());
+    let id = draft.pool[0];
+    draft.pick(id).unwrap();
+    assert!(draft.pick(id).is_err());
+}
+
+#[test]
+fn the_recommendation_has_the_best_projected_rating() {
+    let roster = common::roster();
+    let captains = choose_captains(&roster.players, 2);
+    let mut draft = Draft::new(&roster.players, captains, DraftOrder::Snake, false);
+
+    let team = draft.team_on_the_clock().unwrap();
+    let (recommended, rating) = draft.recommend().unwrap();
+    assert!(draft.eligible_picks(team).contains(&recommended));
+    assert_eq!(draft.projected_rating(recommended), Some(rating));
+    for id in draft.eligible_picks(team) {
+        assert!(draft.projected_rating(id).unwrap() >= rating);
+    }
+
+    // Taking every recommendation ends with the projected rating
+    let mut last_projection = rating;
+    while let Some((id, rating)) = draft.recommend() {
+        draft.pick(id).unwrap();
+        last_projection = rating;
+    }
+    let scrim = draft.get_context("A").unwrap();
+    assert_eq!(scrim.rating, last_projection);
+    assert!(scrim.bench.is_empty());
+    assert!(scrim.teams.iter().all(|team| team.players.len() == 6));
+}
@@ -0,0 +1,76 @@
+mod common;
+
+use autophil::heroes::{hero_position, team_conflicts, unknown_heroes};
+use autophil::lineup::rate_lineup;
+use autophil::{Penalties, Position};
+
+#[test]
+fn heroes_are_looked_up_in_the_catalogue() {
+    assert_eq!(hero_position("Winston"), Some(Position::Tank));
+    assert_eq!(hero_position("soldier: 76"), Some(Position::Damage));
+    assert_eq!(hero_position("Bob"), None);
+
+    let ana = common::player("Ana - 0 - 0 - 2500 - s - heroes=Ana,Bob,Kiriko");
+    assert_eq!(unknown_heroes(&ana), ["Bob"]);
+}
+
+#[test]
+fn players_sharing_one_hero_conflict() {
+    let first = common::player("First - 2500 - 0 - 0 - t - heroes=Winston");
+    let second = common::player("Second - 2500 - 0 - 0 - t - heroes=Winston");
+
+    let conflicts = team_conflicts(
+        "Red",
+        &[(Position::Tank, &first), (Position::Tank, &second)],
+    );
+    assert_eq!(
+        conflicts,
+        ["Red: First and Second can't play distinct Tank heroes (Winston)"]
+    );
+}
+
+#[test]
+fn pools_with_a_hero_for_everyone_dont_conflict() {
+    let first = common::player("First - 0 - 0 - 2500 - s - heroes=Ana");
+    let second = common::player("Second - 0 - 0 - 2500 - s - heroes=Ana,Kiriko");
+    let anyone = common::player("Anyone - 0 - 0 - 2500 - s");
+    let slots = [(Position::Support, &first), (Position::Support, &second)];
+
+    assert!(team_conflicts("Red", &slots).is_empty());
+    // Players without a pool play whatever is left
+    let slots = [(Position::Support, &first), (Position::Support, &anyone)];
+    assert!(team_conflicts("Red", &slots).is_empty());
+    // Only players of the same role compete for heroes
+    let tank = common::player("Tank - 2500 - 0 - 2500 - ts - heroes=Winston,Ana");
+    let slots = [(Position::Tank, &tank), (Position::Support, &first)];
+    assert!(team_conflicts("Red", &slots).is_empty());
+}
+
+#[test]
+fn a_player_without_a_hero_for_the_role_conflicts() {
+    let flex = common::player("Flex - 2500 - 0 - 2500 - ts - heroes=Winston");
+
+    assert_eq!(
+        team_conflicts("Red", &[(Position::Support, &flex)]),
+        ["Red: Flex has no Support hero"]
+    );
+}
+
+#[test]
+fn every_conflict_adds_the_penalty_to_the_rating() {
+    let roster =
+        common::roster_with(&[("Edgers", "heroes=Winston"), ("TrixCold", "heroes=Winston")]);
+    let penalties = Penalties {
+        hero_conflict: 300,
+        ..Penalties::default()
+    };
+
+    let without_penalty = rate_lineup(&common::lineup(), &roster, Penalties::default()).unwrap();
+    let with_penalty = rate_lineup(&common::lineup(), &roster, penalties).unwrap();
+    assert_eq!(
+        with_penalty.hero_conflicts,
+        ["Fighting Foxes: Edgers and TrixCold can't play distinct Tank heroes (Winston)"]
+    );
+    assert_eq!(with_penalty.rating, without_penalty.rating + 300);
+    assert_eq!(without_penalty.rating, common::scrim().rating);
+}
@@ -0,0 +1,31 @@
+mod common;
+
+use autophil::html::render_report;
+
+#[test]
+fn report_shows_a_bar_per_role_scaled_to_the_best_role() {
+    let scrim = common::scrim();
+    let html = render_report(&[scrim]).unwrap();
+
+    assert!(html.starts_with("<!DOCTYPE html>"));
+    assert!(html.contains("Scrim A"));
+    // Two teams with three roles each
+    assert_eq!(html.matches("<div class=\"bar\">").count(), 6);
+    for role in ["Tank", "Damage", "Support"] {
+        assert_eq!(html.matches(&format!("<div class=\"{}\"", role)).count(), 2);
+    }
+    assert!(html.contains("style=\"width: 100"));
+    for name in ["Edgers", "freaker", "Fighting Foxes", "Dancing Dragons"] {
+        assert!(html.contains(name), "{} is missing", name);
+    }
+}
+
+#[test]
+fn names_are_escaped() {
+    let mut scrim = common::scrim();
+    scrim.rename_team(0, "<script>alert(1)</script> & Co");
+    let html = render_report(&[scrim]).unwrap();
+
+    assert!(!html.contains("<script>alert(1)"));
+    assert!(html.contains("&lt;script&gt;alert(1)&lt;/script&gt; &amp; Co"));
+}
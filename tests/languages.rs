@@ -0,0 +1,73 @@
+mod common;
+
+use autophil::languages::{common_languages, get_comms_string, team_conflict};
+use autophil::lineup::rate_lineup;
+use autophil::{balance, BalanceConfig, Penalties, SearchProgress};
+
+#[test]
+fn teams_speak_the_languages_all_players_share() {
+    let german = common::player("German - 2500 - 0 - 0 - t - languages=de,en");
+    let english = common::player("English - 2500 - 0 - 0 - t - languages=en,fr");
+    let anyone = common::player("Anyone - 2500 - 0 - 0 - t");
+
+    assert_eq!(
+        common_languages(&[&german, &english, &anyone]),
+        Some(vec!["en".to_string()])
+    );
+    assert_eq!(get_comms_string(&[&german, &anyone]), "de, en");
+    // Nobody lists a language, so there is nothing to check
+    assert_eq!(common_languages(&[&anyone]), None);
+    assert_eq!(get_comms_string(&[&anyone]), "-");
+    assert_eq!(team_conflict("Red", &[&german, &english]), None);
+}
+
+#[test]
+fn a_team_without_a_shared_language_conflicts() {
+    let german = common::player("German - 2500 - 0 - 0 - t - languages=de");
+    let french = common::player("French - 2500 - 0 - 0 - t - languages=fr");
+
+    assert_eq!(common_languages(&[&german, &french]), Some(Vec::new()));
+    assert_eq!(get_comms_string(&[&german, &french]), "none");
+    assert_eq!(
+        team_conflict("Red", &[&german, &french]).as_deref(),
+        Some("Red: no common comms language")
+    );
+}
+
+#[test]
+fn every_team_without_a_language_adds_the_penalty() {
+    let roster = common::roster_with(&[("Edgers", "languages=fr"), ("Starboy", "languages=de")]);
+    let penalties = Penalties {
+        language_conflict: 500,
+        ..Penalties::default()
+    };
+
+    let scrim = rate_lineup(&common::lineup(), &roster, penalties).unwrap();
+    assert_eq!(
+        scrim.language_conflicts,
+        ["Fighting Foxes: no common comms language"]
+    );
+    assert_eq!(scrim.rating, common::scrim().rating + 500);
+    assert!(scrim.has_languages);
+    assert_eq!(scrim.teams[0].comms, "none");
+    assert_eq!(scrim.teams[1].comms, "-");
+}
+
+#[test]
+fn required_languages_keep_players_without_one_apart() {
+    let roster = common::roster_with(&[("Edgers", "languages=fr"), ("Starboy", "languages=de")]);
+    let config = BalanceConfig {
+        penalties: Penalties {
+            require_common_language: true,
+            ..Penalties::default()
+        },
+        ..common::config()
+    };
+
+    let result = balance(&roster, &config, &SearchProgress::silent()).unwrap();
+    assert_eq!(result.scrims.len(), 5);
+    for scrim in &result.scrims {
+        assert!(scrim.language_conflicts.is_empty());
+        assert!(scrim.teams.iter().all(|team| team.comms != "none"));
+    }
+}
@@ -0,0 +1,85 @@
+mod common;
+
+use autophil::explain::explain_scrim;
+use autophil::lineup::rate_lineup;
+use autophil::template::ScrimRenderer;
+use autophil::{Penalties, Roster};
+
+fn error_of(lineup: &autophil::lineup::Lineup) -> String {
+    rate_lineup(lineup, &common::roster(), Penalties::default())
+        .unwrap_err()
+        .to_string()
+}
+
+#[test]
+fn lineup_of_the_whole_roster_is_rated() {
+    let scrim = common::scrim();
+
+    assert_eq!(scrim.teams.len(), 2);
+    assert!(scrim.bench.is_empty());
+}
+
+#[test]
+fn unknown_player_is_an_error() {
+    let mut lineup = common::lineup();
+    lineup.teams[0].tank[0] = "Nobody".to_string();
+
+    assert_eq!(error_of(&lineup), "Player Nobody is not in the roster");
+}
+
+#[test]
+fn player_twice_is_an_error() {
+    let mut lineup = common::lineup();
+    lineup.teams[1].tank[0] = "Edgers".to_string();
+
+    assert_eq!(error_of(&lineup), "Player Edgers is in the lineup twice");
+}
+
+#[test]
+fn player_on_the_bench_twice_is_an_error() {
+    let mut lineup = common::lineup();
+    lineup.bench = vec!["Edgers".to_string()];
+
+    assert_eq!(error_of(&lineup), "Player Edgers is in the lineup twice");
+}
+
+#[test]
+fn missing_player_is_an_error() {
+    let roster = Roster::parse(&format!(
+        "{}\nLatecomer - 2500 - 2500 - 2500 - tds",
+        common::ROSTER
+    ))
+    .unwrap();
+    let error = rate_lineup(&common::lineup(), &roster, Penalties::default()).unwrap_err();
+
+    assert_eq!(
+        error.to_string(),
+        "Player Latecomer is missing from the lineup"
+    );
+
+    let mut lineup = common::lineup();
+    lineup.bench = vec!["Latecomer".to_string()];
+    assert!(rate_lineup(&lineup, &roster, Penalties::default()).is_ok());
+}
+
+#[test]
+fn players_off_their_role_are_warned_about_on_their_own() {
+    // Starboy only plays damage, Edgers only tank
+    let mut lineup = common::lineup();
+    lineup.teams[0].tank[0] = "Starboy".to_string();
+    lineup.teams[0].damage[0] = "Edgers".to_string();
+    let scrim = rate_lineup(&lineup, &common::roster(), Penalties::default()).unwrap();
+
+    assert_eq!(
+        scrim.off_role_warnings,
+        [
+            "Fighting Foxes: Starboy doesn't play Tank",
+            "Fighting Foxes: Edgers doesn't play DPS"
+        ]
+    );
+    assert!(scrim.sub_role_clashes.is_empty());
+    let text = ScrimRenderer::new(None).unwrap().render(&scrim).unwrap();
+    assert!(text.contains("Players off their roles:\n  Fighting Foxes: Starboy doesn't play Tank"));
+    assert!(!text.contains("Sub-role clashes"));
+    assert_eq!(explain_scrim(&scrim).off_role_warnings.len(), 2);
+}
@@ -0,0 +1,103 @@
+use autophil::context::RoundContext;
+use autophil::maps::MapPool;
+use std::collections::HashSet;
+
+const POOL: &str = "# Two modes
+Control: Busan, Ilios, Nepal
+Push: Colosseo, Esperança
+";
+
+fn rounds(number_of_rounds: usize) -> Vec<RoundContext> {
+    (1..=number_of_rounds)
+        .map(|round| RoundContext {
+            round,
+            matches: Vec::new(),
+            bye: None,
+            maps: Vec::new(),
+        })
+        .collect()
+}
+
+fn maps(round: &RoundContext) -> Vec<(&str, &str)> {
+    round
+        .maps
+        .iter()
+        .map(|map| (map.mode.as_str(), map.name.as_str()))
+        .collect()
+}
+
+#[test]
+fn every_round_plays_each_mode_once() {
+    let pool = MapPool::parse(POOL).unwrap();
+    let mut rounds = rounds(3);
+    pool.assign(&mut rounds, None).unwrap();
+
+    assert_eq!(
+        maps(&rounds[0]),
+        [("Control", "Busan"), ("Push", "Colosseo")]
+    );
+    assert_eq!(
+        maps(&rounds[1]),
+        [("Control", "Ilios"), ("Push", "Esperança")]
+    );
+    // Push has run out of maps and starts over, Control hasn't
+    assert_eq!(
+        maps(&rounds[2]),
+        [("Control", "Nepal"), ("Push", "Colosseo")]
+    );
+}
+
+#[test]
+fn maps_repeat_only_after_all_maps_of_their_mode() {
+    let pool = MapPool::default_pool();
+    let mut rounds = rounds(4);
+    pool.assign(&mut rounds, None).unwrap();
+
+    for (mode, mode_maps) in &pool.modes {
+        let played: Vec<&str> = rounds
+            .iter()
+            .flat_map(|round| &round.maps)
+            .filter(|map| &map.mode == mode)
+            .map(|map| map.name.as_str())
+            .collect();
+        assert_eq!(played.len(), 4);
+        let distinct: HashSet<&&str> = played.iter().take(mode_maps.len()).collect();
+        assert_eq!(distinct.len(), played.len().min(mode_maps.len()));
+    }
+}
+
+#[test]
+fn more_maps_than_modes_keep_rotating() {
+    let pool = MapPool::parse(POOL).unwrap();
+    let mut rounds = rounds(2);
+    pool.assign(&mut rounds, Some(3)).unwrap();
+
+    let modes: Vec<&str> = rounds
+        .iter()
+        .flat_map(|round| &round.maps)
+        .map(|map| map.mode.as_str())
+        .collect();
+    assert_eq!(
+        modes,
+        ["Control", "Push", "Control", "Push", "Control", "Push"]
+    );
+}
+
+#[test]
+fn fewer_maps_than_modes_are_an_error() {
+    let pool = MapPool::parse(POOL).unwrap();
+    let mut rounds = rounds(2);
+
+    let error = pool.assign(&mut rounds, Some(1)).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "The map pool has 2 modes, a round needs at least 2 maps to play each of them"
+    );
+    assert!(rounds.iter().all(|round| round.maps.is_empty()));
+}
+
+#[test]
+fn pools_without_maps_are_an_error() {
+    assert!(MapPool::parse("# Nothing yet\n").is_err());
+    assert!(MapPool::parse("Control Busan").is_err());
+}
@@ -0,0 +1,128 @@
+mod common;
+
+use autophil::matchup::Matchup;
+use autophil::reader::{self, ScrimLineup};
+use autophil::template::ScrimRenderer;
+use autophil::Roster;
+
+fn id(roster: &Roster, name: &str) -> u8 {
+    *roster
+        .players
+        .iter()
+        .find(|(_, player)| player.name == name)
+        .unwrap()
+        .0
+}
+
+// The lineup of common::scrim() with other team names
+fn matchup(roster: &Roster, home: &str, away: &str) -> Matchup {
+    let team = |name: &str, players: [&str; 6]| {
+        (
+            name.to_string(),
+            id(roster, players[0]),
+            id(roster, players[1]),
+            id(roster, players[2]),
+            id(roster, players[3]),
+            id(roster, players[4]),
+            id(roster, players[5]),
+        )
+    };
+    Matchup::new(
+        vec![
+            team(
+                home,
+                [
+                    "Edgers",
+                    "TrixCold",
+                    "Starboy",
+                    "michealw",
+                    "Juun",
+                    "Hardsider",
+                ],
+            ),
+            team(
+                away,
+                [
+                    "KingBoo",
+                    "Eyoldaith",
+                    "NootNoot",
+                    "Fool",
+                    "Siruker",
+                    "freaker",
+                ],
+            ),
+        ],
+        &roster.players,
+    )
+}
+
+fn read_back(roster: &Roster, matchup: &Matchup) -> Matchup {
+    let text = format!("SCRIM A:\n{}", matchup.get_pretty_string(&roster.players));
+    let scrims = reader::read_scrims(&text, &roster.players).unwrap();
+    assert_eq!(scrims.len(), 1);
+    assert_eq!(scrims[0].label, "A");
+    match &scrims[0].lineup {
+        ScrimLineup::Matchup(read) => read.clone(),
+        ScrimLineup::OW2Matchup(_) => panic!("read a 6v6 scrim as OW2"),
+    }
+}
+
+#[test]
+fn the_pretty_string_is_read_back_unchanged() {
+    let roster = common::roster();
+    let matchup = matchup(&roster, "Fighting Foxes", "Dancing Dragons");
+
+    assert_eq!(read_back(&roster, &matchup), matchup);
+}
+
+#[test]
+fn team_names_longer_than_their_column_are_read_back() {
+    let roster = common::roster();
+    let long_name = "The Extraordinarily Long Team Name";
+    assert!(long_name.len() > 25);
+
+    for (home, away) in [
+        (long_name, "Dancing Dragons"),
+        ("Fighting Foxes", long_name),
+    ] {
+        let matchup = matchup(&roster, home, away);
+        assert_eq!(read_back(&roster, &matchup), matchup);
+    }
+}
+
+#[test]
+fn rendered_scrims_with_a_long_team_name_are_read_back() {
+    let roster = common::roster();
+    let mut scrim = common::scrim();
+    scrim.rename_team(0, "The Extraordinarily Long Team Name");
+    let text = ScrimRenderer::new(None).unwrap().render(&scrim).unwrap();
+
+    let read = reader::read_scrims(&text, &roster.players).unwrap();
+    assert_eq!(read.len(), 1);
+    let context = read[0].get_context(&roster.players);
+    let names: Vec<&str> = context
+        .teams
+        .iter()
+        .map(|team| team.name.as_str())
+        .collect();
+    assert_eq!(
+        names,
+        ["The Extraordinarily Long Team Name", "Dancing Dragons"]
+    );
+    assert_eq!(context.teams, scrim.teams);
+}
+
+#[test]
+fn a_scrim_without_team_names_is_an_error() {
+    let roster = common::roster();
+    let matchup = matchup(&roster, "Fighting Foxes", "Dancing Dragons");
+    let text = format!("SCRIM A:\n{}", matchup.get_pretty_string(&roster.players));
+    // The team names and their averages are the indented lines
+    let text: String = text
+        .lines()
+        .filter(|line| !line.starts_with("         "))
+        .map(|line| format!("{}\n", line))
+        .collect();
+
+    assert!(reader::read_scrims(&text, &roster.players).is_err());
+}
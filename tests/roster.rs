@@ -0,0 +1,75 @@
+mod common;
+
+use autophil::availability::Availability;
+use autophil::{Position, Roster};
+
+#[test]
+fn players_are_numbered_in_roster_order_without_comments() {
+    let roster = common::roster();
+
+    assert_eq!(roster.len(), 12);
+    assert_eq!(roster.players[&1].name, "Edgers");
+    assert_eq!(roster.players[&5].name, "Starboy");
+    assert_eq!(roster.players[&12].name, "freaker");
+}
+
+#[test]
+fn ratings_and_roles_are_read() {
+    let roster = common::roster();
+    let michealw = &roster.players[&7];
+
+    assert_eq!(michealw.get_sr(Position::Tank), 3175);
+    assert_eq!(michealw.get_sr(Position::Damage), 3368);
+    assert_eq!(michealw.get_sr(Position::Support), 3500);
+    assert!(!michealw.plays_position(Position::Tank));
+    assert!(michealw.plays_position(Position::Damage));
+    assert!(michealw.plays_position(Position::Support));
+}
+
+#[test]
+fn optional_fields_are_read() {
+    let roster = Roster::parse(
+        "Edgers - 2900 - 2200 - 2100 - t - heroes=Winston,Sigma - stack=duo - languages=DE,en - available=1-2",
+    ).unwrap();
+    let edgers = &roster.players[&1];
+
+    assert_eq!(edgers.heroes.len(), 2);
+    assert_eq!(edgers.stack.as_deref(), Some("duo"));
+    assert_eq!(edgers.languages, vec!["de", "en"]);
+    assert_eq!(edgers.availability, vec![Availability::Rounds(1, Some(2))]);
+}
+
+#[test]
+fn lines_that_are_no_players_are_skipped() {
+    let roster = Roster::parse("Edgers - 2900 - 2200 - 2100 - t\nnot a player\n").unwrap();
+
+    assert_eq!(roster.len(), 1);
+}
+
+#[test]
+fn missing_roster_file_is_an_error() {
+    assert!(Roster::from_file("does_not_exist.txt").is_err());
+}
+
+#[test]
+fn problems_with_the_roster_are_warnings() {
+    let (roster, warnings) = Roster::parse_with_warnings(
+        "Edgers - 2900 - 2200 - 2100 - t - heroes=Winston,Nobody\nnot a player\n",
+    )
+    .unwrap();
+
+    assert_eq!(roster.len(), 1);
+    assert_eq!(warnings.len(), 2);
+    assert!(warnings[0].contains("Nobody"));
+}
+
+#[test]
+fn more_players_than_ids_are_an_error() {
+    let line = "Edgers - 2900 - 2200 - 2100 - t";
+    let full = vec![line; 255].join("\n");
+    let roster = Roster::parse(&full).unwrap();
+
+    assert_eq!(roster.len(), 255);
+    assert!(Roster::parse(&vec![line; 256].join("\n")).is_err());
+    assert!(roster.changed(&[], &[line.to_string()]).is_err());
+}
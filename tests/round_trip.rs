@@ -0,0 +1,48 @@
+mod common;
+
+use autophil::template::ScrimRenderer;
+use autophil::{balance, html, reader, BalanceConfig, SearchProgress};
+
+#[test]
+fn written_scrims_are_read_back_unchanged() {
+    let roster = common::roster();
+    let config = BalanceConfig {
+        number_of_scrims: 3,
+        number_of_threads: 4,
+        ..BalanceConfig::default()
+    };
+    let scrims = balance(&roster, &config, &SearchProgress::silent())
+        .unwrap()
+        .scrims;
+
+    let renderer = ScrimRenderer::new(None).unwrap();
+    let mut text = String::new();
+    for scrim in &scrims {
+        text.push_str(&renderer.render(scrim).unwrap());
+    }
+
+    let read_scrims: Vec<_> = reader::read_scrims(&text, &roster.players)
+        .unwrap()
+        .iter()
+        .map(|scrim| scrim.get_context(&roster.players))
+        .collect();
+    assert_eq!(read_scrims, scrims);
+}
+
+#[test]
+fn scrims_in_another_layout_are_an_error() {
+    let roster = common::roster();
+
+    assert!(reader::read_scrims("SCRIM A (1234abcd):\nTank: nobody", &roster.players).is_err());
+}
+
+#[test]
+fn averages_of_no_players_are_written_as_a_dash() {
+    let mut scrim = common::scrim();
+    scrim.sr_average = f32::NAN;
+    scrim.teams[0].average_sr = f32::NAN;
+
+    let text = ScrimRenderer::new(None).unwrap().render(&scrim).unwrap();
+    assert!(text.contains("Average SR: -"));
+    assert!(html::render_report(&[scrim]).is_ok());
+}
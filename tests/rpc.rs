@@ -0,0 +1,240 @@
+mod common;
+
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+
+// The binary in --rpc mode, talked to through its stdin and stdout
+struct RpcClient {
+    child: Child,
+    stdin: Option<ChildStdin>,
+    stdout: BufReader<ChildStdout>,
+}
+
+impl RpcClient {
+    fn start() -> RpcClient {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_autophil"))
+            .arg("--rpc")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let stdin = child.stdin.take();
+        let stdout = BufReader::new(child.stdout.take().unwrap());
+        RpcClient {
+            child,
+            stdin,
+            stdout,
+        }
+    }
+
+    fn send_line(&mut self, line: &str) {
+        let stdin = self.stdin.as_mut().unwrap();
+        writeln!(stdin, "{}", line).unwrap();
+        stdin.flush().unwrap();
+    }
+
+    fn send(&mut self, id: Option<u64>, method: &str, params: Value) {
+        let mut request = json!({ "jsonrpc": "2.0", "method": method, "params": params });
+        if let Some(id) = id {
+            request["id"] = json!(id);
+        }
+        self.send_line(&request.to_string());
+    }
+
+    // Every line is a whole message
+    fn receive(&mut self) -> Value {
+        let mut line = String::new();
+        assert!(self.stdout.read_line(&mut line).unwrap() > 0, "no answer");
+        serde_json::from_str(&line).unwrap()
+    }
+
+    // The answer to the request and the notifications sent before it
+    fn answer(&mut self, id: u64) -> (Value, Vec<Value>) {
+        let mut notifications = Vec::new();
+        loop {
+            let message = self.receive();
+            assert_eq!(message["jsonrpc"], "2.0");
+            if message["id"] == json!(id) {
+                return (message, notifications);
+            }
+            notifications.push(message);
+        }
+    }
+
+    fn call(&mut self, id: u64, method: &str, params: Value) -> (Value, Vec<Value>) {
+        self.send(Some(id), method, params);
+        self.answer(id)
+    }
+
+    fn load_roster(&mut self) {
+        let (answer, _) = self.call(1, "load_roster", json!({ "roster": common::ROSTER }));
+        assert_eq!(answer["result"]["players"], 12);
+    }
+
+    // Closes stdin and waits for the process to end
+    fn finish(mut self) -> Vec<Value> {
+        self.stdin = None;
+        let mut messages = Vec::new();
+        let mut line = String::new();
+        while self.stdout.read_line(&mut line).unwrap() > 0 {
+            messages.push(serde_json::from_str(&line).unwrap());
+            line.clear();
+        }
+        assert!(self.child.wait().unwrap().success());
+        messages
+    }
+}
+
+#[test]
+fn generate_explain_and_rebalance() {
+    let mut client = RpcClient::start();
+    client.load_roster();
+
+    let (answer, notifications) = client.call(2, "generate", json!({ "args": [] }));
+    let scrims = answer["result"]["scrims"].as_array().unwrap();
+    assert_eq!(scrims.len(), 10);
+    assert_eq!(answer["result"]["complete"], true);
+    // Everything the search has to say comes as notifications
+    assert!(notifications.iter().all(|n| n.get("id").is_none()));
+    let progress: Vec<&Value> = notifications
+        .iter()
+        .filter(|n| n["method"] == "progress")
+        .collect();
+    assert!(!progress.is_empty());
+    assert!(progress.iter().any(|p| p["params"]["phase"] == "Scrims"));
+    assert!(notifications
+        .iter()
+        .any(|n| n["method"] == "message" && n["params"]["text"] != ""));
+    let best: Vec<&Value> = notifications
+        .iter()
+        .filter(|n| n["method"] == "best_scrim")
+        .collect();
+    assert_eq!(
+        best.last().unwrap()["params"]["rating"],
+        scrims[0]["rating"]
+    );
+
+    let (answer, _) = client.call(3, "explain", json!({ "scrim": "A" }));
+    assert_eq!(answer["result"]["label"], "A");
+    assert_eq!(answer["result"]["rating"], scrims[0]["rating"]);
+
+    let (answer, _) = client.call(
+        4,
+        "rebalance",
+        json!({
+            "scrim": scrims[0]["id"],
+            "remove": ["Starboy"],
+            "add": ["Newcomer - 0 - 2000 - 0 - d"],
+        }),
+    );
+    let rebalanced = &answer["result"]["scrims"][0];
+    let names: Vec<&str> = rebalanced["teams"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .flat_map(|team| team["players"].as_array().unwrap())
+        .map(|player| player["name"].as_str().unwrap())
+        .collect();
+    assert!(names.contains(&"Newcomer"));
+    assert!(!names.contains(&"Starboy"));
+    client.finish();
+}
+
+#[test]
+fn errors_follow_json_rpc() {
+    let mut client = RpcClient::start();
+
+    client.send_line("{not json");
+    let answer = client.receive();
+    assert_eq!(answer["id"], Value::Null);
+    assert_eq!(answer["error"]["code"], -32700);
+
+    let (answer, _) = client.call(1, "shuffle", json!({}));
+    assert_eq!(answer["error"]["code"], -32601);
+    let (answer, _) = client.call(2, "generate", json!({}));
+    assert_eq!(
+        answer["error"]["message"],
+        "Load a roster first with load_roster"
+    );
+    let (answer, _) = client.call(3, "load_roster", json!({ "roster": 12 }));
+    assert_eq!(answer["error"]["code"], -32602);
+
+    client.load_roster();
+    let (answer, _) = client.call(4, "explain", json!({ "scrim": "A" }));
+    assert_eq!(answer["error"]["code"], -32000);
+    let (answer, _) = client.call(5, "generate", json!({ "args": ["--seats"] }));
+    assert_eq!(answer["error"]["code"], -32602);
+    let (answer, _) = client.call(6, "generate", json!({ "args": ["--html", "scrims.html"] }));
+    assert_eq!(answer["error"]["code"], -32602);
+    assert_eq!(answer["error"]["message"], "--html is not a search option");
+    client.finish();
+}
+
+#[test]
+fn roster_problems_are_warnings() {
+    let mut client = RpcClient::start();
+    let (answer, _) = client.call(
+        1,
+        "load_roster",
+        json!({ "roster": "Ana - 0 - 0 - 2500 - s - heroes=Ana,Bob\nBroken - 10 - s" }),
+    );
+    assert_eq!(answer["result"]["players"], 1);
+    let warnings = answer["result"]["warnings"].as_array().unwrap();
+    assert_eq!(warnings.len(), 2);
+    assert_eq!(warnings[0], "Unknown hero Bob in the pool of Ana");
+    assert!(warnings[1]
+        .as_str()
+        .unwrap()
+        .starts_with("Error with parsing player: Splitting Error"));
+
+    let (answer, _) = client.call(2, "load_roster", json!({ "file": "missing.txt" }));
+    assert!(answer["error"]["message"]
+        .as_str()
+        .unwrap()
+        .starts_with("Could not read missing.txt"));
+    client.finish();
+}
+
+#[test]
+fn cancel_stops_the_search() {
+    let mut client = RpcClient::start();
+    client.load_roster();
+    client.send(Some(2), "generate", json!({}));
+    client.send(Some(3), "generate", json!({}));
+    let (answer, _) = client.answer(3);
+    assert_eq!(
+        answer["error"]["message"],
+        "A search is already running, cancel it first"
+    );
+
+    let (answer, _) = client.call(4, "cancel", json!({}));
+    assert_eq!(answer["result"]["cancelled"], true);
+    // The search answers with the scrims found so far, if there are any
+    let (answer, _) = client.answer(2);
+    assert!(
+        answer["result"]["scrims"].is_array()
+            || answer["error"]["message"] == "The search was stopped before a scrim was found"
+    );
+    let (answer, _) = client.call(5, "cancel", json!({}));
+    assert_eq!(answer["result"]["cancelled"], false);
+    client.finish();
+}
+
+#[test]
+fn the_running_search_answers_after_the_input_ends() {
+    let mut client = RpcClient::start();
+    client.load_roster();
+    // Notifications from the client aren't answered
+    client.send(None, "cancel", json!({}));
+    client.send(Some(2), "generate", json!({}));
+
+    let messages = client.finish();
+    let answers: Vec<&Value> = messages
+        .iter()
+        .filter(|message| message.get("id").is_some())
+        .collect();
+    assert_eq!(answers.len(), 1);
+    assert_eq!(answers[0]["id"], 2);
+    assert_eq!(answers[0]["result"]["complete"], true);
+}
@@ -0,0 +1,101 @@
+use autophil::context::{RoundContext, TeamContext};
+use autophil::schedule::round_robin;
+use std::collections::HashSet;
+
+fn teams(number_of_teams: usize) -> Vec<TeamContext> {
+    (0..number_of_teams)
+        .map(|i| TeamContext {
+            name: format!("Team {}", i + 1),
+            average_sr: 2000.0 + 100.0 * i as f32,
+            players: Vec::new(),
+            roles: Vec::new(),
+            comms: String::new(),
+        })
+        .collect()
+}
+
+// Every pair of teams as it is played, in the order of the names
+fn pairs(rounds: &[RoundContext]) -> Vec<(String, String)> {
+    let mut pairs = Vec::new();
+    for round in rounds {
+        for game in &round.matches {
+            let mut pair = [game.home.clone(), game.away.clone()];
+            pair.sort();
+            let [first, second] = pair;
+            pairs.push((first, second));
+        }
+    }
+    pairs
+}
+
+fn assert_every_pair_meets_once(number_of_teams: usize, rounds: &[RoundContext]) {
+    let played = pairs(rounds);
+    let unique: HashSet<&(String, String)> = played.iter().collect();
+    assert_eq!(unique.len(), played.len(), "a pair met twice");
+    assert_eq!(played.len(), number_of_teams * (number_of_teams - 1) / 2);
+
+    // Nobody plays twice in a round
+    for round in rounds {
+        let mut playing: Vec<&String> = round
+            .matches
+            .iter()
+            .flat_map(|game| vec![&game.home, &game.away])
+            .chain(round.bye.as_ref())
+            .collect();
+        let seats = playing.len();
+        playing.sort();
+        playing.dedup();
+        assert_eq!(playing.len(), seats);
+        assert_eq!(seats, number_of_teams);
+    }
+}
+
+#[test]
+fn even_numbers_of_teams_play_everyone_once() {
+    for number_of_teams in [2, 4, 6] {
+        let rounds = round_robin(&teams(number_of_teams), None);
+
+        assert_eq!(rounds.len(), number_of_teams - 1);
+        assert!(rounds.iter().all(|round| round.bye.is_none()));
+        assert_every_pair_meets_once(number_of_teams, &rounds);
+    }
+}
+
+#[test]
+fn odd_numbers_of_teams_give_every_team_one_bye() {
+    for number_of_teams in [3, 5, 7] {
+        let rounds = round_robin(&teams(number_of_teams), None);
+
+        assert_eq!(rounds.len(), number_of_teams);
+        let byes: HashSet<&String> = rounds
+            .iter()
+            .filter_map(|round| round.bye.as_ref())
+            .collect();
+        assert_eq!(byes.len(), number_of_teams);
+        assert_every_pair_meets_once(number_of_teams, &rounds);
+    }
+}
+
+#[test]
+fn rounds_are_numbered_from_the_closest_matches() {
+    let rounds = round_robin(&teams(4), None);
+
+    let numbers: Vec<usize> = rounds.iter().map(|round| round.round).collect();
+    assert_eq!(numbers, [1, 2, 3]);
+    let cost =
+        |round: &RoundContext| -> f32 { round.matches.iter().map(|game| game.sr_difference).sum() };
+    assert!(rounds.windows(2).all(|w| cost(&w[0]) <= cost(&w[1])));
+    // Neighbours in SR meet first
+    assert_eq!(
+        pairs(&rounds[..1]),
+        [
+            ("Team 3".to_string(), "Team 4".to_string()),
+            ("Team 1".to_string(), "Team 2".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn the_number_of_rounds_can_be_limited() {
+    assert_eq!(round_robin(&teams(6), Some(2)).len(), 2);
+}
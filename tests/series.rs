@@ -0,0 +1,114 @@
+mod common;
+
+use autophil::availability::Session;
+use autophil::series::plan_series;
+use autophil::ScrimContext;
+use std::collections::HashSet;
+
+const SESSION: Session = Session {
+    start: None,
+    round_length: 30,
+};
+
+// common::scrim() with a player taken out of the teams and put on the bench
+fn benching(round: usize, name: &str, rating: i16) -> ScrimContext {
+    let mut scrim = common::scrim();
+    for team in scrim.teams.iter_mut() {
+        team.players.retain(|player| player.name != name);
+    }
+    scrim.bench = vec![name.to_string()];
+    // Another id every round, the same scrim is never played twice anyway
+    scrim.id = format!("{}-{}", round, name);
+    scrim.rating = rating;
+    scrim
+}
+
+// common::scrim() with the supports of the teams swapped
+fn swapped_supports(rating: i16) -> ScrimContext {
+    let mut scrim = common::scrim();
+    let (first, second) = scrim.teams.split_at_mut(1);
+    for player in first[0]
+        .players
+        .iter_mut()
+        .chain(second[0].players.iter_mut())
+    {
+        if player.role == "Support" {
+            let swapped = match player.name.as_str() {
+                "Juun" => "Siruker",
+                "Hardsider" => "freaker",
+                "Siruker" => "Juun",
+                _ => "Hardsider",
+            };
+            player.name = swapped.to_string();
+        }
+    }
+    scrim.id = "swapped".to_string();
+    scrim.rating = rating;
+    scrim
+}
+
+fn bench_of(round: &ScrimContext) -> &str {
+    &round.bench[0]
+}
+
+#[test]
+fn nobody_sits_out_twice_before_everyone_sat_out_once() {
+    let roster = common::roster();
+    let names: Vec<String> = roster
+        .players
+        .values()
+        .map(|player| player.name.clone())
+        .collect();
+    // The lower the rating the better, Edgers on the bench makes the best scrim every round
+    let round_candidates: Vec<Vec<ScrimContext>> = (1..=names.len())
+        .map(|round| {
+            let mut candidates: Vec<ScrimContext> = names
+                .iter()
+                .map(|name| benching(round, name, if name == "Edgers" { 0 } else { 50 }))
+                .collect();
+            candidates.sort_by_key(|candidate| candidate.rating);
+            candidates
+        })
+        .collect();
+
+    let rounds = plan_series(&round_candidates, &roster.players, &SESSION);
+
+    assert_eq!(rounds.len(), names.len());
+    assert_eq!(bench_of(&rounds[0]), "Edgers");
+    let benched: HashSet<&str> = rounds.iter().map(bench_of).collect();
+    assert_eq!(benched.len(), names.len());
+    let labels: Vec<&str> = rounds.iter().map(|round| round.label.as_str()).collect();
+    assert_eq!(labels[..3], ["1", "2", "3"]);
+}
+
+#[test]
+fn teammates_rotate_from_round_to_round() {
+    let roster = common::roster();
+    let same_teams = {
+        let mut scrim = common::scrim();
+        scrim.id = "same teams".to_string();
+        scrim.rating = 10;
+        scrim
+    };
+    let round_candidates = vec![
+        vec![common::scrim()],
+        vec![same_teams, swapped_supports(100)],
+    ];
+
+    let rounds = plan_series(&round_candidates, &roster.players, &SESSION);
+
+    // The same teams again would be the better rating but repeat every pair of teammates
+    assert_eq!(rounds[0].id, common::scrim().id);
+    assert_eq!(rounds[1].id, "swapped");
+}
+
+#[test]
+fn a_scrim_is_only_played_once() {
+    let roster = common::roster();
+    let round_candidates = vec![vec![common::scrim()]; 3];
+
+    let rounds = plan_series(&round_candidates, &roster.players, &SESSION);
+
+    // Without another candidate the series ends early
+    assert_eq!(rounds.len(), 1);
+}
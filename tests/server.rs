@@ -0,0 +1,414 @@
+mod common;
+
+use autophil::server::{Api, Server, MAX_FINISHED_JOBS};
+use serde_json::{json, Value};
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+// A server on a free local port, answering on its own thread
+fn start_server() -> (Arc<Server>, SocketAddr) {
+    start_server_with(Api::new())
+}
+
+fn start_server_with(api: Api) -> (Arc<Server>, SocketAddr) {
+    let server = Arc::new(Server::with_api("127.0.0.1:0", api, false).unwrap());
+    let address = server.local_address().unwrap();
+    let running = Arc::clone(&server);
+    thread::spawn(move || running.run());
+    (server, address)
+}
+
+fn request_text(
+    address: SocketAddr,
+    method: &str,
+    path: &str,
+    body: Option<Value>,
+) -> (u16, String) {
+    let host = format!("localhost:{}", address.port());
+    request_with_host(address, &host, method, path, body)
+}
+
+// Sends one request and returns the status and the body of the answer, HTTP/1.0 keeps the
+// answer from being chunked
+fn request_with_host(
+    address: SocketAddr,
+    host: &str,
+    method: &str,
+    path: &str,
+    body: Option<Value>,
+) -> (u16, String) {
+    let body = body.map(|body| body.to_string()).unwrap_or_default();
+    let mut stream = TcpStream::connect(address).unwrap();
+    write!(
+        stream,
+        "{} {} HTTP/1.0\r\nHost: {}\r\nConnection: close\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+        method,
+        path,
+        host,
+        body.len(),
+        body
+    )
+    .unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+
+    let status: u16 = response.split(' ').nth(1).unwrap().parse().unwrap();
+    let (_, body) = response.split_once("\r\n\r\n").unwrap();
+    (status, body.to_string())
+}
+
+fn request(address: SocketAddr, method: &str, path: &str, body: Option<Value>) -> (u16, Value) {
+    let (status, body) = request_text(address, method, path, body);
+    (status, serde_json::from_str(&body).unwrap())
+}
+
+// Polls the job until it isn't running anymore
+fn wait_for_job(address: SocketAddr, id: u64) -> Value {
+    let start = Instant::now();
+    loop {
+        let (status, job) = request(address, "GET", &format!("/jobs/{}", id), None);
+        assert_eq!(status, 200);
+        if job["state"] != "running" {
+            return job;
+        }
+        assert!(
+            start.elapsed() < Duration::from_secs(120),
+            "job {} takes too long",
+            id
+        );
+        thread::sleep(Duration::from_millis(50));
+    }
+}
+
+fn start_job(address: SocketAddr) -> u64 {
+    let (status, body) = request(
+        address,
+        "POST",
+        "/jobs",
+        Some(json!({ "roster": common::ROSTER, "args": [] })),
+    );
+    assert_eq!(status, 202);
+    body["id"].as_u64().unwrap()
+}
+
+#[test]
+fn job_is_searched_and_its_scrims_are_returned() {
+    let (server, address) = start_server();
+
+    let id = start_job(address);
+    let job = wait_for_job(address, id);
+    assert_eq!(job["state"], "done");
+    assert_eq!(job["complete"], true);
+    assert_eq!(job["progress"]["phase"], "Scrims");
+    assert_eq!(job["progress"]["done"], job["progress"]["total"]);
+
+    let (status, result) = request(address, "GET", &format!("/jobs/{}/scrims", id), None);
+    assert_eq!(status, 200);
+    let scrims = result["scrims"].as_array().unwrap();
+    assert!(!scrims.is_empty());
+    assert_eq!(scrims[0]["label"], "A");
+    assert_eq!(job["best_rating"], scrims[0]["rating"]);
+
+    let (status, jobs) = request(address, "GET", "/jobs", None);
+    assert_eq!(status, 200);
+    assert_eq!(jobs[0]["id"], id);
+
+    server.shutdown();
+}
+
+#[test]
+fn scrims_are_explained_and_rebalanced() {
+    let (server, address) = start_server();
+    let id = start_job(address);
+    wait_for_job(address, id);
+
+    let (status, explanation) = request(
+        address,
+        "GET",
+        &format!("/jobs/{}/scrims/A/explain", id),
+        None,
+    );
+    assert_eq!(status, 200);
+    assert_eq!(explanation["label"], "A");
+    assert_eq!(explanation["role_gaps"].as_array().unwrap().len(), 3);
+    assert!(explanation["lines"][0]
+        .as_str()
+        .unwrap()
+        .starts_with("Rating "));
+
+    // Juun leaves, another support takes the spot
+    let (status, body) = request(
+        address,
+        "POST",
+        &format!("/jobs/{}/scrims/A/rebalance", id),
+        Some(json!({ "remove": ["Juun"], "add": ["Newcomer - 0 - 0 - 2400 - s"] })),
+    );
+    assert_eq!(status, 202);
+    let rebalance_id = body["id"].as_u64().unwrap();
+    assert_eq!(wait_for_job(address, rebalance_id)["state"], "done");
+
+    let (_, original) = request(address, "GET", &format!("/jobs/{}/scrims", id), None);
+    let (_, rebalanced) = request(
+        address,
+        "GET",
+        &format!("/jobs/{}/scrims", rebalance_id),
+        None,
+    );
+    let names = |scrim: &Value| -> Vec<String> {
+        scrim["teams"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|team| team["name"].as_str().unwrap().to_string())
+            .collect()
+    };
+    let best = &rebalanced["scrims"][0];
+    let mut original_names = names(&original["scrims"][0]);
+    let mut rebalanced_names = names(best);
+    original_names.sort();
+    rebalanced_names.sort();
+    assert_eq!(rebalanced_names, original_names);
+    let players = best.to_string();
+    assert!(players.contains("Newcomer"));
+    assert!(!players.contains("\"Juun\""));
+
+    server.shutdown();
+}
+
+#[test]
+fn errors_are_answered_in_json() {
+    let (server, address) = start_server();
+
+    let (status, body) = request(address, "GET", "/jobs/42", None);
+    assert_eq!(status, 404);
+    assert!(body["error"].is_string());
+
+    let (status, _) = request(address, "POST", "/jobs", Some(json!({ "args": [] })));
+    assert_eq!(status, 400);
+
+    let (status, _) = request(
+        address,
+        "POST",
+        "/jobs",
+        Some(json!({ "roster": common::ROSTER, "args": ["--no-such-option"] })),
+    );
+    assert_eq!(status, 400);
+
+    // Options that read files or pick a front end can't be given in a request
+    let (status, body) = request(
+        address,
+        "POST",
+        "/jobs",
+        Some(json!({ "roster": common::ROSTER, "args": ["--team-names", "/etc/passwd"] })),
+    );
+    assert_eq!(status, 400);
+    assert_eq!(body["error"], "--team-names is not a search option");
+
+    let (status, _) = request(address, "GET", "/nothing", None);
+    assert_eq!(status, 404);
+
+    server.shutdown();
+}
+
+#[test]
+fn cancelled_job_stops() {
+    let (server, address) = start_server();
+    let id = start_job(address);
+
+    let (status, _) = request(address, "DELETE", &format!("/jobs/{}", id), None);
+    assert_eq!(status, 200);
+    let job = wait_for_job(address, id);
+    // Stopped before a scrim was found, or done with the ones found before
+    if job["state"] == "done" {
+        let (status, _) = request(address, "GET", &format!("/jobs/{}/scrims", id), None);
+        assert_eq!(status, 200);
+    } else {
+        assert_eq!(job["state"], "failed");
+        let (status, _) = request(address, "GET", &format!("/jobs/{}/scrims", id), None);
+        assert_eq!(status, 409);
+    }
+
+    server.shutdown();
+}
+
+// The lineup of a scrim, the way the web UI sends it
+fn lineup_of(scrim: &Value) -> Value {
+    let names = |team: &Value, role: usize| -> Vec<Value> {
+        team["roles"][role]["players"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|player| player["name"].clone())
+            .collect()
+    };
+    let teams: Vec<Value> = scrim["teams"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|team| {
+            json!({
+                "name": team["name"],
+                "tank": names(team, 0),
+                "damage": names(team, 1),
+                "support": names(team, 2),
+            })
+        })
+        .collect();
+    json!({ "label": scrim["label"], "teams": teams, "bench": scrim["bench"] })
+}
+
+#[test]
+fn lineups_are_rated_like_the_search() {
+    let (server, address) = start_server();
+    let id = start_job(address);
+    wait_for_job(address, id);
+    let (_, result) = request(address, "GET", &format!("/jobs/{}/scrims", id), None);
+    let scrim = &result["scrims"][0];
+
+    let mut lineup = lineup_of(scrim);
+    let (status, rated) = request(
+        address,
+        "POST",
+        "/lineups",
+        Some(json!({ "roster": common::ROSTER, "lineup": lineup })),
+    );
+    assert_eq!(status, 200);
+    assert_eq!(rated["scrim"]["rating"], scrim["rating"]);
+    assert_eq!(rated["scrim"]["id"], scrim["id"]);
+    assert!(rated["text"].as_str().unwrap().starts_with("SCRIM A"));
+
+    // Swapping a tank with a damage player of the other team changes the rating
+    let tank = lineup["teams"][0]["tank"][0].clone();
+    lineup["teams"][0]["tank"][0] = lineup["teams"][1]["damage"][0].clone();
+    lineup["teams"][1]["damage"][0] = tank;
+    let (status, swapped) = request(
+        address,
+        "POST",
+        "/lineups",
+        Some(json!({ "roster": common::ROSTER, "lineup": lineup })),
+    );
+    assert_eq!(status, 200);
+    assert_ne!(swapped["scrim"]["id"], scrim["id"]);
+    assert_ne!(swapped["scrim"]["rating"], scrim["rating"]);
+
+    lineup["teams"][0]["tank"][0] = json!("Nobody");
+    let (status, _) = request(
+        address,
+        "POST",
+        "/lineups",
+        Some(json!({ "roster": common::ROSTER, "lineup": lineup })),
+    );
+    assert_eq!(status, 400);
+
+    server.shutdown();
+}
+
+#[test]
+fn web_page_and_players_file_are_served() {
+    let players_file =
+        std::env::temp_dir().join(format!("autophil_web_{}.txt", std::process::id()));
+    std::fs::write(&players_file, common::ROSTER).unwrap();
+    let (server, address) =
+        start_server_with(Api::with_players_file(players_file.to_str().unwrap()));
+
+    let (status, page) = request_text(address, "GET", "/", None);
+    assert_eq!(status, 200);
+    assert!(page.contains("<title>AutoPhil</title>"));
+
+    let (status, roster) = request(address, "GET", "/roster", None);
+    assert_eq!(status, 200);
+    assert_eq!(roster["roster"], common::ROSTER);
+
+    let changed = common::ROSTER.replace("Fool - 1400", "Fool - 1500");
+    let (status, saved) = request(
+        address,
+        "PUT",
+        "/roster",
+        Some(json!({ "roster": changed })),
+    );
+    assert_eq!(status, 200);
+    assert_eq!(saved["players"], 12);
+    assert_eq!(std::fs::read_to_string(&players_file).unwrap(), changed);
+
+    // A roster with a line that isn't a player doesn't overwrite the players file
+    for roster in &[
+        "",
+        "not a player",
+        &format!("{}\nnot a player", common::ROSTER),
+    ] {
+        let (status, _) = request(address, "PUT", "/roster", Some(json!({ "roster": roster })));
+        assert_eq!(status, 400);
+    }
+    assert_eq!(std::fs::read_to_string(&players_file).unwrap(), changed);
+
+    std::fs::remove_file(&players_file).unwrap();
+    server.shutdown();
+}
+
+#[test]
+fn server_only_listens_on_loopback_unless_allowed() {
+    assert!(Server::with_api("0.0.0.0:0", Api::new(), false).is_err());
+    assert!(Server::bind("localhost:0").is_ok());
+
+    let server = Server::with_api("0.0.0.0:0", Api::new(), true).unwrap();
+    server.shutdown();
+}
+
+#[test]
+fn oldest_finished_jobs_are_evicted() {
+    let api = Api::new();
+    // A roster smaller than a team fails right away
+    let body = json!({ "roster": "Edgers - 2900 - 2200 - 2100 - t", "args": [] }).to_string();
+    let jobs = MAX_FINISHED_JOBS as u64 + 1;
+    for id in 1..=jobs {
+        assert_eq!(api.handle("POST", "/jobs", &body).status, 202);
+        let start = Instant::now();
+        while api.handle("GET", &format!("/jobs/{}", id), "").body["state"] == "running" {
+            assert!(start.elapsed() < Duration::from_secs(10));
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    assert_eq!(api.handle("GET", "/jobs/1", "").status, 404);
+    assert_eq!(api.handle("GET", "/jobs/2", "").status, 200);
+    assert_eq!(
+        api.handle("GET", &format!("/jobs/{}", jobs), "").status,
+        200
+    );
+}
+
+#[test]
+fn requests_need_a_local_host() {
+    let (server, address) = start_server();
+    let port = address.port();
+
+    for host in &[
+        format!("127.0.0.1:{}", port),
+        format!("[::1]:{}", port),
+        format!("LOCALHOST:{}", port),
+    ] {
+        let (status, _) = request_with_host(address, host, "GET", "/jobs", None);
+        assert_eq!(status, 200, "{}", host);
+    }
+    // A page that rebinds its own domain to 127.0.0.1 still sends that domain
+    for host in &[
+        format!("attacker.example:{}", port),
+        "localhost:1".to_string(),
+        "localhost".to_string(),
+    ] {
+        let (status, body) = request_with_host(
+            address,
+            host,
+            "PUT",
+            "/roster",
+            Some(json!({ "roster": common::ROSTER })),
+        );
+        assert_eq!(status, 403, "{}", host);
+        assert!(body.contains("localhost"));
+    }
+
+    server.shutdown();
+}
@@ -0,0 +1,119 @@
+mod common;
+
+use autophil::lineup::rate_lineup;
+use autophil::stacks::{get_stacks, stack_rating_change, MAX_STACK_SIZE};
+use autophil::{balance, BalanceConfig, Penalties, Player, SearchProgress};
+use std::collections::HashMap;
+
+fn bonuses(size: usize, bonus: i16) -> [i16; MAX_STACK_SIZE + 1] {
+    let mut bonuses = [0; MAX_STACK_SIZE + 1];
+    bonuses[size] = bonus;
+    bonuses
+}
+
+#[test]
+fn the_bonus_makes_up_for_a_weaker_team() {
+    let players: HashMap<u8, Player> = [
+        (1, "First - 2000 - 0 - 0 - t - stack=duo"),
+        (2, "Second - 2000 - 0 - 0 - t - stack=duo"),
+        (3, "Third - 2100 - 0 - 0 - t"),
+        (4, "Fourth - 2100 - 0 - 0 - t"),
+    ]
+    .iter()
+    .map(|(id, line)| (*id, Player::new(line.to_string()).unwrap()))
+    .collect();
+    assert_eq!(get_stacks(&players), [vec![1, 2]]);
+    let teams = [vec![1, 2], vec![3, 4]];
+    let role_averages = [[2000.0; 3], [2100.0; 3]];
+
+    // Every role was 100 SR apart and is even with the bonus
+    assert_eq!(
+        stack_rating_change(&teams, &role_averages, &players, &bonuses(2, 100)),
+        -1500
+    );
+    // On the stronger team the bonus widens the gap instead
+    let role_averages = [[2100.0; 3], [2000.0; 3]];
+    assert_eq!(
+        stack_rating_change(&teams, &role_averages, &players, &bonuses(2, 100)),
+        1500
+    );
+    // Only stacks of the given size get the bonus
+    assert_eq!(
+        stack_rating_change(&teams, &role_averages, &players, &bonuses(3, 100)),
+        0
+    );
+}
+
+#[test]
+fn the_bonus_is_added_to_the_team_sr() {
+    let roster = common::roster_with(&[("Edgers", "stack=duo"), ("Starboy", "stack=duo")]);
+    let penalties = Penalties {
+        stack_bonuses: bonuses(2, 50),
+        ..Penalties::default()
+    };
+
+    let without_bonus = rate_lineup(&common::lineup(), &roster, Penalties::default()).unwrap();
+    let with_bonus = rate_lineup(&common::lineup(), &roster, penalties).unwrap();
+    assert_eq!(
+        with_bonus.teams[0].average_sr,
+        without_bonus.teams[0].average_sr + 50.0
+    );
+    assert_eq!(
+        with_bonus.teams[1].average_sr,
+        without_bonus.teams[1].average_sr
+    );
+    assert_ne!(with_bonus.rating, without_bonus.rating);
+}
+
+#[test]
+fn stacks_play_on_one_team() {
+    let roster = common::roster_with(&[
+        ("Edgers", "stack=trio"),
+        ("Starboy", "stack=trio"),
+        ("Siruker", "stack=trio"),
+    ]);
+    let config = BalanceConfig {
+        number_of_scrims: 5,
+        number_of_threads: 4,
+        ..BalanceConfig::default()
+    };
+
+    let result = balance(&roster, &config, &SearchProgress::silent()).unwrap();
+    assert!(!result.scrims.is_empty());
+    for scrim in &result.scrims {
+        let team_of = |name: &str| {
+            scrim
+                .teams
+                .iter()
+                .position(|team| team.players.iter().any(|player| player.name == name))
+        };
+        assert_eq!(team_of("Edgers"), team_of("Starboy"));
+        assert_eq!(team_of("Edgers"), team_of("Siruker"));
+    }
+}
+
+#[test]
+fn stacks_larger_than_a_team_are_an_error() {
+    let names = [
+        "Edgers", "TrixCold", "KingBoo", "Fool", "Starboy", "NootNoot", "michealw",
+    ];
+    let fields: Vec<(&str, &str)> = names.iter().map(|name| (*name, "stack=big")).collect();
+    let roster = common::roster_with(&fields);
+
+    let error = balance(
+        &roster,
+        &BalanceConfig::default(),
+        &SearchProgress::silent(),
+    )
+    .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Stack big has 7 players, more than fit on a team of 6"
+    );
+    let ow2 = BalanceConfig {
+        ow_2: true,
+        ..BalanceConfig::default()
+    };
+    let roster = common::roster_with(&fields[..6]);
+    assert!(balance(&roster, &ow2, &SearchProgress::silent()).is_err());
+}
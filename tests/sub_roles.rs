@@ -0,0 +1,94 @@
+mod common;
+
+use autophil::lineup::rate_lineup;
+use autophil::player::duo_sub_roles;
+use autophil::types::SubRole;
+use autophil::{balance, BalanceConfig, Penalties, Position, SearchProgress};
+
+#[test]
+fn duos_take_both_sub_roles_where_they_can() {
+    let flexible = common::player("Flexible - 2500 - 0 - 0 - t - subroles=main_tank,off_tank");
+    let main_tank = common::player("Main - 2500 - 0 - 0 - t - subroles=main_tank");
+    let anyone = common::player("Anyone - 2500 - 0 - 0 - t");
+
+    assert_eq!(
+        duo_sub_roles(&[&flexible, &main_tank], Position::Tank),
+        (vec![Some(SubRole::OffTank), Some(SubRole::MainTank)], false)
+    );
+    // Players without a listed sub-role fill in but aren't shown with one
+    assert_eq!(
+        duo_sub_roles(&[&main_tank, &anyone], Position::Tank),
+        (vec![Some(SubRole::MainTank), None], false)
+    );
+    assert_eq!(
+        duo_sub_roles(&[&main_tank, &main_tank], Position::Tank),
+        (vec![Some(SubRole::MainTank), Some(SubRole::MainTank)], true)
+    );
+}
+
+#[test]
+fn prefer_adds_the_penalty_for_every_clash() {
+    let roster = common::roster_with(&[
+        ("Juun", "subroles=main_support"),
+        ("Hardsider", "subroles=main_support"),
+    ]);
+    let penalties = Penalties {
+        sub_role_clash: 300,
+        ..Penalties::default()
+    };
+
+    let scrim = rate_lineup(&common::lineup(), &roster, penalties).unwrap();
+    assert_eq!(
+        scrim.sub_role_clashes,
+        ["Fighting Foxes: Juun and Hardsider are both Main Support"]
+    );
+    assert_eq!(scrim.rating, common::scrim().rating + 300);
+    let juun = scrim.teams[0]
+        .players
+        .iter()
+        .find(|player| player.name == "Juun")
+        .unwrap();
+    assert_eq!(juun.sub_role.as_deref(), Some("MS"));
+
+    // The search prefers the lineups without the clash
+    let config = BalanceConfig {
+        penalties,
+        ..common::config()
+    };
+    let result = balance(&roster, &config, &SearchProgress::silent()).unwrap();
+    assert!(result.scrims[0].sub_role_clashes.is_empty());
+}
+
+#[test]
+fn require_never_builds_a_clashing_duo() {
+    let roster = common::roster_with(&[
+        ("Juun", "subroles=main_support"),
+        ("Siruker", "subroles=main_support"),
+    ]);
+    let config = BalanceConfig {
+        require_sub_roles: true,
+        ..common::config()
+    };
+
+    let result = balance(&roster, &config, &SearchProgress::silent()).unwrap();
+    assert_eq!(result.scrims.len(), 5);
+    for scrim in &result.scrims {
+        assert!(
+            scrim.sub_role_clashes.is_empty(),
+            "{:?}",
+            scrim.sub_role_clashes
+        );
+        let supports_of = |team: usize| -> Vec<&str> {
+            scrim.teams[team]
+                .players
+                .iter()
+                .filter(|player| player.role == "Support")
+                .map(|player| player.name.as_str())
+                .collect()
+        };
+        for team in 0..scrim.teams.len() {
+            let supports = supports_of(team);
+            assert!(!(supports.contains(&"Juun") && supports.contains(&"Siruker")));
+        }
+    }
+}
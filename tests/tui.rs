@@ -0,0 +1,150 @@
+mod common;
+
+use autophil::lineup::{Lineup, Place};
+use autophil::tui::{Action, Editor, Screen};
+use autophil::{balance, BalanceConfig, Penalties, Position, ScrimContext, SearchProgress};
+use ratatui::crossterm::event::KeyCode;
+
+fn scrims() -> Vec<ScrimContext> {
+    let config = BalanceConfig {
+        number_of_scrims: 3,
+        number_of_threads: 4,
+        ..BalanceConfig::default()
+    };
+    balance(&common::roster(), &config, &SearchProgress::silent())
+        .unwrap()
+        .scrims
+}
+
+fn press(editor: &mut Editor, keys: &[KeyCode]) -> Action {
+    let mut action = Action::Continue;
+    for key in keys {
+        action = editor.handle_key(*key);
+    }
+    action
+}
+
+// Picks the first damage player of the first team and swaps them with the one of the second team
+const SWAP_DAMAGE: [KeyCode; 6] = [
+    KeyCode::Enter,
+    KeyCode::Down,
+    KeyCode::Down,
+    KeyCode::Char(' '),
+    KeyCode::Right,
+    KeyCode::Char(' '),
+];
+
+#[test]
+fn moving_a_player_rates_the_scrim_again() {
+    let scrims = scrims();
+    let original = scrims[0].clone();
+    let mut editor = Editor::new(scrims, common::roster(), Penalties::default()).unwrap();
+
+    assert_eq!(press(&mut editor, &SWAP_DAMAGE), Action::Continue);
+    assert_eq!(editor.screen, Screen::Lineup);
+    let edited = editor.scrim();
+    assert_eq!(edited.label, original.label);
+    assert_ne!(edited.id, original.id);
+    assert_ne!(edited.rating, original.rating);
+
+    // The two damage players changed teams, everyone else stayed
+    let damage = |scrim: &ScrimContext, team: usize| -> Vec<String> {
+        scrim.teams[team].roles[Position::Damage as usize]
+            .players
+            .iter()
+            .map(|player| player.name.clone())
+            .collect()
+    };
+    assert_eq!(damage(edited, 0)[0], damage(&original, 1)[0]);
+    assert_eq!(damage(edited, 1)[0], damage(&original, 0)[0]);
+    assert_eq!(damage(edited, 0)[1], damage(&original, 0)[1]);
+    assert_eq!(
+        edited.teams[0].roles[Position::Tank as usize],
+        original.teams[0].roles[Position::Tank as usize]
+    );
+    assert_ne!(
+        edited.teams[0].roles[Position::Damage as usize].average_sr,
+        original.teams[0].roles[Position::Damage as usize].average_sr
+    );
+}
+
+#[test]
+fn undo_restores_the_scrim() {
+    let scrims = scrims();
+    let original = scrims[0].clone();
+    let mut editor = Editor::new(scrims, common::roster(), Penalties::default()).unwrap();
+
+    press(&mut editor, &SWAP_DAMAGE);
+    press(&mut editor, &[KeyCode::Char('u')]);
+    assert_eq!(editor.scrim().id, original.id);
+    assert_eq!(editor.scrim().rating, original.rating);
+
+    press(&mut editor, &[KeyCode::Char('u')]);
+    assert_eq!(editor.message, "Nothing to undo");
+}
+
+#[test]
+fn quitting_with_unsaved_changes_needs_a_second_q() {
+    let mut editor = Editor::new(scrims(), common::roster(), Penalties::default()).unwrap();
+    press(&mut editor, &SWAP_DAMAGE);
+
+    assert_eq!(press(&mut editor, &[KeyCode::Char('q')]), Action::Continue);
+    assert_eq!(press(&mut editor, &[KeyCode::Char('q')]), Action::Quit);
+
+    assert_eq!(press(&mut editor, &[KeyCode::Char('s')]), Action::Save);
+    editor.saved();
+    assert_eq!(press(&mut editor, &[KeyCode::Char('q')]), Action::Quit);
+}
+
+#[test]
+fn esc_and_ctrl_c_ask_before_quitting_with_unsaved_changes() {
+    let mut editor = Editor::new(scrims(), common::roster(), Penalties::default()).unwrap();
+    assert_eq!(editor.interrupt(), Action::Quit);
+    press(&mut editor, &SWAP_DAMAGE);
+    press(&mut editor, &[KeyCode::Esc]);
+    assert_eq!(editor.screen, Screen::Scrims);
+
+    assert_eq!(press(&mut editor, &[KeyCode::Esc]), Action::Continue);
+    assert!(editor.message.contains("Unsaved changes"));
+    assert_eq!(press(&mut editor, &[KeyCode::Esc]), Action::Quit);
+
+    assert_eq!(editor.interrupt(), Action::Continue);
+    assert_eq!(editor.interrupt(), Action::Quit);
+}
+
+#[test]
+fn esc_drops_the_picked_player_before_leaving_the_lineup() {
+    let mut editor = Editor::new(scrims(), common::roster(), Penalties::default()).unwrap();
+    press(
+        &mut editor,
+        &[KeyCode::Down, KeyCode::Enter, KeyCode::Char(' ')],
+    );
+    assert_eq!(editor.selected, 1);
+    assert_eq!(editor.picked, Some(Place::Team(0, Position::Tank, 0)));
+
+    press(&mut editor, &[KeyCode::Esc]);
+    assert_eq!(editor.picked, None);
+    assert_eq!(editor.screen, Screen::Lineup);
+    press(&mut editor, &[KeyCode::Esc]);
+    assert_eq!(editor.screen, Screen::Scrims);
+}
+
+#[test]
+fn lineup_of_a_scrim_swaps_players() {
+    let scrim = &scrims()[0];
+    let mut lineup = Lineup::from_context(scrim);
+    let tank = lineup.teams[0].tank[0].clone();
+    let support = lineup.teams[1].support[1].clone();
+
+    lineup
+        .swap(
+            Place::Team(0, Position::Tank, 0),
+            Place::Team(1, Position::Support, 1),
+        )
+        .unwrap();
+    assert_eq!(lineup.teams[0].tank[0], support);
+    assert_eq!(lineup.teams[1].support[1], tank);
+    assert!(lineup
+        .swap(Place::Bench(0), Place::Team(0, Position::Tank, 0))
+        .is_err());
+}
@@ -0,0 +1,41 @@
+mod common;
+
+use autophil::wasm::balance_json;
+use serde_json::Value;
+
+#[test]
+fn balance_returns_the_scrims_as_json() {
+    let roster = common::ROSTER.replace(
+        "Siruker - 0 - 0 - 1500 - s",
+        "Ana - 0 - 0 - 1500 - s - heroes=Ana,Bob",
+    );
+    let result: Value =
+        serde_json::from_str(&balance_json(&roster, "--time-limit 60").unwrap()).unwrap();
+
+    let scrims = result["scrims"].as_array().unwrap();
+    assert_eq!(scrims.len(), 10);
+    assert_eq!(scrims[0]["label"], "A");
+    assert_eq!(scrims[0]["teams"].as_array().unwrap().len(), 2);
+    assert_eq!(result["complete"], true);
+    assert_eq!(result["warnings"][0], "Unknown hero Bob in the pool of Ana");
+}
+
+#[test]
+fn balance_errors_are_messages() {
+    assert_eq!(
+        balance_json("# nobody yet", "").unwrap_err().to_string(),
+        "The roster has no players"
+    );
+    assert_eq!(
+        balance_json(common::ROSTER, "--seats 2")
+            .unwrap_err()
+            .to_string(),
+        "--seats is not a search option"
+    );
+    assert_eq!(
+        balance_json(common::ROSTER, "--cards cards")
+            .unwrap_err()
+            .to_string(),
+        "--cards is not a search option"
+    );
+}
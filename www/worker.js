@@ -0,0 +1,13 @@
+// Runs the balancer off the main thread, the search blocks the thread it runs on
+import init, { balance } from "./pkg/autophil.js";
+
+const ready = init();
+
+onmessage = async ({ data }) => {
+  await ready;
+  try {
+    postMessage({ result: JSON.parse(balance(data.roster, data.args)) });
+  } catch (error) {
+    postMessage({ error: error.message });
+  }
+};